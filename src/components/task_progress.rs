@@ -692,7 +692,7 @@ impl Model for TaskProgressView {
             TaskProgressMsg::Cancel => {
                 self.was_cancelled = true;
                 self.should_quit = true;
-                Some(Cmd::quit())
+                Some(Cmd::quit_cancelled())
             },
             TaskProgressMsg::RunStep(index) => {
                 if self.config.external_control
@@ -746,6 +746,7 @@ impl Model for TaskProgressView {
                     self.should_quit = true;
                     if self.phase != Phase::Completed {
                         self.was_cancelled = true;
+                        return Some(Cmd::quit_cancelled());
                     }
                     Some(Cmd::quit())
                 }
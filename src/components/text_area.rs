@@ -12,11 +12,16 @@
 //!     .height(10)
 //!     .width(60);
 //! ```
+//!
+//! As with [`TextInput`](super::TextInput), [`TextArea::native_cursor`]
+//! shows the terminal's own cursor instead of a reverse-video one.
 
 use std::process::Command;
 
+use unicode_width::UnicodeWidthStr;
+
 use crate::{
-    runtime::{Cmd, Model, accessible::Accessible},
+    runtime::{Cmd, Cursor, CursorShape, Frame, Model, accessible::Accessible},
     style::Color,
     terminal::{Event, KeyCode, KeyModifiers},
 };
@@ -108,6 +113,8 @@ pub struct TextArea {
     editor: Option<String>,
     /// File extension for temp file when using external editor.
     editor_extension: String,
+    /// Leave the cursor to the terminal instead of drawing it.
+    native_cursor: bool,
 }
 
 impl Default for TextArea {
@@ -138,6 +145,7 @@ impl TextArea {
             validation_error: None,
             editor: None,
             editor_extension: "txt".to_string(),
+            native_cursor: false,
         }
     }
 
@@ -177,6 +185,15 @@ impl TextArea {
         self
     }
 
+    /// Use the terminal's cursor instead of drawing one in the view.
+    ///
+    /// The view then contains no cursor; place the terminal cursor from
+    /// [`TextArea::cursor`] in the model's [`Model::frame`].
+    pub fn native_cursor(mut self, native: bool) -> Self {
+        self.native_cursor = native;
+        self
+    }
+
     /// Enable or disable line numbers.
     pub fn show_line_numbers(mut self, show: bool) -> Self {
         self.show_line_numbers = show;
@@ -235,6 +252,38 @@ impl TextArea {
         self.cursor
     }
 
+    /// Where the cursor is in the view, or `None` when not focused.
+    ///
+    /// Unlike [`TextArea::cursor_position`], which is a position in the
+    /// text, this is the cell in the rendered view, accounting for
+    /// scrolling, the scroll indicator and line numbers.
+    pub fn cursor(&self) -> Option<Cursor> {
+        if !self.focused {
+            return None;
+        }
+
+        let indicator = usize::from(self.scroll_offset > 0);
+        let row = indicator + self.cursor.row.saturating_sub(self.scroll_offset);
+
+        let gutter = if self.show_line_numbers {
+            // "{num:>width} │ "
+            self.lines.len().to_string().len().max(2) + 3
+        } else {
+            0
+        };
+        let line = self.current_line();
+        let before = &line[..self.col_to_byte_offset(line, self.cursor.col).min(line.len())];
+        let column = gutter + before.width();
+
+        Some(
+            Cursor::new(
+                u16::try_from(column).unwrap_or(u16::MAX),
+                u16::try_from(row).unwrap_or(u16::MAX),
+            )
+            .shape(CursorShape::Bar),
+        )
+    }
+
     /// Get the total number of lines.
     pub fn line_count(&self) -> usize {
         self.lines.len()
@@ -598,7 +647,7 @@ impl TextArea {
             ));
         }
 
-        let is_cursor_line = self.focused && line_idx == self.cursor.row;
+        let is_cursor_line = self.focused && !self.native_cursor && line_idx == self.cursor.row;
 
         if is_cursor_line {
             // Render line with cursor
@@ -752,6 +801,11 @@ impl Model for TextArea {
         output
     }
 
+    fn frame(&self) -> Frame {
+        let cursor = if self.native_cursor { self.cursor() } else { None };
+        Frame::new(self.view()).with_cursor(cursor)
+    }
+
     fn handle_event(&self, event: Event) -> Option<Self::Message> {
         if !self.focused {
             return None;
@@ -1050,4 +1104,22 @@ mod tests {
         assert!(value.contains("First line"));
         assert!(value.contains("Second line"));
     }
+
+    #[test]
+    fn test_native_cursor() {
+        let mut textarea =
+            TextArea::new().value("one\ntwo\nthree").height(2).show_line_numbers(true);
+        textarea = textarea.native_cursor(true);
+
+        // Scrolled to the last line, below the "↑ 1 more lines" indicator;
+        // the gutter is " 3 │ "
+        assert_eq!(textarea.cursor(), Some(Cursor::new(10, 2).shape(CursorShape::Bar)));
+        assert!(!textarea.view().contains("\x1b[7m"));
+
+        textarea.update(TextAreaMsg::CursorUp);
+        assert_eq!(textarea.frame().cursor.map(|cursor| (cursor.column, cursor.row)), Some((8, 1)));
+
+        textarea.update(TextAreaMsg::Blur);
+        assert_eq!(textarea.frame().cursor, None);
+    }
 }
@@ -11,14 +11,21 @@
 //!     .placeholder("Enter your name...")
 //!     .prompt("> ");
 //! ```
+//!
+//! By default the cursor is drawn in reverse video. With
+//! [`TextInput::native_cursor`], the terminal's own cursor is shown at the
+//! insertion point instead, so input methods and screen magnifiers follow it.
+
+use unicode_width::UnicodeWidthStr;
 
 use crate::{
     runtime::{
-        Cmd, Model,
+        Cmd, Cursor, CursorShape, Frame, Model,
         accessible::{Accessible, AccessibleInput},
     },
     style::Color,
     terminal::{Event, KeyCode, KeyModifiers},
+    util::measure_text,
 };
 
 /// Message type for text input.
@@ -69,6 +76,7 @@ pub struct TextInput {
     placeholder_color: Color,
     submitted: bool,
     validation_error: Option<String>,
+    native_cursor: bool,
 }
 
 impl Default for TextInput {
@@ -93,6 +101,7 @@ impl TextInput {
             placeholder_color: Color::BrightBlack,
             submitted: false,
             validation_error: None,
+            native_cursor: false,
         }
     }
 
@@ -133,6 +142,29 @@ impl TextInput {
         self
     }
 
+    /// Use the terminal's cursor instead of drawing one in the view.
+    ///
+    /// The view then contains no cursor; place the terminal cursor from
+    /// [`TextInput::cursor`] in the model's [`Model::frame`].
+    pub fn native_cursor(mut self, native: bool) -> Self {
+        self.native_cursor = native;
+        self
+    }
+
+    /// Where the cursor is in the view, or `None` when not focused.
+    ///
+    /// The position is relative to the start of the prompt.
+    pub fn cursor(&self) -> Option<Cursor> {
+        if !self.focused {
+            return None;
+        }
+
+        let before = &self.value[..self.cursor.min(self.value.len())];
+        let width = if self.hidden { before.chars().count() } else { before.width() };
+        let column = measure_text(&self.prompt) + width;
+        Some(Cursor::new(u16::try_from(column).unwrap_or(u16::MAX), 0).shape(CursorShape::Bar))
+    }
+
     /// Set the text color.
     pub fn text_color(mut self, color: Color) -> Self {
         self.text_color = color;
@@ -293,7 +325,7 @@ impl Model for TextInput {
             output.push_str(&format!(
                 "{}{}{}",
                 self.placeholder_color.to_ansi_fg(),
-                &self.placeholder,
+                self.placeholder,
                 "\x1b[0m"
             ));
         } else {
@@ -304,7 +336,7 @@ impl Model for TextInput {
                 self.value.clone()
             };
 
-            if self.focused {
+            if self.focused && !self.native_cursor {
                 // Show cursor - need character-aware splitting for hidden mode
                 let char_pos = if self.hidden {
                     self.value[..self.cursor].chars().count()
@@ -351,6 +383,11 @@ impl Model for TextInput {
         output
     }
 
+    fn frame(&self) -> Frame {
+        let cursor = if self.native_cursor { self.cursor() } else { None };
+        Frame::new(self.view()).with_cursor(cursor)
+    }
+
     fn handle_event(&self, event: Event) -> Option<Self::Message> {
         if !self.focused {
             return None;
@@ -510,4 +547,19 @@ mod tests {
         let view = input.view();
         assert!(view.contains("•")); // Should show bullets, not the actual text
     }
+
+    #[test]
+    fn test_native_cursor() {
+        let mut input =
+            TextInput::new().prompt("\x1b[1m> \x1b[0m").value("日本").native_cursor(true);
+
+        // Wide characters count as two cells; the prompt's styling counts as none
+        assert_eq!(input.cursor(), Some(Cursor::new(6, 0).shape(CursorShape::Bar)));
+        input.update(TextInputMsg::CursorLeft);
+        assert_eq!(input.frame().cursor.map(|cursor| cursor.column), Some(4));
+        assert!(!input.view().contains("\x1b[7m"));
+
+        input.update(TextInputMsg::Blur);
+        assert_eq!(input.frame().cursor, None);
+    }
 }
@@ -245,13 +245,10 @@ impl Model for Form {
             FormMsg::Group(idx, group_msg) => {
                 // Update the group and capture state before releasing borrow
                 let (result, is_complete, is_cancelled) = {
-                    if let Some(group) = self.groups.get_mut(idx) {
-                        let result =
-                            group.update(group_msg).map(|c| c.map(move |m| FormMsg::Group(idx, m)));
-                        (result, group.is_complete(), group.is_cancelled())
-                    } else {
-                        return None;
-                    }
+                    let group = self.groups.get_mut(idx)?;
+                    let result =
+                        group.update(group_msg).map(|c| c.map(move |m| FormMsg::Group(idx, m)));
+                    (result, group.is_complete(), group.is_cancelled())
                 };
 
                 // Now handle state changes without borrow conflicts
@@ -285,7 +282,7 @@ impl Model for Form {
             },
             FormMsg::Cancel => {
                 self.cancelled = true;
-                Some(Cmd::quit())
+                Some(Cmd::quit_cancelled())
             },
         }
     }
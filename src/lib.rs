@@ -108,6 +108,8 @@
 //!
 //! The framework automatically detects non-interactive environments and
 //! adjusts behavior accordingly (no animations, no prompts, clear errors).
+//! Programs still run their full update loop without a terminal, writing
+//! the view as plain text; see [`runtime::NonInteractiveOutput`].
 //!
 //! ## Accessible Mode
 //!
@@ -131,6 +133,9 @@
 //! ```
 //!
 //! Components implement the [`Accessible`] trait for custom accessible handling.
+//! Any model implementing it can be run as a whole application in accessible
+//! mode with [`Program::with_accessible_model`], and other models can supply
+//! a [`runtime::AccessibleAdapter`].
 
 pub mod components;
 pub mod forms;
@@ -138,6 +143,7 @@ pub mod output;
 pub mod runtime;
 pub mod style;
 pub mod terminal;
+pub mod testing;
 pub mod util;
 
 // Re-export core types at crate root
@@ -151,7 +157,10 @@ pub use components::{
 pub use forms::{Form, Group};
 // Re-export cmd module for Bubble Tea-style function calls
 pub use runtime::cmd;
-pub use runtime::{Accessible, AccessibleInput, Cmd, Model, Program, ProgramOptions, Sub};
+pub use runtime::{
+    Accessible, AccessibleInput, Cmd, Frame, Model, Program, ProgramError, ProgramHandle,
+    ProgramOptions, Signal, Sub,
+};
 // Re-export ANSI constants for convenience
 pub use style::{
     BLINK, BOLD, CLEAR_LINE, CR, CURSOR_UP, DIM, HIDDEN, ITALIC, RESET, REVERSE, STRIKETHROUGH,
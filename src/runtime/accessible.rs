@@ -28,6 +28,8 @@
 
 use std::io::{self, BufRead, Write};
 
+use super::Model;
+
 /// Result of parsing accessible input.
 #[derive(Debug, Clone)]
 pub enum AccessibleInput {
@@ -163,6 +165,110 @@ pub trait Accessible {
     fn is_accessible_complete(&self) -> bool;
 }
 
+/// Builds a message from a line of accessible input.
+type ParseFn<M> = Box<dyn Fn(&M, &str) -> Option<<M as Model>::Message> + Send>;
+
+/// Drives a [`Model`] in accessible mode.
+///
+/// When accessible mode is on, a [`Program`](super::Program) with an adapter
+/// runs a line-based loop instead of drawing the TUI: it prints the prompt,
+/// reads a line from stdin, turns it into a message for `update`, and
+/// repeats until the model is complete or quits. Commands, timers and
+/// subscriptions run as usual between prompts.
+///
+/// Models implementing [`Accessible`] can use
+/// [`Program::with_accessible_model`](super::Program::with_accessible_model);
+/// other models describe their prompts with closures.
+///
+/// # Example
+///
+/// ```rust
+/// use teapot::{Cmd, Model};
+/// use teapot::runtime::AccessibleAdapter;
+///
+/// struct Counter { count: i32 }
+///
+/// enum Msg { Add(i32), Quit }
+///
+/// impl Model for Counter {
+///     type Message = Msg;
+///     fn init(&self) -> Option<Cmd<Msg>> { None }
+///     fn update(&mut self, msg: Msg) -> Option<Cmd<Msg>> {
+///         match msg {
+///             Msg::Add(n) => { self.count += n; None },
+///             Msg::Quit => Some(Cmd::quit()),
+///         }
+///     }
+///     fn view(&self) -> String { format!("Count: {}", self.count) }
+/// }
+///
+/// let adapter = AccessibleAdapter::new(
+///     |counter: &Counter| format!("Count is {}. Enter a number to add, or q to quit.\n> ", counter.count),
+///     |_, input| match input.trim() {
+///         "q" => Some(Msg::Quit),
+///         n => n.parse().ok().map(Msg::Add),
+///     },
+/// );
+/// ```
+pub struct AccessibleAdapter<M: Model> {
+    prompt: Box<dyn Fn(&M) -> String + Send>,
+    parse: ParseFn<M>,
+    complete: Box<dyn Fn(&M) -> bool + Send>,
+}
+
+impl<M: Model> AccessibleAdapter<M> {
+    /// Create an adapter from a prompt and an input parser.
+    ///
+    /// Input the parser rejects with `None` is reported as invalid and the
+    /// prompt is shown again.
+    pub fn new(
+        prompt: impl Fn(&M) -> String + Send + 'static,
+        parse: impl Fn(&M, &str) -> Option<M::Message> + Send + 'static,
+    ) -> Self {
+        Self { prompt: Box::new(prompt), parse: Box::new(parse), complete: Box::new(|_| false) }
+    }
+
+    /// Stop prompting once `complete` returns true.
+    ///
+    /// Without it, the loop runs until the model quits or input ends.
+    pub fn complete_when(mut self, complete: impl Fn(&M) -> bool + Send + 'static) -> Self {
+        self.complete = Box::new(complete);
+        self
+    }
+
+    /// The prompt for the model's current state.
+    pub(crate) fn prompt(&self, model: &M) -> String {
+        (self.prompt)(model)
+    }
+
+    /// Turn a line of input into a message.
+    pub(crate) fn parse(&self, model: &M, input: &str) -> Option<M::Message> {
+        (self.parse)(model, input)
+    }
+
+    /// Whether the model needs no more input.
+    pub(crate) fn is_complete(&self, model: &M) -> bool {
+        (self.complete)(model)
+    }
+}
+
+impl<M> AccessibleAdapter<M>
+where
+    M: Model + Accessible<Message = <M as Model>::Message> + 'static,
+{
+    /// Create an adapter from the model's [`Accessible`] implementation.
+    pub fn from_model() -> Self {
+        Self::new(M::accessible_prompt, M::parse_accessible_input)
+            .complete_when(M::is_accessible_complete)
+    }
+}
+
+impl<M: Model> std::fmt::Debug for AccessibleAdapter<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AccessibleAdapter").finish_non_exhaustive()
+    }
+}
+
 /// Read a line of input from stdin.
 pub fn read_line() -> io::Result<String> {
     let stdin = io::stdin();
@@ -7,9 +7,26 @@ use std::{
     future::Future,
     pin::Pin,
     process::Command as ProcessCommand,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
     time::{Duration, Instant},
 };
 
+use super::{
+    control::TerminalControl,
+    error::BoxError,
+    outcome::Exit,
+    process::{EventFn, ProcessEvent},
+};
+
+/// A blocking command body, given the command's cancellation token.
+pub(crate) type BlockingFn<M> = Box<dyn FnOnce(&CancelToken) -> Result<M, BoxError> + Send>;
+
+/// An async command body.
+pub(crate) type AsyncFn<M> = Pin<Box<dyn Future<Output = Result<M, BoxError>> + Send>>;
+
 /// A command representing an IO operation that produces a message.
 ///
 /// Commands are returned from `Model::init` and `Model::update` to request
@@ -41,9 +58,9 @@ enum CmdInner<M> {
     /// No-op command
     None,
     /// Quit the program
-    Quit,
+    Quit(Exit),
     /// A synchronous action that produces a message
-    Sync(Box<dyn FnOnce() -> M + Send>),
+    Sync(BlockingFn<M>),
     /// A tick timer
     Tick { duration: Duration, msg_fn: Box<dyn Fn(Instant) -> M + Send> },
     /// Batch of commands to run concurrently
@@ -51,12 +68,58 @@ enum CmdInner<M> {
     /// Sequence of commands to run in order
     Sequence(Vec<Cmd<M>>),
     /// An async action
-    Async(Pin<Box<dyn Future<Output = M> + Send>>),
+    Async(AsyncFn<M>),
     /// Run an external process (suspends the TUI)
     RunProcess {
         command: ProcessCommand,
         on_exit: Box<dyn FnOnce(std::io::Result<std::process::ExitStatus>) -> M + Send>,
     },
+    /// Run an external process in the background, streaming its output
+    SpawnProcess { command: ProcessCommand, on_event: EventFn<M> },
+    /// Print a permanent line above the view
+    Println(String),
+    /// Send a control sequence to the terminal
+    Terminal(TerminalControl),
+    /// A command that replaces earlier work under the same key
+    Keyed { key: String, cmd: Box<Cmd<M>> },
+    /// Cancel the work running under a key
+    Cancel(String),
+    /// Drop the command debounced under a key
+    CancelDebounce(String),
+    /// Stop the program with an error
+    Fail(BoxError),
+    /// Run a command once no other is debounced under the key for a while
+    Debounce { key: String, duration: Duration, cmd: Box<Cmd<M>> },
+    /// Run a command unless another ran under the key recently
+    Throttle { key: String, duration: Duration, cmd: Box<Cmd<M>> },
+}
+
+/// Tells a running command that its result is no longer wanted.
+///
+/// Commands started with [`Cmd::keyed`] are cancelled when another command
+/// is issued under the same key, or by [`Cmd::cancel`]. Async commands and
+/// background processes are stopped outright; blocking closures cannot be
+/// interrupted, but those created with [`Cmd::perform_cancellable`] can
+/// check the token and stop early. Either way, a cancelled command's
+/// message is discarded.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// Whether the command was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+
+    /// Mark the command as cancelled.
+    pub(crate) fn cancel(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+
+    /// Whether other clones of this token are still alive.
+    pub(crate) fn is_shared(&self) -> bool {
+        Arc::strong_count(&self.0) > 1
+    }
 }
 
 impl<M> Cmd<M> {
@@ -83,7 +146,39 @@ impl<M> Cmd<M> {
     /// ```
     #[inline]
     pub fn quit() -> Self {
-        Self { inner: CmdInner::Quit }
+        Self { inner: CmdInner::Quit(Exit::Quit) }
+    }
+
+    /// Create a command to quit the program with a result.
+    ///
+    /// [`Program::run_with_outcome`] returns the value as
+    /// [`Outcome::Completed`], for example the item the user picked.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use teapot::Cmd;
+    ///
+    /// enum Msg { Pick }
+    ///
+    /// let cmd: Cmd<Msg> = Cmd::quit_with(String::from("main"));
+    /// ```
+    ///
+    /// [`Program::run_with_outcome`]: super::Program::run_with_outcome
+    /// [`Outcome::Completed`]: super::Outcome::Completed
+    pub fn quit_with<T: Send + 'static>(value: T) -> Self {
+        Self { inner: CmdInner::Quit(Exit::Value(Box::new(value))) }
+    }
+
+    /// Create a command to quit the program because the user cancelled.
+    ///
+    /// [`Program::run_with_outcome`] reports it as [`Outcome::Cancelled`].
+    ///
+    /// [`Program::run_with_outcome`]: super::Program::run_with_outcome
+    /// [`Outcome::Cancelled`]: super::Outcome::Cancelled
+    #[inline]
+    pub fn quit_cancelled() -> Self {
+        Self { inner: CmdInner::Quit(Exit::Cancelled) }
     }
 
     /// Create a tick command that fires after a duration.
@@ -111,6 +206,10 @@ impl<M> Cmd<M> {
     /// Create a command from a synchronous function.
     ///
     /// The function is called once and its return value becomes the message.
+    /// It runs on a background worker thread, so it may block (file I/O,
+    /// blocking HTTP clients) without freezing rendering or input. A new
+    /// worker is started when all existing ones are busy, so blocking
+    /// commands never wait for each other.
     ///
     /// # Example
     ///
@@ -125,13 +224,76 @@ impl<M> Cmd<M> {
     where
         F: FnOnce() -> M + Send + 'static,
     {
-        Self { inner: CmdInner::Sync(Box::new(f)) }
+        Self::perform_cancellable(move |_| f())
+    }
+
+    /// Create a command from a synchronous function that can stop early.
+    ///
+    /// Like [`Cmd::perform`], but the function receives the command's
+    /// [`CancelToken`]. Long-running work should check it periodically when
+    /// the command is [keyed](Cmd::keyed); its message is discarded anyway
+    /// once cancelled.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use teapot::Cmd;
+    ///
+    /// enum Msg { Indexed(usize) }
+    ///
+    /// let cmd: Cmd<Msg> = Cmd::perform_cancellable(|token| {
+    ///     let mut indexed = 0;
+    ///     while indexed < 1000 && !token.is_cancelled() {
+    ///         indexed += 1;
+    ///     }
+    ///     Msg::Indexed(indexed)
+    /// })
+    /// .keyed("index");
+    /// ```
+    pub fn perform_cancellable<F>(f: F) -> Self
+    where
+        F: FnOnce(&CancelToken) -> M + Send + 'static,
+    {
+        Self { inner: CmdInner::Sync(Box::new(move |token| Ok(f(token)))) }
+    }
+
+    /// Create a command from a synchronous function that may fail.
+    ///
+    /// Like [`Cmd::perform`], but an `Err` stops the program, which returns
+    /// it from `run()` as [`ProgramError::User`](super::ProgramError::User).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use teapot::Cmd;
+    ///
+    /// enum Msg { Config(String) }
+    ///
+    /// let cmd: Cmd<Msg> =
+    ///     Cmd::try_perform(|| std::fs::read_to_string("app.toml").map(Msg::Config));
+    /// ```
+    pub fn try_perform<F, E>(f: F) -> Self
+    where
+        F: FnOnce() -> Result<M, E> + Send + 'static,
+        E: Into<BoxError>,
+    {
+        Self { inner: CmdInner::Sync(Box::new(move |_| f().map_err(Into::into))) }
     }
 
     /// Create a command from an async future.
     ///
     /// The future is executed and its result becomes the message.
     ///
+    /// Where the future runs depends on how the program was started:
+    ///
+    /// - Under [`Program::run`], futures run on a small built-in executor thread, so they should
+    ///   not rely on a specific async runtime's reactor (such as tokio timers or sockets). Channel
+    ///   receivers and runtime-agnostic futures work fine.
+    /// - Under `Program::run_async` (the `tokio` feature), futures are spawned on the caller's
+    ///   tokio runtime and may use its timers, I/O and other tokio APIs.
+    ///
+    /// [`Program::run`]: super::Program::run
+    ///
     /// # Example
     ///
     /// ```rust,ignore
@@ -150,7 +312,39 @@ impl<M> Cmd<M> {
     where
         F: Future<Output = M> + Send + 'static,
     {
-        Self { inner: CmdInner::Async(Box::pin(future)) }
+        Self { inner: CmdInner::Async(Box::pin(async move { Ok(future.await) })) }
+    }
+
+    /// Create a command from an async future that may fail.
+    ///
+    /// Like [`Cmd::perform_async`], but an `Err` stops the program, which
+    /// returns it from `run()` as
+    /// [`ProgramError::User`](super::ProgramError::User).
+    pub fn try_perform_async<F, E>(future: F) -> Self
+    where
+        F: Future<Output = Result<M, E>> + Send + 'static,
+        E: Into<BoxError>,
+    {
+        Self { inner: CmdInner::Async(Box::pin(async move { future.await.map_err(Into::into) })) }
+    }
+
+    /// Stop the program with an error.
+    ///
+    /// The terminal is restored and `run()` returns the error as
+    /// [`ProgramError::User`](super::ProgramError::User), so `update` can
+    /// fail the program instead of stashing the error in the model.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use teapot::Cmd;
+    ///
+    /// enum Msg { Done }
+    ///
+    /// let cmd: Cmd<Msg> = Cmd::fail("lost connection to the server");
+    /// ```
+    pub fn fail(err: impl Into<BoxError>) -> Self {
+        Self { inner: CmdInner::Fail(err.into()) }
     }
 
     /// Run an external process, suspending the TUI.
@@ -183,9 +377,138 @@ impl<M> Cmd<M> {
         Self { inner: CmdInner::RunProcess { command, on_exit: Box::new(on_exit) } }
     }
 
+    /// Run an external process in the background without suspending the TUI.
+    ///
+    /// The process's stdin, stdout and stderr are piped. Each line it writes
+    /// to stdout or stderr is delivered through `on_event`, between a
+    /// [`ProcessEvent::Started`] event carrying a [`ProcessHandle`] (to
+    /// write to stdin or kill the process) and a final
+    /// [`ProcessEvent::Exited`] event. In a [`Cmd::sequence`], the next
+    /// command runs once the process has exited.
+    ///
+    /// Processes still running when the program exits are killed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::process::Command;
+    /// use teapot::{Cmd, runtime::ProcessEvent};
+    ///
+    /// enum Msg { Build(ProcessEvent) }
+    ///
+    /// let mut cargo = Command::new("cargo");
+    /// cargo.arg("build");
+    /// let cmd: Cmd<Msg> = Cmd::spawn_process(cargo, Msg::Build);
+    /// ```
+    ///
+    /// [`ProcessEvent::Started`]: super::ProcessEvent::Started
+    /// [`ProcessEvent::Exited`]: super::ProcessEvent::Exited
+    /// [`ProcessHandle`]: super::ProcessHandle
+    pub fn spawn_process<F>(command: ProcessCommand, on_event: F) -> Self
+    where
+        F: Fn(ProcessEvent) -> M + Send + Sync + 'static,
+    {
+        Self { inner: CmdInner::SpawnProcess { command, on_event: Arc::new(on_event) } }
+    }
+
+    /// Create a command that prints text above the program's view.
+    ///
+    /// In inline mode the text is written above the live view and scrolls
+    /// up into the terminal's history, while the view stays at the bottom.
+    /// Use it to log completed work alongside a spinner or progress bar.
+    /// Multi-line text prints one line per `\n`.
+    ///
+    /// The text is ignored in alternate screen mode, which has no history.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use teapot::Cmd;
+    ///
+    /// enum Msg { Built(String) }
+    ///
+    /// let name = "foo";
+    /// let cmd: Cmd<Msg> = Cmd::println(format!("✓ built {}", name));
+    /// ```
+    pub fn println(text: impl Into<String>) -> Self {
+        Self { inner: CmdInner::Println(text.into()) }
+    }
+
+    /// Create a command that sets the terminal window title.
+    ///
+    /// Like the other terminal control commands, this does nothing when the
+    /// program is not running interactively (in accessible mode, without a
+    /// terminal, or in tests). A title set with [`Frame::title`] replaces
+    /// this one the next time it changes.
+    ///
+    /// [`Frame::title`]: super::Frame::title
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use teapot::Cmd;
+    ///
+    /// enum Msg { Opened }
+    ///
+    /// let cmd: Cmd<Msg> = Cmd::set_title("notes.md - editor");
+    /// ```
+    pub fn set_title(title: impl Into<String>) -> Self {
+        Self { inner: CmdInner::Terminal(TerminalControl::SetTitle(title.into())) }
+    }
+
+    /// Create a command that copies text to the system clipboard.
+    ///
+    /// Uses the OSC 52 escape sequence, so it also works over SSH. Some
+    /// terminals disable clipboard access by default or limit its size.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use teapot::Cmd;
+    ///
+    /// enum Msg { Copied }
+    ///
+    /// let row = vec!["api", "running", "3"];
+    /// let cmd: Cmd<Msg> = Cmd::copy_to_clipboard(row.join("\t"));
+    /// ```
+    pub fn copy_to_clipboard(text: impl Into<String>) -> Self {
+        Self { inner: CmdInner::Terminal(TerminalControl::Copy(text.into())) }
+    }
+
+    /// Create a command that raises a desktop notification.
+    ///
+    /// Uses OSC 777 on VTE-based terminals, foot and urxvt, and OSC 9
+    /// elsewhere (iTerm2, WezTerm, Windows Terminal). Terminals without
+    /// notification support ignore it.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use teapot::Cmd;
+    ///
+    /// enum Msg { Finished }
+    ///
+    /// let cmd: Cmd<Msg> = Cmd::notify("Deploy", "All 12 tasks completed");
+    /// ```
+    pub fn notify(title: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            inner: CmdInner::Terminal(TerminalControl::Notify {
+                title: title.into(),
+                body: body.into(),
+            }),
+        }
+    }
+
+    /// Create a command that rings the terminal bell.
+    pub fn bell() -> Self {
+        Self { inner: CmdInner::Terminal(TerminalControl::Bell) }
+    }
+
     /// Batch multiple commands to run concurrently.
     ///
-    /// All commands execute simultaneously with no ordering guarantees.
+    /// All commands execute simultaneously with no ordering guarantees:
+    /// blocking and async commands in the batch are dispatched to background
+    /// workers together and their messages arrive as each completes.
     ///
     /// # Example
     ///
@@ -205,7 +528,8 @@ impl<M> Cmd<M> {
 
     /// Sequence commands to run in order.
     ///
-    /// Each command completes before the next starts.
+    /// Each command completes, and its message is delivered to `update`,
+    /// before the next starts.
     ///
     /// # Example
     ///
@@ -223,6 +547,108 @@ impl<M> Cmd<M> {
         Self { inner: CmdInner::Sequence(cmds) }
     }
 
+    /// Run this command under a key, replacing earlier work with the same key.
+    ///
+    /// When a keyed command is issued, everything still running under the
+    /// same key is cancelled: async futures are dropped, background
+    /// processes killed, pending ticks removed and the rest of a sequence
+    /// skipped. Messages from cancelled work that already finished are
+    /// discarded, so stale results never reach `update` out of order.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// // Each keystroke starts a new search; only the latest result arrives
+    /// Msg::QueryChanged(query) => {
+    ///     self.query = query.clone();
+    ///     Some(Cmd::perform_async(search(query)).keyed("search"))
+    /// }
+    /// ```
+    pub fn keyed(self, key: impl Into<String>) -> Self {
+        Self { inner: CmdInner::Keyed { key: key.into(), cmd: Box::new(self) } }
+    }
+
+    /// Cancel the work running under a key.
+    ///
+    /// See [`Cmd::keyed`]. Cancelling a key with nothing running does nothing.
+    /// Keys of [`Cmd::debounce`] are separate; use [`Cmd::cancel_debounce`]
+    /// for those.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use teapot::Cmd;
+    ///
+    /// enum Msg { Results(Vec<String>) }
+    ///
+    /// let cmd: Cmd<Msg> = Cmd::cancel("search");
+    /// ```
+    pub fn cancel(key: impl Into<String>) -> Self {
+        Self { inner: CmdInner::Cancel(key.into()) }
+    }
+
+    /// Drop the command waiting under a [`Cmd::debounce`] key, if any.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use teapot::Cmd;
+    ///
+    /// enum Msg { Lookup(String) }
+    ///
+    /// let cmd: Cmd<Msg> = Cmd::cancel_debounce("lookup");
+    /// ```
+    pub fn cancel_debounce(key: impl Into<String>) -> Self {
+        Self { inner: CmdInner::CancelDebounce(key.into()) }
+    }
+
+    /// Run a command once the key has been quiet for `duration`.
+    ///
+    /// Each debounced command replaces the one waiting under the same key
+    /// and restarts the wait, so only the last command of a burst runs. Use
+    /// it for live filtering or lookups while the user types. A waiting
+    /// command can be dropped with [`Cmd::cancel_debounce`]. Debounce keys
+    /// are separate from the keys of [`Cmd::keyed`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use teapot::Cmd;
+    ///
+    /// enum Msg { Lookup(String) }
+    ///
+    /// let query = "tea".to_string();
+    /// let cmd: Cmd<Msg> = Cmd::debounce(
+    ///     "lookup",
+    ///     Duration::from_millis(300),
+    ///     Cmd::perform(move || Msg::Lookup(query)),
+    /// );
+    /// ```
+    pub fn debounce(key: impl Into<String>, duration: Duration, cmd: Cmd<M>) -> Self {
+        Self { inner: CmdInner::Debounce { key: key.into(), duration, cmd: Box::new(cmd) } }
+    }
+
+    /// Run a command at most once per `duration` for the key.
+    ///
+    /// The first command runs immediately; commands issued under the same
+    /// key during the following `duration` are dropped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// use teapot::Cmd;
+    ///
+    /// enum Msg { Saved }
+    ///
+    /// let cmd: Cmd<Msg> =
+    ///     Cmd::throttle("autosave", Duration::from_secs(5), Cmd::perform(|| Msg::Saved));
+    /// ```
+    pub fn throttle(key: impl Into<String>, duration: Duration, cmd: Cmd<M>) -> Self {
+        Self { inner: CmdInner::Throttle { key: key.into(), duration, cmd: Box::new(cmd) } }
+    }
+
     /// Transform the message type of this command.
     ///
     /// This is useful for composing commands from child components.
@@ -246,10 +672,10 @@ impl<M> Cmd<M> {
     {
         match self.inner {
             CmdInner::None => Cmd::none(),
-            CmdInner::Quit => Cmd::quit(),
+            CmdInner::Quit(exit) => Cmd { inner: CmdInner::Quit(exit) },
             CmdInner::Sync(action) => {
                 let f = f.clone();
-                Cmd::perform(move || f(action()))
+                Cmd { inner: CmdInner::Sync(Box::new(move |token| action(token).map(f))) }
             },
             CmdInner::Tick { duration, msg_fn } => {
                 Cmd::tick(duration, move |instant| f(msg_fn(instant)))
@@ -262,18 +688,29 @@ impl<M> Cmd<M> {
             },
             CmdInner::Async(future) => {
                 let f = f.clone();
-                Cmd::perform_async(async move { f(future.await) })
+                Cmd { inner: CmdInner::Async(Box::pin(async move { future.await.map(f) })) }
             },
             CmdInner::RunProcess { command, on_exit } => {
                 let f = f.clone();
                 Cmd::run_process(command, move |result| f(on_exit(result)))
             },
+            CmdInner::SpawnProcess { command, on_event } => {
+                Cmd::spawn_process(command, move |event| f(on_event(event)))
+            },
+            CmdInner::Println(text) => Cmd::println(text),
+            CmdInner::Terminal(control) => Cmd { inner: CmdInner::Terminal(control) },
+            CmdInner::Keyed { key, cmd } => cmd.map(f).keyed(key),
+            CmdInner::Cancel(key) => Cmd::cancel(key),
+            CmdInner::CancelDebounce(key) => Cmd::cancel_debounce(key),
+            CmdInner::Fail(err) => Cmd { inner: CmdInner::Fail(err) },
+            CmdInner::Debounce { key, duration, cmd } => Cmd::debounce(key, duration, cmd.map(f)),
+            CmdInner::Throttle { key, duration, cmd } => Cmd::throttle(key, duration, cmd.map(f)),
         }
     }
 
     /// Check if this is a quit command.
     pub(crate) fn is_quit(&self) -> bool {
-        matches!(self.inner, CmdInner::Quit)
+        matches!(self.inner, CmdInner::Quit(_))
     }
 
     /// Check if this is a no-op command.
@@ -287,13 +724,28 @@ impl<M> Cmd<M> {
     pub(crate) fn execute(self) -> CmdResult<M> {
         match self.inner {
             CmdInner::None => CmdResult::None,
-            CmdInner::Quit => CmdResult::Quit,
-            CmdInner::Sync(f) => CmdResult::Message(f()),
+            CmdInner::Quit(exit) => CmdResult::Quit(exit),
+            CmdInner::Sync(f) => CmdResult::Perform(f),
             CmdInner::Tick { duration, msg_fn } => CmdResult::Tick { duration, msg_fn },
             CmdInner::Batch(cmds) => CmdResult::Batch(cmds),
             CmdInner::Sequence(cmds) => CmdResult::Sequence(cmds),
             CmdInner::Async(future) => CmdResult::Async(future),
             CmdInner::RunProcess { command, on_exit } => CmdResult::RunProcess { command, on_exit },
+            CmdInner::SpawnProcess { command, on_event } => {
+                CmdResult::SpawnProcess { command, on_event }
+            },
+            CmdInner::Println(text) => CmdResult::Println(text),
+            CmdInner::Terminal(control) => CmdResult::Terminal(control),
+            CmdInner::Keyed { key, cmd } => CmdResult::Keyed { key, cmd: *cmd },
+            CmdInner::Cancel(key) => CmdResult::Cancel(key),
+            CmdInner::CancelDebounce(key) => CmdResult::CancelDebounce(key),
+            CmdInner::Fail(err) => CmdResult::Fail(err),
+            CmdInner::Debounce { key, duration, cmd } => {
+                CmdResult::Debounce { key, duration, cmd: *cmd }
+            },
+            CmdInner::Throttle { key, duration, cmd } => {
+                CmdResult::Throttle { key, duration, cmd: *cmd }
+            },
         }
     }
 }
@@ -308,13 +760,28 @@ impl<M> std::fmt::Debug for Cmd<M> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match &self.inner {
             CmdInner::None => write!(f, "Cmd::None"),
-            CmdInner::Quit => write!(f, "Cmd::Quit"),
+            CmdInner::Quit(_) => write!(f, "Cmd::Quit"),
             CmdInner::Sync(_) => write!(f, "Cmd::Sync(...)"),
             CmdInner::Tick { duration, .. } => write!(f, "Cmd::Tick({:?})", duration),
             CmdInner::Batch(cmds) => write!(f, "Cmd::Batch({} cmds)", cmds.len()),
             CmdInner::Sequence(cmds) => write!(f, "Cmd::Sequence({} cmds)", cmds.len()),
             CmdInner::Async(_) => write!(f, "Cmd::Async(...)"),
             CmdInner::RunProcess { .. } => write!(f, "Cmd::RunProcess(...)"),
+            CmdInner::SpawnProcess { command, .. } => {
+                write!(f, "Cmd::SpawnProcess({:?})", command.get_program())
+            },
+            CmdInner::Println(text) => write!(f, "Cmd::Println({:?})", text),
+            CmdInner::Terminal(control) => write!(f, "Cmd::Terminal({:?})", control),
+            CmdInner::Keyed { key, cmd } => write!(f, "Cmd::Keyed({:?}, {:?})", key, cmd),
+            CmdInner::Cancel(key) => write!(f, "Cmd::Cancel({:?})", key),
+            CmdInner::CancelDebounce(key) => write!(f, "Cmd::CancelDebounce({:?})", key),
+            CmdInner::Fail(err) => write!(f, "Cmd::Fail({})", err),
+            CmdInner::Debounce { key, duration, cmd } => {
+                write!(f, "Cmd::Debounce({:?}, {:?}, {:?})", key, duration, cmd)
+            },
+            CmdInner::Throttle { key, duration, cmd } => {
+                write!(f, "Cmd::Throttle({:?}, {:?}, {:?})", key, duration, cmd)
+            },
         }
     }
 }
@@ -322,19 +789,42 @@ impl<M> std::fmt::Debug for Cmd<M> {
 /// Internal result of executing a command.
 pub(crate) enum CmdResult<M> {
     None,
-    Quit,
-    Message(M),
+    Quit(Exit),
+    Perform(BlockingFn<M>),
     Tick {
         duration: Duration,
         msg_fn: Box<dyn Fn(Instant) -> M + Send>,
     },
     Batch(Vec<Cmd<M>>),
     Sequence(Vec<Cmd<M>>),
-    Async(Pin<Box<dyn Future<Output = M> + Send>>),
+    Async(AsyncFn<M>),
     RunProcess {
         command: ProcessCommand,
         on_exit: Box<dyn FnOnce(std::io::Result<std::process::ExitStatus>) -> M + Send>,
     },
+    SpawnProcess {
+        command: ProcessCommand,
+        on_event: EventFn<M>,
+    },
+    Println(String),
+    Terminal(TerminalControl),
+    Keyed {
+        key: String,
+        cmd: Cmd<M>,
+    },
+    Cancel(String),
+    CancelDebounce(String),
+    Fail(BoxError),
+    Debounce {
+        key: String,
+        duration: Duration,
+        cmd: Cmd<M>,
+    },
+    Throttle {
+        key: String,
+        duration: Duration,
+        cmd: Cmd<M>,
+    },
 }
 
 // ============================================================================
@@ -375,6 +865,20 @@ pub fn quit<M>() -> Cmd<M> {
     Cmd::quit()
 }
 
+/// Quit the program with a result.
+///
+/// Equivalent to `Cmd::quit_with(value)`.
+pub fn quit_with<M, T: Send + 'static>(value: T) -> Cmd<M> {
+    Cmd::quit_with(value)
+}
+
+/// Stop the program with an error.
+///
+/// Equivalent to `Cmd::fail(err)`.
+pub fn fail<M>(err: impl Into<BoxError>) -> Cmd<M> {
+    Cmd::fail(err)
+}
+
 /// Batch multiple commands to run concurrently.
 ///
 /// Module-level function equivalent to `Cmd::batch()`.
@@ -459,3 +963,97 @@ where
 {
     Cmd::run_process(command, on_exit)
 }
+
+/// Run an external process in the background, streaming its output.
+///
+/// Module-level function equivalent to `Cmd::spawn_process()`.
+///
+/// # Example
+///
+/// ```rust
+/// use std::process::Command;
+/// use teapot::{cmd, runtime::ProcessEvent};
+///
+/// enum Msg { Deploy(ProcessEvent) }
+///
+/// let cmd: teapot::Cmd<Msg> = cmd::spawn_process(Command::new("./deploy.sh"), Msg::Deploy);
+/// ```
+pub fn spawn_process<M, F>(command: ProcessCommand, on_event: F) -> Cmd<M>
+where
+    F: Fn(ProcessEvent) -> M + Send + Sync + 'static,
+{
+    Cmd::spawn_process(command, on_event)
+}
+
+/// Print text above the program's view.
+///
+/// Module-level function equivalent to `Cmd::println()`.
+///
+/// # Example
+///
+/// ```rust
+/// use teapot::cmd;
+///
+/// enum Msg { Done }
+///
+/// let cmd: teapot::Cmd<Msg> = cmd::println("✓ done");
+/// ```
+pub fn println<M>(text: impl Into<String>) -> Cmd<M> {
+    Cmd::println(text)
+}
+
+/// Cancel the work running under a key.
+///
+/// Module-level function equivalent to `Cmd::cancel()`.
+pub fn cancel<M>(key: impl Into<String>) -> Cmd<M> {
+    Cmd::cancel(key)
+}
+
+/// Drop the command waiting under a debounce key.
+///
+/// Module-level function equivalent to `Cmd::cancel_debounce()`.
+pub fn cancel_debounce<M>(key: impl Into<String>) -> Cmd<M> {
+    Cmd::cancel_debounce(key)
+}
+
+/// Run a command once the key has been quiet for a duration.
+///
+/// Module-level function equivalent to `Cmd::debounce()`.
+pub fn debounce<M>(key: impl Into<String>, duration: Duration, cmd: Cmd<M>) -> Cmd<M> {
+    Cmd::debounce(key, duration, cmd)
+}
+
+/// Run a command at most once per duration for the key.
+///
+/// Module-level function equivalent to `Cmd::throttle()`.
+pub fn throttle<M>(key: impl Into<String>, duration: Duration, cmd: Cmd<M>) -> Cmd<M> {
+    Cmd::throttle(key, duration, cmd)
+}
+
+/// Set the terminal window title.
+///
+/// Module-level function equivalent to `Cmd::set_title()`.
+pub fn set_title<M>(title: impl Into<String>) -> Cmd<M> {
+    Cmd::set_title(title)
+}
+
+/// Copy text to the system clipboard.
+///
+/// Module-level function equivalent to `Cmd::copy_to_clipboard()`.
+pub fn copy_to_clipboard<M>(text: impl Into<String>) -> Cmd<M> {
+    Cmd::copy_to_clipboard(text)
+}
+
+/// Raise a desktop notification.
+///
+/// Module-level function equivalent to `Cmd::notify()`.
+pub fn notify<M>(title: impl Into<String>, body: impl Into<String>) -> Cmd<M> {
+    Cmd::notify(title, body)
+}
+
+/// Ring the terminal bell.
+///
+/// Module-level function equivalent to `Cmd::bell()`.
+pub fn bell<M>() -> Cmd<M> {
+    Cmd::bell()
+}
@@ -0,0 +1,134 @@
+//! Terminal control sequences sent by commands.
+//!
+//! These reach the user outside the program's view: the window title, the
+//! system clipboard (OSC 52), desktop notifications (OSC 9 or OSC 777) and
+//! the bell. Terminals without support ignore them.
+
+use std::io::{self, Write};
+
+/// A terminal control action requested with a [`Cmd`](super::Cmd).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum TerminalControl {
+    /// Set the window title
+    SetTitle(String),
+    /// Copy text to the system clipboard
+    Copy(String),
+    /// Raise a desktop notification
+    Notify { title: String, body: String },
+    /// Ring the bell
+    Bell,
+}
+
+impl TerminalControl {
+    /// Write the control sequence and flush it.
+    pub fn write(&self, out: &mut impl Write) -> io::Result<()> {
+        match self {
+            Self::SetTitle(title) => write!(out, "\x1b]0;{}\x07", sanitize(title))?,
+            Self::Copy(text) => write!(out, "\x1b]52;c;{}\x07", base64(text.as_bytes()))?,
+            Self::Notify { title, body } => match notification_protocol() {
+                NotificationProtocol::Osc777 => write!(
+                    out,
+                    "\x1b]777;notify;{};{}\x07",
+                    sanitize(title).replace(';', ","),
+                    sanitize(body)
+                )?,
+                NotificationProtocol::Osc9 => {
+                    let message = if title.is_empty() {
+                        sanitize(body)
+                    } else {
+                        format!("{}: {}", sanitize(title), sanitize(body))
+                    };
+                    write!(out, "\x1b]9;{}\x07", message)?
+                },
+            },
+            Self::Bell => out.write_all(b"\x07")?,
+        }
+        out.flush()
+    }
+}
+
+/// Escape sequence used for desktop notifications.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NotificationProtocol {
+    /// `OSC 9`, understood by iTerm2, WezTerm, Windows Terminal and others
+    Osc9,
+    /// `OSC 777`, understood by VTE-based terminals, foot and urxvt
+    Osc777,
+}
+
+/// Pick the notification sequence the current terminal understands.
+fn notification_protocol() -> NotificationProtocol {
+    let term = std::env::var("TERM").unwrap_or_default();
+    if std::env::var_os("VTE_VERSION").is_some()
+        || term.starts_with("rxvt")
+        || term.starts_with("foot")
+    {
+        NotificationProtocol::Osc777
+    } else {
+        NotificationProtocol::Osc9
+    }
+}
+
+/// Remove control characters, which would end the sequence early.
+fn sanitize(text: &str) -> String {
+    text.chars().map(|c| if c.is_control() { ' ' } else { c }).collect()
+}
+
+/// Standard base64 with padding, as OSC 52 expects.
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes =
+            [chunk[0], chunk.get(1).copied().unwrap_or(0), chunk.get(2).copied().unwrap_or(0)];
+        let group = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(group >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn written(control: TerminalControl) -> String {
+        let mut out = Vec::new();
+        control.write(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64("héllo wörld".as_bytes()), "aMOpbGxvIHfDtnJsZA==");
+    }
+
+    #[test]
+    fn test_sequences() {
+        assert_eq!(
+            written(TerminalControl::SetTitle("build\x07done".into())),
+            "\x1b]0;build done\x07"
+        );
+        assert_eq!(written(TerminalControl::Copy("foo".into())), "\x1b]52;c;Zm9v\x07");
+        assert_eq!(written(TerminalControl::Bell), "\x07");
+
+        let notify =
+            written(TerminalControl::Notify { title: "Deploy".into(), body: "done".into() });
+        assert!(
+            notify == "\x1b]9;Deploy: done\x07" || notify == "\x1b]777;notify;Deploy;done\x07",
+            "unexpected notification sequence {:?}",
+            notify
+        );
+    }
+}
@@ -0,0 +1,136 @@
+//! Errors that stop a program.
+
+use std::{error::Error, fmt, io};
+
+use super::Signal;
+
+/// An error raised by the application, as passed to [`Cmd::fail`].
+///
+/// [`Cmd::fail`]: super::Cmd::fail
+pub type BoxError = Box<dyn Error + Send + Sync>;
+
+/// Why a program stopped without completing normally.
+///
+/// Returned by [`Program::run`](super::Program::run). The terminal has been
+/// restored by the time the error is returned.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// match Program::new(app).run() {
+///     Ok(app) => println!("{}", app.summary()),
+///     Err(ProgramError::Interrupted(_)) => std::process::exit(130),
+///     Err(err) => {
+///         if let Some(err) = err.downcast_ref::<DeployError>() {
+///             eprintln!("deploy failed: {}", err);
+///         }
+///         std::process::exit(1);
+///     },
+/// }
+/// ```
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ProgramError {
+    /// Reading from or writing to the terminal failed.
+    Io(io::Error),
+    /// `update`, `view` or a command panicked; holds the panic message.
+    Panic(String),
+    /// A signal or Ctrl+C stopped the program, and the model did not handle it.
+    Interrupted(Signal),
+    /// The application failed the program with [`Cmd::fail`] or a fallible
+    /// command.
+    ///
+    /// [`Cmd::fail`]: super::Cmd::fail
+    User(BoxError),
+    /// The model quit with a [`Cmd::quit_with`] value of another type than
+    /// the one [`Program::run_with_outcome`] expected; holds the expected
+    /// type's name.
+    ///
+    /// [`Cmd::quit_with`]: super::Cmd::quit_with
+    /// [`Program::run_with_outcome`]: super::Program::run_with_outcome
+    QuitValueType(&'static str),
+}
+
+impl ProgramError {
+    /// The application error, if it is of type `E`.
+    pub fn downcast_ref<E: Error + 'static>(&self) -> Option<&E> {
+        match self {
+            Self::User(err) => err.downcast_ref(),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ProgramError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "terminal I/O failed: {}", err),
+            Self::Panic(message) => write!(f, "program panicked: {}", message),
+            Self::Interrupted(signal) => write!(f, "program interrupted by {:?}", signal),
+            Self::User(err) => err.fmt(f),
+            Self::QuitValueType(expected) => {
+                write!(f, "Cmd::quit_with value is not a `{}` as expected", expected)
+            },
+        }
+    }
+}
+
+impl Error for ProgramError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::User(err) => Some(err.as_ref()),
+            Self::Panic(_) | Self::Interrupted(_) | Self::QuitValueType(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for ProgramError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<ProgramError> for io::Error {
+    fn from(err: ProgramError) -> Self {
+        match err {
+            ProgramError::Io(err) => err,
+            ProgramError::Interrupted(_) => io::Error::new(io::ErrorKind::Interrupted, err),
+            err => io::Error::other(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct DeployError;
+
+    impl fmt::Display for DeployError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "deploy failed")
+        }
+    }
+
+    impl Error for DeployError {}
+
+    #[test]
+    fn test_user_error() {
+        let err = ProgramError::User(Box::new(DeployError));
+        assert_eq!(err.to_string(), "deploy failed");
+        assert!(err.downcast_ref::<DeployError>().is_some());
+        assert!(err.downcast_ref::<io::Error>().is_none());
+        assert!(err.source().is_some());
+    }
+
+    #[test]
+    fn test_into_io_error() {
+        let err: io::Error = ProgramError::Interrupted(Signal::Interrupt).into();
+        assert_eq!(err.kind(), io::ErrorKind::Interrupted);
+
+        let err: io::Error = ProgramError::Io(io::ErrorKind::BrokenPipe.into()).into();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+    }
+}
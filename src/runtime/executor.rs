@@ -0,0 +1,297 @@
+//! Minimal executor for async commands.
+//!
+//! Futures created with `Cmd::perform_async` are driven on a single
+//! background thread. The executor has no I/O reactor or timer of its own:
+//! futures are polled again only when their waker is called. This is enough
+//! for channel receivers, thread-backed futures and most runtime-agnostic
+//! libraries. Futures that depend on a specific runtime's reactor (for
+//! example `tokio::time::sleep`) must be driven by that runtime instead;
+//! `Program::run_async` spawns them on the caller's tokio runtime.
+
+use std::{
+    any::Any,
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::{
+        Arc, Mutex, Weak,
+        mpsc::{self, Receiver, Sender},
+    },
+    task::{Context, Poll, Wake, Waker},
+    thread,
+};
+
+/// A boxed future with no output, as stored by the executor.
+type BoxFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// A spawned future along with the queue used to reschedule it.
+struct Task {
+    future: Mutex<Option<BoxFuture>>,
+    queue: Sender<Option<Arc<Task>>>,
+}
+
+impl Wake for Task {
+    fn wake(self: Arc<Self>) {
+        let queue = self.queue.clone();
+        let _ = queue.send(Some(self));
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        let _ = self.queue.send(Some(Arc::clone(self)));
+    }
+}
+
+/// Single-threaded executor running futures in the background.
+///
+/// The worker thread exits when the executor is dropped.
+pub(crate) struct Executor {
+    queue: Sender<Option<Arc<Task>>>,
+}
+
+impl Executor {
+    /// Create a new executor and start its worker thread.
+    pub fn new() -> Self {
+        let (queue, receiver) = mpsc::channel();
+        thread::Builder::new()
+            .name("teapot-executor".to_string())
+            .spawn(move || run(receiver))
+            .expect("failed to spawn executor thread");
+        Self { queue }
+    }
+
+    /// Spawn a future onto the executor.
+    ///
+    /// The returned handle can be used to cancel the future; dropping the
+    /// handle lets the future run to completion.
+    pub fn spawn<F>(&self, future: F) -> TaskHandle
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let task = Arc::new(Task {
+            future: Mutex::new(Some(Box::pin(future))),
+            queue: self.queue.clone(),
+        });
+        let handle = TaskHandle { task: TaskRef::Executor(Arc::downgrade(&task)) };
+        let _ = self.queue.send(Some(task));
+        handle
+    }
+}
+
+/// Handle to a future spawned on the [`Executor`], or on a tokio runtime.
+#[derive(Clone)]
+pub(crate) struct TaskHandle {
+    task: TaskRef,
+}
+
+#[derive(Clone)]
+enum TaskRef {
+    Executor(Weak<Task>),
+    #[cfg(feature = "tokio")]
+    Tokio(tokio::task::AbortHandle),
+}
+
+impl TaskHandle {
+    /// Cancel the task, dropping its future without polling it again.
+    ///
+    /// If the future is being polled on the executor, this waits for the
+    /// poll to finish; a tokio task is dropped once its current poll ends.
+    pub fn cancel(&self) {
+        match &self.task {
+            TaskRef::Executor(task) => {
+                if let Some(task) = task.upgrade() {
+                    let future = match task.future.lock() {
+                        Ok(mut slot) => slot.take(),
+                        Err(poisoned) => poisoned.into_inner().take(),
+                    };
+                    drop(future);
+                }
+            },
+            #[cfg(feature = "tokio")]
+            TaskRef::Tokio(handle) => handle.abort(),
+        }
+    }
+
+    /// Whether the task completed or was cancelled.
+    pub fn is_finished(&self) -> bool {
+        match &self.task {
+            TaskRef::Executor(task) => task.strong_count() == 0,
+            #[cfg(feature = "tokio")]
+            TaskRef::Tokio(handle) => handle.is_finished(),
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl From<tokio::task::AbortHandle> for TaskHandle {
+    fn from(handle: tokio::task::AbortHandle) -> Self {
+        Self { task: TaskRef::Tokio(handle) }
+    }
+}
+
+impl Default for Executor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Executor {
+    fn drop(&mut self) {
+        // Ask the worker thread to stop; pending futures are dropped with it
+        let _ = self.queue.send(None);
+    }
+}
+
+/// Future adapter that turns a panic while polling into an error.
+///
+/// Keeps a panicking command from taking down the executor thread.
+pub(crate) struct CatchUnwind<F> {
+    future: Pin<Box<F>>,
+}
+
+impl<F: Future> CatchUnwind<F> {
+    /// Wrap a future so that panics are caught.
+    pub fn new(future: F) -> Self {
+        Self { future: Box::pin(future) }
+    }
+}
+
+impl<F: Future> Future for CatchUnwind<F> {
+    type Output = Result<F::Output, Box<dyn Any + Send>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let future = self.future.as_mut();
+        match panic::catch_unwind(AssertUnwindSafe(|| future.poll(cx))) {
+            Ok(Poll::Ready(output)) => Poll::Ready(Ok(output)),
+            Ok(Poll::Pending) => Poll::Pending,
+            Err(payload) => Poll::Ready(Err(payload)),
+        }
+    }
+}
+
+/// Worker loop: poll each task whenever it is (re)scheduled.
+fn run(receiver: Receiver<Option<Arc<Task>>>) {
+    while let Ok(Some(task)) = receiver.recv() {
+        let mut slot = match task.future.lock() {
+            Ok(slot) => slot,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        // A task woken after completion has nothing left to poll
+        let Some(mut future) = slot.take() else {
+            continue;
+        };
+
+        let waker = Waker::from(Arc::clone(&task));
+        let mut cx = Context::from_waker(&waker);
+        if future.as_mut().poll(&mut cx) == Poll::Pending {
+            *slot = Some(future);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::mpsc, time::Duration};
+
+    use super::*;
+
+    /// A future that resolves once another thread sets its value.
+    struct Delayed {
+        state: Arc<Mutex<(Option<i32>, Option<Waker>)>>,
+    }
+
+    impl Future for Delayed {
+        type Output = i32;
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<i32> {
+            let mut state = self.state.lock().unwrap();
+            match state.0.take() {
+                Some(value) => Poll::Ready(value),
+                None => {
+                    state.1 = Some(cx.waker().clone());
+                    Poll::Pending
+                },
+            }
+        }
+    }
+
+    #[test]
+    fn test_executor_runs_ready_future() {
+        let executor = Executor::new();
+        let (tx, rx) = mpsc::channel();
+
+        executor.spawn(async move {
+            tx.send(42).unwrap();
+        });
+
+        assert_eq!(rx.recv_timeout(Duration::from_secs(1)), Ok(42));
+    }
+
+    #[test]
+    fn test_executor_repolls_on_wake() {
+        let executor = Executor::new();
+        let (tx, rx) = mpsc::channel();
+        let state = Arc::new(Mutex::new((None, None)));
+
+        let delayed = Delayed { state: Arc::clone(&state) };
+        executor.spawn(async move {
+            tx.send(delayed.await).unwrap();
+        });
+
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            let waker = {
+                let mut state = state.lock().unwrap();
+                state.0 = Some(7);
+                state.1.take()
+            };
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+        });
+
+        assert_eq!(rx.recv_timeout(Duration::from_secs(1)), Ok(7));
+    }
+
+    #[test]
+    fn test_cancel_drops_pending_future() {
+        let executor = Executor::new();
+        let (tx, rx) = mpsc::channel::<i32>();
+        let state = Arc::new(Mutex::new((None, None)));
+
+        let delayed = Delayed { state: Arc::clone(&state) };
+        let handle = executor.spawn(async move {
+            tx.send(delayed.await).unwrap();
+        });
+
+        // Wait until the future has registered its waker
+        while state.lock().unwrap().1.is_none() {
+            thread::yield_now();
+        }
+        handle.cancel();
+
+        // Dropping the future drops its sender, disconnecting the channel
+        assert_eq!(
+            rx.recv_timeout(Duration::from_secs(1)),
+            Err(mpsc::RecvTimeoutError::Disconnected)
+        );
+    }
+
+    #[test]
+    fn test_catch_unwind_survives_panicking_future() {
+        let executor = Executor::new();
+        let (tx, rx) = mpsc::channel();
+
+        let tx2 = tx.clone();
+        executor.spawn(async move {
+            let result = CatchUnwind::new(async { panic!("boom") }).await;
+            tx2.send(result.is_err()).unwrap();
+        });
+        executor.spawn(async move {
+            tx.send(true).unwrap();
+        });
+
+        assert_eq!(rx.recv_timeout(Duration::from_secs(1)), Ok(true));
+        assert_eq!(rx.recv_timeout(Duration::from_secs(1)), Ok(true));
+    }
+}
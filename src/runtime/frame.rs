@@ -0,0 +1,127 @@
+//! Structured view output.
+//!
+//! [`Model::view`] returns the text to draw. Models that need more control
+//! override [`Model::frame`] instead, returning a [`Frame`] that also places
+//! the terminal's real cursor and sets the window title. A visible cursor at
+//! the insertion point lets input methods, screen magnifiers and the user's
+//! cursor style work as they do in other programs.
+//!
+//! [`Model::view`]: super::Model::view
+//! [`Model::frame`]: super::Model::frame
+
+/// Shape of the terminal cursor.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum CursorShape {
+    /// The shape configured by the user in their terminal.
+    #[default]
+    Default,
+    /// A steady block.
+    Block,
+    /// A blinking block.
+    BlinkingBlock,
+    /// A steady underline.
+    Underline,
+    /// A blinking underline.
+    BlinkingUnderline,
+    /// A steady vertical bar, as used by most text fields.
+    Bar,
+    /// A blinking vertical bar.
+    BlinkingBar,
+}
+
+/// Position and shape of the visible terminal cursor.
+///
+/// Positions are in terminal cells, relative to the top-left corner of the
+/// frame.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Cursor {
+    /// Column, counted in display cells.
+    pub column: u16,
+    /// Row, counted in lines of the frame.
+    pub row: u16,
+    /// Shape of the cursor.
+    pub shape: CursorShape,
+}
+
+impl Cursor {
+    /// Create a cursor at the given column and row with the default shape.
+    pub fn new(column: u16, row: u16) -> Self {
+        Self { column, row, shape: CursorShape::Default }
+    }
+
+    /// Set the cursor shape.
+    pub fn shape(mut self, shape: CursorShape) -> Self {
+        self.shape = shape;
+        self
+    }
+
+    /// Move the cursor by the given number of columns and rows.
+    ///
+    /// Use this to place a component's cursor, which is relative to the
+    /// component's own view, within the enclosing frame.
+    pub fn offset(mut self, columns: u16, rows: u16) -> Self {
+        self.column = self.column.saturating_add(columns);
+        self.row = self.row.saturating_add(rows);
+        self
+    }
+}
+
+/// A rendered view with per-frame terminal state.
+///
+/// # Example
+///
+/// ```rust
+/// use teapot::runtime::{Cursor, CursorShape, Frame};
+///
+/// let frame = Frame::new("Name: Ferris")
+///     .cursor(Cursor::new(12, 0).shape(CursorShape::Bar))
+///     .title("Sign up");
+///
+/// assert_eq!(frame.content, "Name: Ferris");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Frame {
+    /// The text to draw, as returned by `view()`.
+    pub content: String,
+    /// Where to show the terminal cursor; hidden when `None`.
+    pub cursor: Option<Cursor>,
+    /// Window title to set; left unchanged when `None`.
+    pub title: Option<String>,
+}
+
+impl Frame {
+    /// Create a frame with the given content, a hidden cursor and no title.
+    pub fn new(content: impl Into<String>) -> Self {
+        Self { content: content.into(), cursor: None, title: None }
+    }
+
+    /// Show the terminal cursor at the given position.
+    pub fn cursor(mut self, cursor: Cursor) -> Self {
+        self.cursor = Some(cursor);
+        self
+    }
+
+    /// Show the terminal cursor if `cursor` is `Some`.
+    pub fn with_cursor(mut self, cursor: Option<Cursor>) -> Self {
+        self.cursor = cursor;
+        self
+    }
+
+    /// Set the window title.
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+}
+
+impl From<String> for Frame {
+    fn from(content: String) -> Self {
+        Self::new(content)
+    }
+}
+
+impl From<&str> for Frame {
+    fn from(content: &str) -> Self {
+        Self::new(content)
+    }
+}
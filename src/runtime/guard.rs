@@ -0,0 +1,234 @@
+//! Terminal restoration on exit and on panic.
+//!
+//! [`Terminal`] restores the terminal modes it enabled when dropped, so the
+//! terminal is left usable however the event loop exits. Unwinding runs
+//! the guard only after the panic message has been printed (in raw mode, on
+//! the alternate screen), so [`PanicHook`] additionally restores the
+//! terminal from the panic hook, before the message is printed.
+
+use std::{
+    io,
+    panic::{self, PanicHookInfo},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::{self, ThreadId},
+};
+
+use crossterm::{cursor, event, execute, terminal};
+
+use super::ProgramOptions;
+use crate::terminal::{Backend, CrosstermBackend, OutputStream};
+
+/// Terminal modes enabled while a program runs.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TerminalModes {
+    alt_screen: bool,
+    mouse: bool,
+    bracketed_paste: bool,
+    focus_change: bool,
+}
+
+impl TerminalModes {
+    /// The modes requested by the program options.
+    pub fn from_options(options: &ProgramOptions) -> Self {
+        Self {
+            alt_screen: options.alt_screen,
+            mouse: options.mouse,
+            bracketed_paste: options.bracketed_paste,
+            focus_change: options.focus_change,
+        }
+    }
+
+    /// Enable raw mode and the requested modes.
+    fn enable(&self, out: &mut impl Backend) -> io::Result<()> {
+        out.enable_raw_mode()?;
+
+        if self.alt_screen {
+            execute!(out, terminal::EnterAlternateScreen, cursor::MoveTo(0, 0))?;
+        }
+
+        if self.mouse {
+            execute!(out, event::EnableMouseCapture)?;
+        }
+
+        if self.bracketed_paste {
+            execute!(out, event::EnableBracketedPaste)?;
+        }
+
+        if self.focus_change {
+            execute!(out, event::EnableFocusChange)?;
+        }
+
+        execute!(out, cursor::Hide)?;
+
+        Ok(())
+    }
+
+    /// Disable the modes enabled by [`TerminalModes::enable`], in reverse order.
+    fn restore(&self, out: &mut impl Backend) -> io::Result<()> {
+        execute!(out, cursor::SetCursorStyle::DefaultUserShape, cursor::Show)?;
+
+        if self.focus_change {
+            execute!(out, event::DisableFocusChange)?;
+        }
+
+        if self.bracketed_paste {
+            execute!(out, event::DisableBracketedPaste)?;
+        }
+
+        if self.mouse {
+            execute!(out, event::DisableMouseCapture)?;
+        }
+
+        if self.alt_screen {
+            execute!(out, terminal::LeaveAlternateScreen)?;
+        }
+
+        out.disable_raw_mode()?;
+
+        // Ensure cursor is at column 0 for clean output after TUI exits
+        execute!(out, cursor::MoveToColumn(0))?;
+
+        Ok(())
+    }
+}
+
+/// The backend a program draws on; restores the terminal when dropped if
+/// it is set up.
+pub(crate) struct Terminal {
+    backend: Box<dyn Backend>,
+    /// Modes enabled while the terminal is set up
+    modes: Option<TerminalModes>,
+    /// Cleared once the terminal has been restored, by this or by the
+    /// panic hook
+    active: Arc<AtomicBool>,
+}
+
+impl Terminal {
+    pub fn new(backend: Box<dyn Backend>) -> Self {
+        Self { backend, modes: None, active: Arc::default() }
+    }
+
+    /// The backend, for writing frames and reading input.
+    pub fn backend(&mut self) -> &mut Box<dyn Backend> {
+        &mut self.backend
+    }
+
+    /// Whether the terminal is set up.
+    pub fn is_enabled(&self) -> bool {
+        self.modes.is_some()
+    }
+
+    /// Flag cleared once the terminal has been restored, for [`PanicHook`].
+    pub fn active(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.active)
+    }
+
+    /// Set up the terminal with `modes`.
+    pub fn enable(&mut self, modes: TerminalModes) -> io::Result<()> {
+        // Recorded first so a failure halfway through still restores
+        self.modes = Some(modes);
+        self.active.store(true, Ordering::SeqCst);
+        modes.enable(&mut self.backend)
+    }
+
+    /// Restore the terminal if it is set up, reporting any error.
+    pub fn restore(&mut self) -> io::Result<()> {
+        match self.modes.take() {
+            Some(modes) if self.active.swap(false, Ordering::SeqCst) => {
+                modes.restore(&mut self.backend)
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        let _ = self.restore();
+    }
+}
+
+/// The panic hook in place before ours.
+type Hook = Box<dyn Fn(&PanicHookInfo<'_>) + Sync + Send + 'static>;
+
+/// Restores the terminal before panic messages from the event-loop thread
+/// are printed. The previous hook is reinstated when dropped.
+pub(crate) struct PanicHook {
+    previous: Arc<Hook>,
+}
+
+impl PanicHook {
+    /// Install a hook running `restore` when the current thread panics.
+    ///
+    /// Panics on other threads (background commands) are caught and
+    /// reported by the runtime, so they leave the terminal alone.
+    pub fn install(restore: impl Fn() + Send + Sync + 'static) -> Self {
+        let previous: Arc<Hook> = Arc::new(panic::take_hook());
+        let thread: ThreadId = thread::current().id();
+
+        let chained = Arc::clone(&previous);
+        panic::set_hook(Box::new(move |info| {
+            if thread::current().id() == thread {
+                restore();
+            }
+            chained(info);
+        }));
+
+        Self { previous }
+    }
+
+    /// Install a hook restoring the process's terminal, drawn on `output`
+    /// and set up with `modes`.
+    pub fn for_terminal(
+        modes: TerminalModes,
+        output: OutputStream,
+        active: Arc<AtomicBool>,
+    ) -> Self {
+        Self::install(move || {
+            if active.swap(false, Ordering::SeqCst) {
+                if let Ok(mut backend) = CrosstermBackend::with_output(output) {
+                    let _ = modes.restore(&mut backend);
+                }
+            }
+        })
+    }
+}
+
+impl Drop for PanicHook {
+    fn drop(&mut self) {
+        // Skip while panicking: the hook cannot be replaced then
+        if thread::panicking() {
+            return;
+        }
+        let previous = Arc::clone(&self.previous);
+        panic::set_hook(Box::new(move |info| previous(info)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use super::*;
+
+    #[test]
+    fn test_panic_hook_restores_only_on_its_thread() {
+        let restored = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&restored);
+        let hook = PanicHook::install(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        let _ = thread::spawn(|| panic!("background")).join();
+        assert_eq!(restored.load(Ordering::SeqCst), 0);
+
+        let result = panic::catch_unwind(|| panic!("event loop"));
+        assert!(result.is_err());
+        assert_eq!(restored.load(Ordering::SeqCst), 1);
+
+        drop(hook);
+    }
+}
@@ -0,0 +1,129 @@
+//! Handle for interacting with a running program from other threads.
+
+use std::{mem, sync::Arc};
+
+use super::{
+    metrics::{MetricsRecorder, RenderMetrics},
+    program::{EventSender, RuntimeEvent},
+};
+
+/// A cloneable handle for sending messages into a running [`Program`].
+///
+/// This is the equivalent of Bubble Tea's `Program.Send`. Obtain a handle
+/// with [`Program::handle`] before calling `run()`, then move it to another
+/// thread (a network listener, a log tailer, a signal handler) to inject
+/// messages, request a quit or force a repaint.
+///
+/// Messages sent before the program starts are queued and delivered once
+/// the event loop is running.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use teapot::{Cmd, Model, Program};
+/// use std::{thread, time::Duration};
+///
+/// struct App { lines: Vec<String> }
+/// enum Msg { Line(String) }
+///
+/// impl Model for App {
+///     type Message = Msg;
+///     fn init(&self) -> Option<Cmd<Msg>> { None }
+///     fn update(&mut self, msg: Msg) -> Option<Cmd<Msg>> {
+///         let Msg::Line(line) = msg;
+///         self.lines.push(line);
+///         None
+///     }
+///     fn view(&self) -> String { self.lines.join("\n") }
+/// }
+///
+/// let program = Program::new(App { lines: Vec::new() });
+/// let handle = program.handle();
+///
+/// thread::spawn(move || {
+///     for i in 0..3 {
+///         handle.send(Msg::Line(format!("line {}", i)));
+///         thread::sleep(Duration::from_millis(500));
+///     }
+///     handle.quit();
+/// });
+///
+/// program.run().unwrap();
+/// ```
+///
+/// [`Program`]: super::Program
+/// [`Program::handle`]: super::Program::handle
+pub struct ProgramHandle<M> {
+    sender: EventSender<M>,
+    /// Shared with the program to tell whether any handles are alive
+    token: Arc<()>,
+    metrics: Arc<MetricsRecorder>,
+}
+
+impl<M> ProgramHandle<M> {
+    /// Create a handle sending into the given event channel.
+    pub(crate) fn new(
+        sender: EventSender<M>,
+        token: Arc<()>,
+        metrics: Arc<MetricsRecorder>,
+    ) -> Self {
+        Self { sender, token, metrics }
+    }
+
+    /// Send a message to the program's `update` function.
+    ///
+    /// The message passes through the program's message filter like any
+    /// other message. Returns `false` if the program has already exited.
+    pub fn send(&self, msg: M) -> bool {
+        self.sender.send(RuntimeEvent::External(msg)).is_ok()
+    }
+
+    /// Ask the program to quit, as if `Cmd::quit()` had been returned.
+    ///
+    /// Returns `false` if the program has already exited.
+    pub fn quit(&self) -> bool {
+        self.sender.send(RuntimeEvent::Quit).is_ok()
+    }
+
+    /// Force the program to redraw its view from scratch.
+    ///
+    /// Useful after something else has written to the terminal.
+    /// Returns `false` if the program has already exited.
+    pub fn repaint(&self) -> bool {
+        self.sender.send(RuntimeEvent::Repaint).is_ok()
+    }
+
+    /// Rendering statistics of the program so far.
+    ///
+    /// Updates arriving faster than the frame rate are merged into a single
+    /// frame; compare `updates`, `merged_updates` and `frames` to see how
+    /// much rendering was saved, and `slow_renders` to spot a `view()` that
+    /// cannot keep up.
+    pub fn metrics(&self) -> RenderMetrics {
+        self.metrics.snapshot()
+    }
+}
+
+impl<M> Clone for ProgramHandle<M> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            token: Arc::clone(&self.token),
+            metrics: Arc::clone(&self.metrics),
+        }
+    }
+}
+
+impl<M> Drop for ProgramHandle<M> {
+    fn drop(&mut self) {
+        // Let the program notice once the last handle is gone
+        drop(mem::take(&mut self.token));
+        let _ = self.sender.send(RuntimeEvent::HandleDropped);
+    }
+}
+
+impl<M> std::fmt::Debug for ProgramHandle<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProgramHandle").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,210 @@
+//! Output of programs running without a terminal.
+//!
+//! When stdout or stdin is not a terminal (in CI, or when output is piped),
+//! the program still runs its full update loop — commands, ticks and
+//! subscriptions — but instead of drawing frames it writes plain text that
+//! reads well in a log: either the lines of the view that changed, or
+//! periodic snapshots of the whole view. It stops when the model quits, or
+//! as its [`HeadlessExit`] policy decides.
+
+use std::{
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+use crate::output::strip_ansi;
+
+/// How a program without a terminal reports its view.
+///
+/// The view is checked at most once per interval, and once more when the
+/// program exits, so animations such as spinners do not flood the log.
+/// Lines printed with `Cmd::println` are written as they are produced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NonInteractiveOutput {
+    /// Print each line of the view that differs from the line at the same
+    /// position when the view was last checked.
+    ChangedLines(Duration),
+    /// Print the whole view whenever it changed, followed by a blank line.
+    Snapshots(Duration),
+    /// Print only the final view when the program exits.
+    Final,
+}
+
+impl NonInteractiveOutput {
+    /// How often the view is checked, if periodically.
+    fn interval(&self) -> Option<Duration> {
+        match self {
+            Self::ChangedLines(interval) | Self::Snapshots(interval) => Some(*interval),
+            Self::Final => None,
+        }
+    }
+}
+
+impl Default for NonInteractiveOutput {
+    fn default() -> Self {
+        Self::ChangedLines(Duration::from_millis(500))
+    }
+}
+
+/// When a program without a terminal stops if the model does not quit.
+///
+/// By default the program runs until nothing is left that could update the
+/// model: no background work, timers, subscriptions or program handles. A
+/// model that always declares an interval subscription, such as a spinner,
+/// or whose handles stay alive would then never stop; ignore those sources,
+/// or bound the run with a timeout. Either way the program ends with
+/// [`Outcome::Quit`](super::Outcome::Quit).
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+/// use teapot::runtime::HeadlessExit;
+///
+/// let exit = HeadlessExit {
+///     ignore_intervals: true,
+///     timeout: Some(Duration::from_secs(600)),
+///     ..HeadlessExit::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HeadlessExit {
+    /// Consider the program idle even while interval subscriptions are
+    /// active.
+    pub ignore_intervals: bool,
+    /// Consider the program idle even while program handles are alive.
+    pub ignore_handles: bool,
+    /// Stop after running this long, even if the program is not idle.
+    pub timeout: Option<Duration>,
+}
+
+/// Writes a headless program's view as plain text.
+pub(crate) struct HeadlessPrinter {
+    output: NonInteractiveOutput,
+    /// Lines of the view when it was last checked
+    lines: Vec<String>,
+    /// When the view is checked next
+    next: Option<Instant>,
+}
+
+impl HeadlessPrinter {
+    pub fn new(output: NonInteractiveOutput, now: Instant) -> Self {
+        Self { output, lines: Vec::new(), next: output.interval().map(|_| now) }
+    }
+
+    /// When the view should be checked next, if it is checked periodically.
+    pub fn next_due(&self) -> Option<Instant> {
+        self.next
+    }
+
+    /// Write lines printed with `Cmd::println`.
+    pub fn print_lines(&mut self, out: &mut impl Write, printed: &[String]) -> io::Result<()> {
+        for line in printed {
+            writeln!(out, "{}", strip_ansi(line))?;
+        }
+        Ok(())
+    }
+
+    /// Whether the view should be checked at `now`.
+    pub fn is_due(&self, now: Instant) -> bool {
+        self.next.is_some_and(|next| now >= next)
+    }
+
+    /// Write the view and schedule the next check.
+    pub fn update(&mut self, out: &mut impl Write, view: &str, now: Instant) -> io::Result<()> {
+        self.next = self.output.interval().map(|interval| now + interval);
+        self.write(out, view)
+    }
+
+    /// Write the final view.
+    pub fn finish(&mut self, out: &mut impl Write, view: &str) -> io::Result<()> {
+        self.write(out, view)
+    }
+
+    /// Write the view according to the output mode, if it changed.
+    fn write(&mut self, out: &mut impl Write, view: &str) -> io::Result<()> {
+        let lines: Vec<String> =
+            strip_ansi(view).lines().map(|line| line.trim_end().to_string()).collect();
+        if lines == self.lines {
+            return Ok(());
+        }
+
+        match self.output {
+            NonInteractiveOutput::ChangedLines(_) => {
+                for (row, line) in lines.iter().enumerate() {
+                    if self.lines.get(row) != Some(line) && !line.is_empty() {
+                        writeln!(out, "{}", line)?;
+                    }
+                }
+            },
+            NonInteractiveOutput::Snapshots(_) => {
+                for line in &lines {
+                    writeln!(out, "{}", line)?;
+                }
+                writeln!(out)?;
+            },
+            NonInteractiveOutput::Final => {
+                for line in &lines {
+                    writeln!(out, "{}", line)?;
+                }
+            },
+        }
+        out.flush()?;
+
+        self.lines = lines;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn printed(printer: &mut HeadlessPrinter, view: &str, now: Instant) -> String {
+        let mut out = Vec::new();
+        if printer.is_due(now) {
+            printer.update(&mut out, view, now).unwrap();
+        }
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_changed_lines() {
+        let start = Instant::now();
+        let interval = Duration::from_secs(1);
+        let mut printer = HeadlessPrinter::new(NonInteractiveOutput::ChangedLines(interval), start);
+
+        let view = "\x1b[1mTasks\x1b[0m\n  build  running\n  test   waiting";
+        assert_eq!(
+            printed(&mut printer, view, start),
+            "Tasks\n  build  running\n  test   waiting\n"
+        );
+
+        // Not due yet
+        let view = "Tasks\n  build  done\n  test   running";
+        assert_eq!(printed(&mut printer, view, start + interval / 2), "");
+
+        // Only the changed lines are printed
+        assert_eq!(
+            printed(&mut printer, view, start + interval),
+            "  build  done\n  test   running\n"
+        );
+        assert_eq!(printed(&mut printer, view, start + interval * 2), "");
+    }
+
+    #[test]
+    fn test_snapshots_and_final() {
+        let start = Instant::now();
+        let mut printer =
+            HeadlessPrinter::new(NonInteractiveOutput::Snapshots(Duration::from_secs(1)), start);
+        assert_eq!(printed(&mut printer, "a\nb", start), "a\nb\n\n");
+
+        let mut printer = HeadlessPrinter::new(NonInteractiveOutput::Final, start);
+        assert_eq!(printer.next_due(), None);
+        assert_eq!(printed(&mut printer, "a\nb", start), "");
+
+        let mut out = Vec::new();
+        printer.finish(&mut out, "a\nb").unwrap();
+        assert_eq!(out, b"a\nb\n");
+    }
+}
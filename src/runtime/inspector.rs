@@ -0,0 +1,376 @@
+//! Debug inspector overlay.
+//!
+//! When enabled with [`Program::with_inspector`], a key toggles a panel drawn
+//! over the view. It shows the messages that reached `update` most recently,
+//! the active subscriptions and pending ticks with their next fire times,
+//! how long the last `view()` and render took, and the terminal size.
+//!
+//! [`Program::with_inspector`]: super::Program::with_inspector
+
+use std::{
+    collections::VecDeque,
+    fmt::{self, Debug},
+    time::{Duration, Instant},
+};
+
+use super::RenderMetrics;
+use crate::{
+    style::Color,
+    terminal::{Event, KeyCode, KeyEvent, KeyModifiers},
+    util::truncate_text,
+};
+
+/// Describes a message for the log.
+type DescribeFn<Msg> = Box<dyn Fn(&Msg) -> String + Send>;
+
+/// Configuration of the debug inspector.
+///
+/// # Example
+///
+/// ```rust
+/// use teapot::runtime::Inspector;
+/// use teapot::KeyCode;
+///
+/// #[derive(Debug)]
+/// enum Msg { Tick, Key(char) }
+///
+/// let inspector = Inspector::<Msg>::new()
+///     .toggle_key(KeyCode::F(10))
+///     .history(50)
+///     .debug_messages();
+/// ```
+pub struct Inspector<Msg> {
+    toggle: KeyEvent,
+    history: usize,
+    describe: DescribeFn<Msg>,
+}
+
+impl<Msg> Inspector<Msg> {
+    /// Create an inspector toggled with F12, keeping the last 20 messages.
+    ///
+    /// Messages are logged by type name; see [`Inspector::debug_messages`]
+    /// and [`Inspector::describe_messages`] to show their contents.
+    pub fn new() -> Self {
+        Self {
+            toggle: KeyEvent { code: KeyCode::F(12), modifiers: KeyModifiers::NONE },
+            history: 20,
+            describe: Box::new(|_| short_type_name::<Msg>().to_string()),
+        }
+    }
+
+    /// Set the key that shows and hides the inspector.
+    ///
+    /// The key is consumed by the inspector and never reaches the model.
+    pub fn toggle_key(self, code: KeyCode) -> Self {
+        self.toggle_key_with(code, KeyModifiers::NONE)
+    }
+
+    /// Set the key and modifiers that show and hide the inspector.
+    pub fn toggle_key_with(mut self, code: KeyCode, modifiers: KeyModifiers) -> Self {
+        self.toggle = KeyEvent { code, modifiers };
+        self
+    }
+
+    /// Set how many recent messages are kept.
+    pub fn history(mut self, messages: usize) -> Self {
+        self.history = messages;
+        self
+    }
+
+    /// Describe messages with a custom function.
+    pub fn describe_messages(mut self, describe: impl Fn(&Msg) -> String + Send + 'static) -> Self {
+        self.describe = Box::new(describe);
+        self
+    }
+}
+
+impl<Msg: Debug> Inspector<Msg> {
+    /// Describe messages with their `Debug` representation.
+    pub fn debug_messages(self) -> Self {
+        self.describe_messages(|msg| format!("{:?}", msg))
+    }
+}
+
+impl<Msg> Default for Inspector<Msg> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Msg> fmt::Debug for Inspector<Msg> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Inspector")
+            .field("toggle", &self.toggle)
+            .field("history", &self.history)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Runtime state shown in the panel, gathered by the program.
+pub(crate) struct InspectorInfo {
+    /// Active subscription ids, with the time until they next fire if timed
+    pub subscriptions: Vec<(String, Option<Duration>)>,
+    /// Time until each pending tick fires
+    pub ticks: Vec<Duration>,
+    pub metrics: RenderMetrics,
+}
+
+/// The inspector of a running program.
+pub(crate) struct InspectorState<Msg> {
+    config: Inspector<Msg>,
+    visible: bool,
+    /// Recent messages with the time they were delivered, newest last
+    messages: VecDeque<(Instant, String)>,
+    /// Total messages delivered
+    delivered: u64,
+    /// Duration of the last `view()` call
+    view_time: Duration,
+    /// Terminal size, when known
+    size: Option<(u16, u16)>,
+}
+
+impl<Msg> InspectorState<Msg> {
+    pub fn new(config: Inspector<Msg>) -> Self {
+        Self {
+            config,
+            visible: false,
+            messages: VecDeque::new(),
+            delivered: 0,
+            view_time: Duration::ZERO,
+            size: None,
+        }
+    }
+
+    /// Whether the panel is shown.
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Toggle the panel if `event` is the toggle key, returning whether it was.
+    ///
+    /// Resize events are noted for the size display.
+    pub fn handle_event(&mut self, event: &Event) -> bool {
+        match event {
+            Event::Key(key) if *key == self.config.toggle => {
+                self.visible = !self.visible;
+                true
+            },
+            Event::Resize { width, height } => {
+                self.size = Some((*width, *height));
+                false
+            },
+            _ => false,
+        }
+    }
+
+    /// Set the terminal size.
+    pub fn set_size(&mut self, size: Option<(u16, u16)>) {
+        self.size = size;
+    }
+
+    /// Log a message about to reach `update`.
+    pub fn record_message(&mut self, msg: &Msg, now: Instant) {
+        self.delivered += 1;
+        if self.config.history == 0 {
+            return;
+        }
+        if self.messages.len() == self.config.history {
+            self.messages.pop_front();
+        }
+        self.messages.push_back((now, (self.config.describe)(msg)));
+    }
+
+    /// Note how long the last `view()` call took.
+    pub fn record_view(&mut self, elapsed: Duration) {
+        self.view_time = elapsed;
+    }
+
+    /// Draw the panel over `content`.
+    ///
+    /// On the alternate screen the panel covers the bottom of the screen;
+    /// inline, it is drawn below the view.
+    pub fn overlay(
+        &self,
+        content: &str,
+        info: &InspectorInfo,
+        now: Instant,
+        alt_screen: bool,
+    ) -> String {
+        // Narrower panels are unreadable anyway; this keeps truncation sane
+        let width = self.size.map_or(80, |(width, _)| usize::from(width)).max(20);
+        let panel = self.panel(info, now, width);
+
+        let mut lines: Vec<&str> = content.split('\n').collect();
+        if alt_screen {
+            if let Some((_, height)) = self.size {
+                let keep = usize::from(height).saturating_sub(panel.len());
+                lines.resize(keep, "");
+            }
+        }
+
+        lines.extend(panel.iter().map(String::as_str));
+        lines.join("\n")
+    }
+
+    /// Lines of the panel, at most `width` cells wide.
+    fn panel(&self, info: &InspectorInfo, now: Instant, width: usize) -> Vec<String> {
+        let dim = Color::BrightBlack.to_ansi_fg();
+        let accent = Color::Magenta.to_ansi_fg();
+        let reset = "\x1b[0m";
+        let fit = |text: String| truncate_text(&text, width - 1, "…");
+
+        let mut lines = Vec::new();
+        let title = format!(" inspector ({}) ", key_label(&self.config.toggle));
+        let rule = "─".repeat(width.saturating_sub(title.chars().count() + 1));
+        lines.push(format!("{}─{}{}{}{}{}", dim, accent, title, dim, rule, reset));
+
+        let size = match self.size {
+            Some((width, height)) => format!("{}x{}", width, height),
+            None => "unknown".to_string(),
+        };
+        lines.push(fit(format!(
+            " size {} · view {} · render {} · frames {} · slow {} · updates {}",
+            size,
+            millis(self.view_time),
+            millis(info.metrics.last_render_time),
+            info.metrics.frames,
+            info.metrics.slow_renders,
+            info.metrics.updates,
+        )));
+
+        let subscriptions = if info.subscriptions.is_empty() {
+            "none".to_string()
+        } else {
+            info.subscriptions
+                .iter()
+                .map(|(id, due)| match due {
+                    Some(due) => format!("{} (in {})", id, millis(*due)),
+                    None => id.clone(),
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        lines.push(fit(format!(" subscriptions: {}", subscriptions)));
+
+        let ticks = match info.ticks.iter().min() {
+            Some(next) => format!("{} pending, next in {}", info.ticks.len(), millis(*next)),
+            None => "none".to_string(),
+        };
+        lines.push(fit(format!(" ticks: {}", ticks)));
+
+        lines.push(format!(" {}messages ({} total, newest last){}", dim, self.delivered, reset));
+        for (at, description) in &self.messages {
+            let age = now.saturating_duration_since(*at);
+            lines.push(fit(format!("   -{:>7} {}", millis(age), description)));
+        }
+        lines
+    }
+}
+
+/// Format a duration as milliseconds.
+fn millis(duration: Duration) -> String {
+    format!("{:.1}ms", duration.as_secs_f64() * 1000.0)
+}
+
+/// Human-readable name of a key, such as `F12` or `Ctrl+D`.
+fn key_label(key: &KeyEvent) -> String {
+    let mut label = String::new();
+    if key.modifiers.ctrl() {
+        label.push_str("Ctrl+");
+    }
+    if key.modifiers.alt() {
+        label.push_str("Alt+");
+    }
+    if key.modifiers.shift() {
+        label.push_str("Shift+");
+    }
+    match key.code {
+        KeyCode::Char(c) => label.push(c.to_ascii_uppercase()),
+        KeyCode::F(n) => label.push_str(&format!("F{}", n)),
+        code => label.push_str(&format!("{:?}", code)),
+    }
+    label
+}
+
+/// The last path segment of a type's name, without generic arguments.
+fn short_type_name<T>() -> &'static str {
+    let name = std::any::type_name::<T>();
+    let base = name.split('<').next().unwrap_or(name);
+    base.rsplit("::").next().unwrap_or(base)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::strip_ansi;
+
+    #[derive(Debug)]
+    enum Msg {
+        Tick,
+        Key(char),
+    }
+
+    fn info() -> InspectorInfo {
+        InspectorInfo {
+            subscriptions: vec![
+                ("clock".to_string(), Some(Duration::from_millis(40))),
+                ("events".to_string(), None),
+            ],
+            ticks: vec![Duration::from_millis(250), Duration::from_millis(120)],
+            metrics: RenderMetrics { frames: 7, ..Default::default() },
+        }
+    }
+
+    #[test]
+    fn test_toggle_and_history() {
+        let mut inspector = InspectorState::new(Inspector::new().history(2).debug_messages());
+        assert!(!inspector.handle_event(&Event::Key(KeyEvent {
+            code: KeyCode::Char('x'),
+            modifiers: KeyModifiers::NONE
+        })));
+        assert!(inspector.handle_event(&Event::Key(KeyEvent {
+            code: KeyCode::F(12),
+            modifiers: KeyModifiers::NONE
+        })));
+        assert!(inspector.is_visible());
+
+        let now = Instant::now();
+        inspector.record_message(&Msg::Tick, now);
+        inspector.record_message(&Msg::Key('a'), now);
+        inspector.record_message(&Msg::Key('b'), now);
+        assert_eq!(inspector.delivered, 3);
+        let logged: Vec<&str> = inspector.messages.iter().map(|(_, m)| m.as_str()).collect();
+        assert_eq!(logged, vec!["Key('a')", "Key('b')"]);
+    }
+
+    #[test]
+    fn test_panel_contents() {
+        let mut inspector = InspectorState::new(Inspector::<Msg>::new());
+        inspector.handle_event(&Event::Resize { width: 100, height: 12 });
+        let now = Instant::now();
+        inspector.record_message(&Msg::Tick, now);
+
+        let output = strip_ansi(&inspector.overlay("app line 1\napp line 2", &info(), now, false));
+        assert!(output.starts_with("app line 1\napp line 2\n─ inspector (F12) ─"));
+        assert!(output.contains("size 100x12"));
+        assert!(output.contains("subscriptions: clock (in 40.0ms), events"));
+        assert!(output.contains("ticks: 2 pending, next in 120.0ms"));
+        // Without Debug, messages are logged by type name
+        assert!(output.contains("-  0.0ms Msg"));
+    }
+
+    #[test]
+    fn test_overlay_covers_bottom_of_alt_screen() {
+        let mut inspector = InspectorState::new(Inspector::<Msg>::new());
+        inspector.set_size(Some((60, 10)));
+
+        let view = (1..=10).map(|n| format!("row {}", n)).collect::<Vec<_>>().join("\n");
+        let output = inspector.overlay(&view, &info(), Instant::now(), true);
+        let lines: Vec<&str> = output.split('\n').collect();
+
+        // Five panel lines replace the last five rows
+        assert_eq!(lines.len(), 10);
+        assert_eq!(lines[4], "row 5");
+        assert!(strip_ansi(lines[5]).starts_with("─ inspector"));
+    }
+}
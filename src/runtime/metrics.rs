@@ -0,0 +1,77 @@
+//! Rendering metrics.
+//!
+//! The event loop coalesces updates into at most one render per frame
+//! interval. These counters show how much work that saves and whether
+//! `view()` is keeping up with the frame rate.
+
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+/// Snapshot of a program's rendering statistics.
+///
+/// Read it from another thread with [`ProgramHandle::metrics`].
+///
+/// [`ProgramHandle::metrics`]: super::ProgramHandle::metrics
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RenderMetrics {
+    /// Messages and events that updated the model.
+    pub updates: u64,
+    /// Updates merged into a frame that was already waiting to be drawn.
+    pub merged_updates: u64,
+    /// Times `view()` was called to render a frame.
+    pub renders: u64,
+    /// Frames written to the terminal (renders whose output changed).
+    pub frames: u64,
+    /// Renders that took longer than one frame interval.
+    pub slow_renders: u64,
+    /// Duration of the most recent render.
+    pub last_render_time: Duration,
+}
+
+/// Counters shared between the program and its handles.
+#[derive(Default)]
+pub(crate) struct MetricsRecorder {
+    updates: AtomicU64,
+    merged_updates: AtomicU64,
+    renders: AtomicU64,
+    frames: AtomicU64,
+    slow_renders: AtomicU64,
+    last_render_nanos: AtomicU64,
+}
+
+impl MetricsRecorder {
+    /// Record an update, noting whether a frame was already pending.
+    pub fn record_update(&self, merged: bool) {
+        self.updates.fetch_add(1, Ordering::Relaxed);
+        if merged {
+            self.merged_updates.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Record a render and how long it took.
+    pub fn record_render(&self, written: bool, elapsed: Duration, frame_duration: Duration) {
+        self.renders.fetch_add(1, Ordering::Relaxed);
+        if written {
+            self.frames.fetch_add(1, Ordering::Relaxed);
+        }
+        if elapsed > frame_duration {
+            self.slow_renders.fetch_add(1, Ordering::Relaxed);
+        }
+        let nanos = u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX);
+        self.last_render_nanos.store(nanos, Ordering::Relaxed);
+    }
+
+    /// Take a snapshot of the counters.
+    pub fn snapshot(&self) -> RenderMetrics {
+        RenderMetrics {
+            updates: self.updates.load(Ordering::Relaxed),
+            merged_updates: self.merged_updates.load(Ordering::Relaxed),
+            renders: self.renders.load(Ordering::Relaxed),
+            frames: self.frames.load(Ordering::Relaxed),
+            slow_renders: self.slow_renders.load(Ordering::Relaxed),
+            last_render_time: Duration::from_nanos(self.last_render_nanos.load(Ordering::Relaxed)),
+        }
+    }
+}
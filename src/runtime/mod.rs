@@ -4,20 +4,52 @@
 //! - [`Model`] - The core trait for application state
 //! - [`Cmd`] - Commands for side effects
 //! - [`Sub`] - Subscriptions for recurring events
+//! - [`Frame`] - A view with cursor placement and window title
 //! - [`Program`] - The runtime that manages the event loop
+//! - [`ProgramHandle`] - Sends messages into a running program from other threads
+//! - [`Inspector`] - Debug overlay showing messages, subscriptions and timings
+//! - [`ProgramError`] - Why a program stopped without completing normally
+//! - [`Signal`] - Process signals (interrupt, terminate, suspend) offered to the model
 //! - [`Accessible`] - Trait for accessible mode support
 
 pub mod accessible;
 pub mod command;
+mod control;
+mod error;
+mod executor;
+mod frame;
+mod guard;
+mod handle;
+mod headless;
+mod inspector;
 mod message;
+mod metrics;
+mod outcome;
+mod pool;
+mod process;
 mod program;
+pub mod recording;
+mod renderer;
+mod signal;
 mod subscription;
+mod watch;
 
-pub use accessible::{Accessible, AccessibleInput};
-pub use command::Cmd;
+pub use accessible::{Accessible, AccessibleAdapter, AccessibleInput};
+pub use command::{CancelToken, Cmd};
+pub use error::{BoxError, ProgramError};
+pub use frame::{Cursor, CursorShape, Frame};
+pub use handle::ProgramHandle;
+pub use headless::{HeadlessExit, NonInteractiveOutput};
+pub use inspector::Inspector;
 pub use message::CommonMsg;
+pub use metrics::RenderMetrics;
+pub use outcome::Outcome;
+pub use process::{ProcessEvent, ProcessHandle};
 pub use program::{Program, ProgramOptions};
-pub use subscription::Sub;
+pub use recording::{Recording, Step, StepKind};
+pub use signal::Signal;
+pub use subscription::{SharedReceiver, Sub};
+pub use watch::FileChange;
 
 /// Module-level command functions (Bubble Tea style).
 ///
@@ -35,7 +67,10 @@ pub use subscription::Sub;
 /// let batch_cmd: teapot::Cmd<Msg> = cmd::batch(vec![cmd::none()]);
 /// ```
 pub mod cmd {
-    pub use super::command::{batch, none, quit, run_process, sequence, tick};
+    pub use super::command::{
+        batch, bell, cancel, cancel_debounce, copy_to_clipboard, debounce, fail, none, notify,
+        println, quit, run_process, sequence, set_title, spawn_process, throttle, tick,
+    };
 }
 
 use crate::terminal::Event;
@@ -150,6 +185,27 @@ pub trait Model: Sized {
     /// The framework handles diffing and efficient updates.
     fn view(&self) -> String;
 
+    /// Render the model as a [`Frame`], with cursor placement and title.
+    ///
+    /// Override this instead of relying on `view()` alone to show the
+    /// terminal's real cursor, for example at the insertion point of a text
+    /// field, or to set the window title. The returned content replaces the
+    /// output of `view()` when the program draws.
+    ///
+    /// The default implementation wraps `view()` with a hidden cursor.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// fn frame(&self) -> Frame {
+    ///     let cursor = self.input.cursor().map(|cursor| cursor.offset(0, 2));
+    ///     Frame::new(self.view()).with_cursor(cursor).title("Sign up")
+    /// }
+    /// ```
+    fn frame(&self) -> Frame {
+        Frame::new(self.view())
+    }
+
     /// Convert terminal events to messages.
     ///
     /// Override this to handle keyboard, mouse, and resize events.
@@ -160,6 +216,19 @@ pub trait Model: Sized {
         None
     }
 
+    /// Convert a process signal to a message.
+    ///
+    /// Called when the program receives SIGINT, SIGTERM, SIGHUP, SIGTSTP or
+    /// SIGCONT, and when Ctrl+C or Ctrl+Z is pressed and `handle_event`
+    /// returned `None` for the key. Return `Some(msg)` to handle the signal
+    /// yourself (for example to confirm before quitting), or `None` for the
+    /// default behaviour described on [`Signal`].
+    ///
+    /// The default implementation returns `None` for every signal.
+    fn handle_signal(&self, _signal: Signal) -> Option<Self::Message> {
+        None
+    }
+
     /// Declare subscriptions for recurring events.
     ///
     /// Subscriptions are re-evaluated after every update. Return different
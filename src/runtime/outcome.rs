@@ -0,0 +1,178 @@
+//! How a program ended.
+
+use std::{any::Any, process::ExitCode};
+
+use super::{ProgramError, Signal};
+
+/// How a program ended, as returned by
+/// [`Program::run_with_outcome`](super::Program::run_with_outcome).
+///
+/// `T` is the type of the value passed to [`Cmd::quit_with`]. Each outcome
+/// maps to a conventional process exit code, so a command-line tool can
+/// finish with `std::process::exit(outcome.exit_code().into())`, or return
+/// the outcome from `main` as an [`ExitCode`].
+///
+/// [`Cmd::quit_with`]: super::Cmd::quit_with
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Outcome<T> {
+    /// The model quit with a value using `Cmd::quit_with`.
+    Completed(T),
+    /// The program quit without a value: `Cmd::quit`, `ProgramHandle::quit`,
+    /// or, without a terminal, nothing was left that could update the model
+    /// or the [`HeadlessExit`](super::HeadlessExit) timeout passed.
+    Quit,
+    /// The model quit with `Cmd::quit_cancelled`, for example when the user
+    /// pressed Esc.
+    Cancelled,
+    /// An interrupt, termination or hangup signal stopped the program, and
+    /// the model did not handle it.
+    Interrupted(Signal),
+    /// The program was terminated while suspended with Ctrl+Z.
+    Killed(Signal),
+}
+
+impl<T> Outcome<T> {
+    /// The value passed to `Cmd::quit_with`, if the program completed.
+    pub fn value(&self) -> Option<&T> {
+        match self {
+            Self::Completed(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Take the value passed to `Cmd::quit_with`, if the program completed.
+    pub fn into_value(self) -> Option<T> {
+        match self {
+            Self::Completed(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Whether the program ended normally, with or without a value.
+    pub fn is_success(&self) -> bool {
+        matches!(self, Self::Completed(_) | Self::Quit)
+    }
+
+    /// The process exit code conventionally used for this outcome.
+    ///
+    /// | Outcome                   | Code                        |
+    /// |---------------------------|-----------------------------|
+    /// | `Completed`, `Quit`       | 0                           |
+    /// | `Cancelled`               | 1                           |
+    /// | `Interrupted`, `Killed`   | 128 + signal number, e.g. 130 for Ctrl+C |
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Self::Completed(_) | Self::Quit => 0,
+            Self::Cancelled => 1,
+            Self::Interrupted(signal) | Self::Killed(signal) => 128 + signal_number(*signal),
+        }
+    }
+}
+
+impl<T> From<Outcome<T>> for ExitCode {
+    fn from(outcome: Outcome<T>) -> Self {
+        ExitCode::from(outcome.exit_code())
+    }
+}
+
+/// The platform's number of a signal that stops the program.
+#[cfg(unix)]
+fn signal_number(signal: Signal) -> u8 {
+    use signal_hook::consts::{SIGCONT, SIGHUP, SIGINT, SIGTERM, SIGTSTP};
+
+    let number = match signal {
+        Signal::Hangup => SIGHUP,
+        Signal::Interrupt => SIGINT,
+        Signal::Terminate => SIGTERM,
+        Signal::Suspend => SIGTSTP,
+        Signal::Resume => SIGCONT,
+    };
+    number as u8
+}
+
+/// The Linux number of a signal that stops the program, for platforms
+/// without POSIX signals.
+#[cfg(not(unix))]
+fn signal_number(signal: Signal) -> u8 {
+    match signal {
+        Signal::Hangup => 1,
+        Signal::Interrupt => 2,
+        Signal::Terminate => 15,
+        Signal::Suspend => 20,
+        Signal::Resume => 18,
+    }
+}
+
+/// Why the event loop stopped, before the quit value is given a type.
+pub(crate) enum Exit {
+    Quit,
+    Value(Box<dyn Any + Send>),
+    Cancelled,
+    Interrupted(Signal),
+    Killed(Signal),
+}
+
+impl Exit {
+    /// The signal that stopped the program, if any.
+    pub fn signal(&self) -> Option<Signal> {
+        match self {
+            Self::Interrupted(signal) | Self::Killed(signal) => Some(*signal),
+            _ => None,
+        }
+    }
+
+    /// Convert to an [`Outcome`], downcasting a `Cmd::quit_with` value.
+    ///
+    /// Fails with [`ProgramError::QuitValueType`] if the model quit with a
+    /// value that is not a `T`.
+    pub fn into_outcome<T: 'static>(self) -> Result<Outcome<T>, ProgramError> {
+        Ok(match self {
+            Self::Quit => Outcome::Quit,
+            Self::Value(value) => match value.downcast::<T>() {
+                Ok(value) => Outcome::Completed(*value),
+                Err(_) => return Err(ProgramError::QuitValueType(std::any::type_name::<T>())),
+            },
+            Self::Cancelled => Outcome::Cancelled,
+            Self::Interrupted(signal) => Outcome::Interrupted(signal),
+            Self::Killed(signal) => Outcome::Killed(signal),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_codes() {
+        assert_eq!(Outcome::Completed("main").exit_code(), 0);
+        assert_eq!(Outcome::<()>::Quit.exit_code(), 0);
+        assert_eq!(Outcome::<()>::Cancelled.exit_code(), 1);
+        assert_eq!(Outcome::<()>::Interrupted(Signal::Interrupt).exit_code(), 130);
+        assert_eq!(Outcome::<()>::Killed(Signal::Terminate).exit_code(), 143);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_exit_codes_use_platform_signal_numbers() {
+        let suspend = 128 + signal_hook::consts::SIGTSTP as u8;
+        assert_eq!(Outcome::<()>::Killed(Signal::Suspend).exit_code(), suspend);
+    }
+
+    #[test]
+    fn test_into_outcome() {
+        let outcome = Exit::Value(Box::new(String::from("main"))).into_outcome::<String>().unwrap();
+        assert_eq!(outcome.value().map(String::as_str), Some("main"));
+        assert!(outcome.is_success());
+
+        assert_eq!(Exit::Cancelled.into_outcome::<String>().unwrap(), Outcome::Cancelled);
+    }
+
+    #[test]
+    fn test_into_outcome_checks_value_type() {
+        let err = Exit::Value(Box::new("main")).into_outcome::<u32>().unwrap_err();
+        assert!(matches!(err, ProgramError::QuitValueType("u32")));
+        assert_eq!(err.to_string(), "Cmd::quit_with value is not a `u32` as expected");
+    }
+}
@@ -0,0 +1,238 @@
+//! Worker pool for blocking commands.
+//!
+//! Commands created with `Cmd::perform` may block (file I/O, blocking HTTP
+//! clients, heavy computation). Running them on the event-loop thread would
+//! freeze rendering and input, so the runtime hands them to a pool of worker
+//! threads and receives their messages through a channel.
+//!
+//! The pool keeps a few workers ready and starts another whenever a job
+//! arrives while all of them are busy, so every blocking command starts
+//! right away. Workers started this way exit after idling for a while.
+
+use std::{
+    sync::{
+        Arc, Mutex, MutexGuard,
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    },
+    thread,
+    time::Duration,
+};
+
+/// A unit of work executed by the pool.
+type Job = Box<dyn FnOnce() + Send>;
+
+/// How long a worker beyond the pool's initial size waits for a job before
+/// exiting.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Pool of worker threads that grows while all workers are busy.
+///
+/// Workers exit once the pool is dropped and the queue has drained.
+pub(crate) struct WorkerPool {
+    jobs: Sender<Job>,
+    shared: Arc<Shared>,
+}
+
+/// State shared between the pool and its workers.
+struct Shared {
+    receiver: Mutex<Receiver<Job>>,
+    workers: Mutex<Workers>,
+}
+
+/// Worker counts, kept under one lock so they stay consistent.
+#[derive(Default)]
+struct Workers {
+    /// Workers waiting for a job that no queued job is meant for
+    idle: usize,
+    /// Workers started so far, used to name new ones
+    started: usize,
+}
+
+impl WorkerPool {
+    /// Minimum number of workers kept ready.
+    const MIN_WORKERS: usize = 4;
+
+    /// Maximum number of workers kept ready; more are started while busy.
+    const MAX_WORKERS: usize = 16;
+
+    /// Create a pool keeping the given number of worker threads ready.
+    pub fn new(size: usize) -> Self {
+        let (jobs, receiver) = mpsc::channel::<Job>();
+        let shared = Arc::new(Shared {
+            receiver: Mutex::new(receiver),
+            workers: Mutex::new(Workers::default()),
+        });
+
+        let pool = Self { jobs, shared };
+        for _ in 0..size.max(1) {
+            pool.shared.lock_workers().idle += 1;
+            pool.spawn_worker(None);
+        }
+        pool
+    }
+
+    /// Create a pool sized to the available parallelism.
+    pub fn with_default_size() -> Self {
+        let size = thread::available_parallelism().map_or(Self::MIN_WORKERS, |n| n.get());
+        Self::new(size.clamp(Self::MIN_WORKERS, Self::MAX_WORKERS))
+    }
+
+    /// Queue a job, starting a new worker for it if all workers are busy.
+    pub fn execute<F>(&self, job: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let busy = {
+            let mut workers = self.shared.lock_workers();
+            match workers.idle.checked_sub(1) {
+                Some(idle) => {
+                    workers.idle = idle;
+                    false
+                },
+                None => true,
+            }
+        };
+
+        if busy {
+            self.spawn_worker(Some(Box::new(job)));
+        } else {
+            let _ = self.jobs.send(Box::new(job));
+        }
+    }
+
+    /// Start a worker running `first`, if given, before waiting for jobs.
+    ///
+    /// Workers started with a job are extras that exit once idle.
+    fn spawn_worker(&self, first: Option<Job>) {
+        let index = {
+            let mut workers = self.shared.lock_workers();
+            workers.started += 1;
+            workers.started - 1
+        };
+        let shared = Arc::clone(&self.shared);
+        let idle_timeout = first.is_some().then_some(IDLE_TIMEOUT);
+        thread::Builder::new()
+            .name(format!("teapot-worker-{}", index))
+            .spawn(move || {
+                if let Some(job) = first {
+                    job();
+                    shared.lock_workers().idle += 1;
+                }
+                worker(&shared, idle_timeout);
+            })
+            .expect("failed to spawn worker thread");
+    }
+}
+
+impl Shared {
+    fn lock_workers(&self) -> MutexGuard<'_, Workers> {
+        self.workers.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Worker loop: run jobs until the pool is dropped, or until no job came
+/// within `idle_timeout`, if given.
+fn worker(shared: &Shared, idle_timeout: Option<Duration>) {
+    loop {
+        // The guard is released as soon as a job has been received
+        let job = {
+            let receiver = shared.receiver.lock().unwrap_or_else(|e| e.into_inner());
+            match idle_timeout {
+                Some(timeout) => receiver.recv_timeout(timeout),
+                None => receiver.recv().map_err(RecvTimeoutError::from),
+            }
+        };
+
+        match job {
+            Ok(job) => {
+                job();
+                shared.lock_workers().idle += 1;
+            },
+            Err(RecvTimeoutError::Timeout) => {
+                // Leave only if no job is on its way to an idle worker
+                let mut workers = shared.lock_workers();
+                if workers.idle > 0 {
+                    workers.idle -= 1;
+                    break;
+                }
+            },
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_pool_runs_jobs() {
+        let pool = WorkerPool::new(2);
+        let (tx, rx) = mpsc::channel();
+
+        for i in 0..8 {
+            let tx = tx.clone();
+            pool.execute(move || tx.send(i).unwrap());
+        }
+
+        let mut results: Vec<i32> =
+            (0..8).map(|_| rx.recv_timeout(Duration::from_secs(1)).unwrap()).collect();
+        results.sort_unstable();
+        assert_eq!(results, (0..8).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_pool_runs_jobs_concurrently() {
+        let pool = WorkerPool::new(2);
+        let (a_tx, a_rx) = mpsc::channel();
+        let (b_tx, b_rx) = mpsc::channel();
+        let (done_tx, done_rx) = mpsc::channel();
+
+        // Each job waits for the other, which only succeeds if both run at once
+        let done = done_tx.clone();
+        pool.execute(move || {
+            a_tx.send(()).unwrap();
+            done.send(b_rx.recv_timeout(Duration::from_secs(1)).is_ok()).unwrap();
+        });
+        pool.execute(move || {
+            b_tx.send(()).unwrap();
+            done_tx.send(a_rx.recv_timeout(Duration::from_secs(1)).is_ok()).unwrap();
+        });
+
+        assert!(done_rx.recv_timeout(Duration::from_secs(2)).unwrap());
+        assert!(done_rx.recv_timeout(Duration::from_secs(2)).unwrap());
+    }
+
+    #[test]
+    fn test_pool_grows_while_busy() {
+        let pool = WorkerPool::new(1);
+        let (done_tx, done_rx) = mpsc::channel();
+
+        // Each job waits for all the others, which only succeeds if every
+        // job gets a worker although the pool started with one
+        let senders: Vec<_> = (0..4).map(|_| mpsc::channel::<()>()).collect();
+        let (txs, rxs): (Vec<_>, Vec<_>) = senders.into_iter().unzip();
+        for (index, rx) in rxs.into_iter().enumerate() {
+            let txs: Vec<_> = txs
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| *i != index)
+                .map(|(_, tx)| tx.clone())
+                .collect();
+            let done = done_tx.clone();
+            pool.execute(move || {
+                for tx in &txs {
+                    tx.send(()).unwrap();
+                }
+                let all = (0..3).all(|_| rx.recv_timeout(Duration::from_secs(1)).is_ok());
+                done.send(all).unwrap();
+            });
+        }
+
+        for _ in 0..4 {
+            assert!(done_rx.recv_timeout(Duration::from_secs(2)).unwrap());
+        }
+    }
+}
@@ -0,0 +1,249 @@
+//! Background processes started with `Cmd::spawn_process`.
+//!
+//! Unlike `Cmd::run_process`, which hands the terminal to the child, these
+//! processes run alongside the program. Their stdout and stderr are read on
+//! background threads and delivered to the model line by line.
+
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    panic::{self, AssertUnwindSafe},
+    process::{Child, ChildStdin, Command, ExitStatus, Stdio},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::Duration,
+};
+
+use super::{
+    command::CancelToken,
+    program::{EventSender, RuntimeEvent, panic_message},
+};
+
+/// Callback turning process events into messages.
+pub(crate) type EventFn<M> = Arc<dyn Fn(ProcessEvent) -> M + Send + Sync>;
+
+/// How often the exit status is checked once the output pipes have closed.
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// An event from a process started with `Cmd::spawn_process`.
+///
+/// Every process produces `Started` first and `Exited` last, with its
+/// output lines in between. Lines from stdout and stderr are each delivered
+/// in order, but the two streams may interleave arbitrarily.
+#[derive(Debug)]
+pub enum ProcessEvent {
+    /// The process started; the handle writes to its stdin or kills it.
+    Started(ProcessHandle),
+    /// A line written to stdout, without the line ending.
+    Stdout(String),
+    /// A line written to stderr, without the line ending.
+    Stderr(String),
+    /// The process exited, or could not be started.
+    Exited(io::Result<ExitStatus>),
+}
+
+/// Handle to a process started with `Cmd::spawn_process`.
+///
+/// Store it in the model (from [`ProcessEvent::Started`]) to send input to
+/// the process or stop it. Cloning the handle refers to the same process.
+#[derive(Clone)]
+pub struct ProcessHandle {
+    id: u32,
+    child: Arc<Mutex<Child>>,
+    stdin: Arc<Mutex<Option<ChildStdin>>>,
+    finished: Arc<AtomicBool>,
+}
+
+impl ProcessHandle {
+    /// The operating system's process id.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Write bytes to the process's stdin.
+    ///
+    /// Fails with [`io::ErrorKind::BrokenPipe`] once stdin has been closed.
+    pub fn write(&self, data: impl AsRef<[u8]>) -> io::Result<()> {
+        let mut stdin = self.stdin.lock().unwrap_or_else(|e| e.into_inner());
+        match stdin.as_mut() {
+            Some(stdin) => {
+                stdin.write_all(data.as_ref())?;
+                stdin.flush()
+            },
+            None => Err(io::Error::new(io::ErrorKind::BrokenPipe, "stdin is closed")),
+        }
+    }
+
+    /// Write a line to the process's stdin, appending a newline.
+    pub fn write_line(&self, line: &str) -> io::Result<()> {
+        self.write(format!("{}\n", line))
+    }
+
+    /// Close the process's stdin, signalling end of input.
+    pub fn close_stdin(&self) {
+        self.stdin.lock().unwrap_or_else(|e| e.into_inner()).take();
+    }
+
+    /// Kill the process.
+    ///
+    /// Its remaining output is still delivered, followed by
+    /// [`ProcessEvent::Exited`]. Killing a process that has already exited
+    /// does nothing.
+    pub fn kill(&self) -> io::Result<()> {
+        if self.is_finished() {
+            return Ok(());
+        }
+        self.child.lock().unwrap_or_else(|e| e.into_inner()).kill()
+    }
+
+    /// Whether the process has exited.
+    pub fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::SeqCst)
+    }
+}
+
+impl std::fmt::Debug for ProcessHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProcessHandle").field("id", &self.id).finish_non_exhaustive()
+    }
+}
+
+/// Spawn a process and start streaming its events into `tx`.
+///
+/// Output is delivered as [`RuntimeEvent::Output`]; the final exit event is
+/// delivered as a completion for `sequence`. All events carry `cancel`, so
+/// the program can drop them once the command is cancelled. Returns the
+/// handle if the process started.
+pub(crate) fn spawn<M: Send + 'static>(
+    mut command: Command,
+    on_event: EventFn<M>,
+    tx: EventSender<M>,
+    sequence: Option<u64>,
+    cancel: Option<CancelToken>,
+) -> Option<ProcessHandle> {
+    let spawned =
+        command.stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn();
+
+    let mut child = match spawned {
+        Ok(child) => child,
+        Err(err) => {
+            let exited = ProcessEvent::Exited(Err(err));
+            let _ = tx.send(completion(&on_event, exited, sequence, cancel));
+            return None;
+        },
+    };
+
+    let stdin = child.stdin.take();
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    let handle = ProcessHandle {
+        id: child.id(),
+        child: Arc::new(Mutex::new(child)),
+        stdin: Arc::new(Mutex::new(stdin)),
+        finished: Arc::new(AtomicBool::new(false)),
+    };
+
+    // Sent before any reader starts, so it is always the first event
+    let _ = tx.send(output(&on_event, ProcessEvent::Started(handle.clone()), &cancel));
+
+    let stderr_reader = {
+        let on_event = Arc::clone(&on_event);
+        let tx = tx.clone();
+        let cancel = cancel.clone();
+        thread::Builder::new()
+            .name("teapot-process-stderr".to_string())
+            .spawn(move || forward_lines(stderr, ProcessEvent::Stderr, &on_event, &tx, &cancel))
+            .ok()
+    };
+
+    let monitor = handle.clone();
+    let monitor_tx = tx.clone();
+    let spawned = thread::Builder::new().name("teapot-process".to_string()).spawn(move || {
+        let tx = monitor_tx;
+        forward_lines(stdout, ProcessEvent::Stdout, &on_event, &tx, &cancel);
+        if let Some(reader) = stderr_reader {
+            let _ = reader.join();
+        }
+
+        let status = wait(&monitor);
+        monitor.finished.store(true, Ordering::SeqCst);
+        monitor.close_stdin();
+        let _ = tx.send(completion(&on_event, ProcessEvent::Exited(status), sequence, cancel));
+    });
+
+    if let Err(err) = spawned {
+        let _ = handle.kill();
+        let _ = tx.send(RuntimeEvent::Panicked {
+            message: format!("failed to spawn process monitor: {}", err),
+        });
+    }
+
+    Some(handle)
+}
+
+/// Deliver each line read from `pipe` until it closes.
+fn forward_lines<M>(
+    pipe: Option<impl Read>,
+    event: fn(String) -> ProcessEvent,
+    on_event: &EventFn<M>,
+    tx: &EventSender<M>,
+    cancel: &Option<CancelToken>,
+) {
+    let Some(pipe) = pipe else {
+        return;
+    };
+
+    let mut reader = BufReader::new(pipe);
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {
+                let line = String::from_utf8_lossy(&buf);
+                let line = line.trim_end_matches(['\n', '\r']).to_string();
+                if tx.send(output(on_event, event(line), cancel)).is_err() {
+                    break;
+                }
+            },
+        }
+    }
+}
+
+/// Wait for the process to exit without holding its lock, so it can still
+/// be killed while running.
+fn wait(handle: &ProcessHandle) -> io::Result<ExitStatus> {
+    loop {
+        if let Some(status) = handle.child.lock().unwrap_or_else(|e| e.into_inner()).try_wait()? {
+            return Ok(status);
+        }
+        thread::sleep(EXIT_POLL_INTERVAL);
+    }
+}
+
+/// Event delivering a message produced while the process runs.
+fn output<M>(
+    on_event: &EventFn<M>,
+    event: ProcessEvent,
+    cancel: &Option<CancelToken>,
+) -> RuntimeEvent<M> {
+    match panic::catch_unwind(AssertUnwindSafe(|| on_event(event))) {
+        Ok(msg) => RuntimeEvent::Output { msg, cancel: cancel.clone() },
+        Err(payload) => RuntimeEvent::Panicked { message: panic_message(payload.as_ref()) },
+    }
+}
+
+/// Event completing the background work tracked for the process.
+fn completion<M>(
+    on_event: &EventFn<M>,
+    event: ProcessEvent,
+    sequence: Option<u64>,
+    cancel: Option<CancelToken>,
+) -> RuntimeEvent<M> {
+    match panic::catch_unwind(AssertUnwindSafe(|| on_event(event))) {
+        Ok(msg) => RuntimeEvent::Message { msg, sequence, cancel },
+        Err(payload) => RuntimeEvent::Panicked { message: panic_message(payload.as_ref()) },
+    }
+}
@@ -1,29 +1,209 @@
 //! Program runner that manages the event loop.
 
 use std::{
-    collections::HashMap,
-    io::{self, Write},
+    any::Any,
+    collections::{HashMap, VecDeque},
+    future::{self, Future},
+    io::{self, BufRead, Write},
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+    sync::{
+        Arc, OnceLock,
+        mpsc::{self, Receiver, SendError, Sender},
+    },
+    thread,
     time::{Duration, Instant},
 };
 
-use crossterm::{
-    cursor,
-    event::{self, Event as CrosstermEvent, KeyEventKind},
-    execute,
-    terminal::{self, ClearType},
+#[cfg(feature = "tokio")]
+use crossterm::event;
+
+use super::{
+    Accessible, Model,
+    accessible::{AccessibleAdapter, strip_ansi},
+    command::{AsyncFn, BlockingFn, CancelToken, CmdResult},
+    error::{BoxError, ProgramError},
+    executor::{CatchUnwind, Executor, TaskHandle},
+    guard::{PanicHook, Terminal, TerminalModes},
+    handle::ProgramHandle,
+    headless::{HeadlessExit, HeadlessPrinter, NonInteractiveOutput},
+    inspector::{Inspector, InspectorInfo, InspectorState},
+    metrics::MetricsRecorder,
+    outcome::{Exit, Outcome},
+    pool::WorkerPool,
+    process::{self, ProcessHandle},
+    recording::{Recorder, StepKind},
+    renderer::{self, Renderer},
+    signal::{self, Signal, SignalListener},
+    subscription::{MakeStream, RecvFn, SubEntry, SubKind},
+    watch::{FileChange, PathWatcher},
 };
+use crate::{
+    Cmd,
+    terminal::{
+        Backend, CrosstermBackend, Event, InputSource, KeyCode, MemoryBackend, OutputStream,
+        Terminals, Waker,
+    },
+};
+
+/// A timer waiting to fire, from `Cmd::tick` or `Cmd::debounce`.
+struct PendingTick<M> {
+    /// When the timer fires
+    scheduled: Instant,
+    action: TickAction<M>,
+    /// Token of the key the timer was started under, if any
+    cancel: Option<CancelToken>,
+}
 
-use super::{Model, command::CmdResult, subscription::SubEntry};
-use crate::{Cmd, terminal::Event};
+/// What happens when a pending tick fires.
+enum TickAction<M> {
+    /// Deliver a message
+    Message(Box<dyn Fn(Instant) -> M + Send>),
+    /// Run the last command debounced under `key`
+    Debounced { key: String, cmd: Box<Cmd<M>> },
+}
 
-/// Type alias for pending tick entries: (scheduled_time, interval, message_generator)
-type PendingTick<M> = (Instant, Duration, Box<dyn Fn(Instant) -> M + Send>);
+impl<M> PendingTick<M> {
+    /// Whether this is the debounced command for `key`.
+    fn is_debounced(&self, key: &str) -> bool {
+        matches!(&self.action, TickAction::Debounced { key: debounced, .. } if debounced == key)
+    }
+}
 
-/// Active subscription with next scheduled fire time
+/// Active subscription, keyed by id in the program
 struct ActiveSub<M> {
-    next_fire: Instant,
-    interval: Duration,
-    msg_fn: Box<dyn Fn() -> M + Send>,
+    /// Distinguishes this activation from earlier ones with the same id
+    token: u64,
+    source: ActiveSource<M>,
+}
+
+/// Message source of an active subscription
+enum ActiveSource<M> {
+    /// Interval timer with next scheduled fire time
+    Interval { next_fire: Instant, interval: Duration, msg_fn: Box<dyn Fn() -> M + Send> },
+    /// Channel forwarded by a background thread until the token is cancelled
+    Channel(CancelToken),
+    /// Stream driven on the executor
+    Stream(TaskHandle),
+    /// File watcher polled on the event loop
+    Watch {
+        watcher: PathWatcher,
+        next_check: Instant,
+        poll_interval: Duration,
+        msg_fn: Box<dyn Fn(FileChange) -> M + Send>,
+    },
+}
+
+impl<M> Drop for ActiveSub<M> {
+    fn drop(&mut self) {
+        // Stop forwarding the channel or stream once the subscription goes away
+        match &self.source {
+            ActiveSource::Channel(cancel) => cancel.cancel(),
+            ActiveSource::Stream(task) => task.cancel(),
+            ActiveSource::Interval { .. } | ActiveSource::Watch { .. } => {},
+        }
+    }
+}
+
+/// Longest time the event loop waits for terminal input while messages may
+/// arrive from background commands, program handles, or channel and stream
+/// subscriptions, and the loop cannot be woken.
+const MESSAGE_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Events delivered to the event loop from background work and handles.
+pub(crate) enum RuntimeEvent<M> {
+    /// A background command produced its message.
+    ///
+    /// `sequence` identifies the command sequence waiting on this result;
+    /// the message is discarded if `cancel` was cancelled meanwhile.
+    Message { msg: M, sequence: Option<u64>, cancel: Option<CancelToken> },
+    /// A background command was cancelled before producing its message.
+    Cancelled { sequence: Option<u64> },
+    /// A fallible background command failed.
+    Failed { error: BoxError, sequence: Option<u64>, cancel: Option<CancelToken> },
+    /// A background command panicked before producing its message.
+    Panicked { message: String },
+    /// A background process produced output.
+    Output { msg: M, cancel: Option<CancelToken> },
+    /// A stream subscription produced a message.
+    Subscription { id: String, token: u64, msg: M },
+    /// A message injected through a [`ProgramHandle`].
+    External(M),
+    /// A [`ProgramHandle`] requested the program to quit.
+    Quit,
+    /// A [`ProgramHandle`] requested a full repaint.
+    Repaint,
+    /// A [`ProgramHandle`] was dropped.
+    HandleDropped,
+}
+
+/// Sends [`RuntimeEvent`]s to the event loop, waking it while it waits for
+/// terminal input.
+pub(crate) struct EventSender<M> {
+    tx: Sender<RuntimeEvent<M>>,
+    /// Set once the loop knows how to be woken
+    waker: Arc<OnceLock<Waker>>,
+}
+
+impl<M> EventSender<M> {
+    /// Send an event and wake the event loop.
+    pub fn send(&self, event: RuntimeEvent<M>) -> Result<(), SendError<RuntimeEvent<M>>> {
+        self.tx.send(event)?;
+        if let Some(waker) = self.waker.get() {
+            waker.wake();
+        }
+        Ok(())
+    }
+}
+
+impl<M> Clone for EventSender<M> {
+    fn clone(&self) -> Self {
+        Self { tx: self.tx.clone(), waker: Arc::clone(&self.waker) }
+    }
+}
+
+/// A command sequence waiting on background work before continuing.
+struct PendingSequence<M> {
+    /// Background commands still running for the current step
+    pending: usize,
+    /// Commands still to run, in order
+    rest: VecDeque<Cmd<M>>,
+    /// Enclosing sequence, when this sequence is itself a step of another
+    parent: Option<u64>,
+    /// Whether this sequence is counted as pending work of its parent
+    counted: bool,
+    /// Key the sequence was started under, if any
+    scope: Option<Scope>,
+}
+
+/// The key a command runs under, from [`Cmd::keyed`].
+#[derive(Clone)]
+struct Scope {
+    key: String,
+    token: CancelToken,
+}
+
+/// Work running under a key, stopped when the key is cancelled.
+#[derive(Default)]
+struct KeyedWork {
+    token: CancelToken,
+    tasks: Vec<TaskHandle>,
+    processes: Vec<ProcessHandle>,
+}
+
+/// Reports an async command dropped before it produced its message, so the
+/// program stops waiting for it.
+struct CancelGuard<M> {
+    tx: Option<EventSender<M>>,
+    sequence: Option<u64>,
+}
+
+impl<M> Drop for CancelGuard<M> {
+    fn drop(&mut self) {
+        if let Some(tx) = self.tx.take() {
+            let _ = tx.send(RuntimeEvent::Cancelled { sequence: self.sequence });
+        }
+    }
 }
 
 /// Options for configuring the program runtime.
@@ -55,6 +235,30 @@ pub struct ProgramOptions {
 
     /// Tick duration for models that want periodic updates.
     pub tick_rate: Duration,
+
+    /// Wrap each frame in synchronized-update sequences so the terminal
+    /// draws it atomically.
+    ///
+    /// Enabled by default, except on dumb terminals and the Linux console.
+    pub synchronized_output: bool,
+
+    /// Record incoming events to this file for later replay.
+    pub record: Option<PathBuf>,
+
+    /// How the view is written when there is no terminal.
+    pub non_interactive_output: NonInteractiveOutput,
+
+    /// When the program stops without a terminal, if the model does not
+    /// quit.
+    pub headless_exit: HeadlessExit,
+
+    /// Where the UI is drawn. Plain-text output without a terminal goes to
+    /// the same stream, or to stderr for [`OutputStream::Tty`].
+    pub output: OutputStream,
+
+    /// Where keys are read from. Accessible mode reads its answers from the
+    /// same source.
+    pub input: InputSource,
 }
 
 impl Default for ProgramOptions {
@@ -69,6 +273,12 @@ impl Default for ProgramOptions {
             respect_no_color: true,
             reduce_motion: std::env::var("REDUCE_MOTION").is_ok(),
             tick_rate: Duration::from_millis(100),
+            synchronized_output: renderer::supports_synchronized_output(),
+            record: None,
+            non_interactive_output: NonInteractiveOutput::default(),
+            headless_exit: HeadlessExit::default(),
+            output: OutputStream::default(),
+            input: InputSource::default(),
         }
     }
 }
@@ -83,6 +293,17 @@ impl ProgramOptions {
     pub fn inline() -> Self {
         Self::default()
     }
+
+    /// Whether a program with these options can run interactively: its
+    /// input and output are terminals, and it is not running in CI.
+    pub fn is_interactive(&self) -> bool {
+        std::env::var("CI").is_err() && self.is_interactive_in(&Terminals::detect())
+    }
+
+    /// Whether the chosen input and output are terminals in `terminals`.
+    fn is_interactive_in(&self, terminals: &Terminals) -> bool {
+        terminals.has_input(self.input) && terminals.has_output(self.output)
+    }
 }
 
 /// A message filter function that can modify or block messages.
@@ -91,6 +312,9 @@ impl ProgramOptions {
 /// or `None` to block the message from reaching the model.
 pub type MessageFilter<M, Msg> = Box<dyn Fn(&M, Msg) -> Option<Msg> + Send>;
 
+/// Asks the model before quitting; see [`Program::with_quit_confirmation`].
+type QuitConfirmation<M, Msg> = Box<dyn Fn(&M) -> Option<Msg> + Send>;
+
 /// The program runtime that manages the event loop.
 ///
 /// The program orchestrates:
@@ -133,14 +357,109 @@ pub type MessageFilter<M, Msg> = Box<dyn Fn(&M, Msg) -> Option<Msg> + Send>;
 pub struct Program<M: Model> {
     model: M,
     options: ProgramOptions,
-    last_view: String,
+    renderer: Renderer,
     filter: Option<MessageFilter<M, M::Message>>,
+    confirm_quit: Option<QuitConfirmation<M, M::Message>>,
+    pending_ticks: Vec<PendingTick<M::Message>>,
+    active_subs: HashMap<String, ActiveSub<M::Message>>,
+    next_sub_token: u64,
+    sequences: HashMap<u64, PendingSequence<M::Message>>,
+    next_sequence_id: u64,
+    /// Number of background commands whose messages have not arrived yet
+    in_flight: usize,
+    /// Work running under each key
+    keys: HashMap<String, KeyedWork>,
+    /// Key of the command being processed, if any
+    scope: Option<Scope>,
+    /// End of the current window of each throttled key
+    throttled: HashMap<String, Instant>,
+    events_tx: EventSender<M::Message>,
+    events_rx: Receiver<RuntimeEvent<M::Message>>,
+    /// Cloned into every [`ProgramHandle`] to track whether any are alive
+    handle_token: Arc<()>,
+    /// Created on first use by an async command
+    executor: Option<Executor>,
+    /// Runtime that async commands and streams are spawned on, in `run_async`
+    #[cfg(feature = "tokio")]
+    runtime: Option<tokio::runtime::Handle>,
+    /// Created on first use by a blocking command
+    pool: Option<WorkerPool>,
+    /// Background processes, killed when the program exits
+    processes: Vec<ProcessHandle>,
+    /// Listens for process signals while the program runs
+    signals: Option<SignalListener>,
+    /// Where frames are drawn and input is read; restores the terminal
+    /// when dropped, while it is set up
+    terminal: Terminal,
+    /// Whether the backend was given with `with_backend` rather than being
+    /// the process's own terminal
+    custom_backend: bool,
+    /// Whether the terminal was torn down for a suspend and awaits resuming
+    suspended: bool,
+    /// Whether the model changed since the last frame was drawn
+    needs_render: bool,
+    /// When the last frame was drawn
+    last_render: Option<Instant>,
+    /// Rendering statistics, shared with handles
+    metrics: Arc<MetricsRecorder>,
+    /// Virtual clock used by the test harness instead of the system clock
+    clock: Option<Instant>,
+    /// Whether the program runs without a terminal, writing plain text
+    headless: bool,
+    /// Writes incoming events to the recording file, when recording
+    recorder: Option<Recorder>,
+    /// Debug overlay, when enabled
+    inspector: Option<InspectorState<M::Message>>,
+    /// Drives the model with line-based prompts in accessible mode
+    accessible: Option<AccessibleAdapter<M>>,
+    /// Why the program stopped, if it did not complete normally
+    failure: Option<ProgramError>,
+    /// How the model asked to quit, or the signal that stopped it
+    exit: Option<Exit>,
 }
 
 impl<M: Model> Program<M> {
     /// Create a new program with the given model.
     pub fn new(model: M) -> Self {
-        Self { model, options: ProgramOptions::default(), last_view: String::new(), filter: None }
+        let (events_tx, events_rx) = mpsc::channel();
+        Self {
+            model,
+            options: ProgramOptions::default(),
+            renderer: Renderer::new(false, false),
+            filter: None,
+            confirm_quit: None,
+            pending_ticks: Vec::new(),
+            active_subs: HashMap::new(),
+            next_sub_token: 0,
+            sequences: HashMap::new(),
+            next_sequence_id: 0,
+            in_flight: 0,
+            keys: HashMap::new(),
+            scope: None,
+            throttled: HashMap::new(),
+            events_tx: EventSender { tx: events_tx, waker: Arc::default() },
+            events_rx,
+            handle_token: Arc::new(()),
+            executor: None,
+            #[cfg(feature = "tokio")]
+            runtime: None,
+            pool: None,
+            processes: Vec::new(),
+            signals: None,
+            terminal: Terminal::new(Box::new(CrosstermBackend::new())),
+            custom_backend: false,
+            suspended: false,
+            needs_render: false,
+            last_render: None,
+            metrics: Arc::default(),
+            clock: None,
+            headless: false,
+            recorder: None,
+            inspector: None,
+            accessible: None,
+            failure: None,
+            exit: None,
+        }
     }
 
     /// Configure the program with custom options.
@@ -149,6 +468,18 @@ impl<M: Model> Program<M> {
         self
     }
 
+    /// Get a handle for sending messages into the program from other threads.
+    ///
+    /// Call this before `run()`. The handle can be cloned and moved to other
+    /// threads; see [`ProgramHandle`] for details.
+    pub fn handle(&self) -> ProgramHandle<M::Message> {
+        ProgramHandle::new(
+            self.events_tx.clone(),
+            Arc::clone(&self.handle_token),
+            Arc::clone(&self.metrics),
+        )
+    }
+
     /// Add a message filter.
     ///
     /// The filter function is called before each message reaches the model's
@@ -188,6 +519,35 @@ impl<M: Model> Program<M> {
         self
     }
 
+    /// Ask the model before quitting.
+    ///
+    /// The hook is called when `Cmd::quit`, `Cmd::quit_with`,
+    /// `Cmd::quit_cancelled` or `ProgramHandle::quit` would stop the program.
+    /// If it returns a message, the quit is abandoned and the message is
+    /// delivered to `update` instead, for example to show a confirmation
+    /// prompt. Once the user confirms, the model quits again; the hook must
+    /// then return `None`, or the program never exits.
+    ///
+    /// Signals are not confirmed; use [`Model::handle_signal`] for those.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// Program::new(editor)
+    ///     .with_quit_confirmation(|editor| {
+    ///         (editor.has_unsaved_changes() && !editor.discard_confirmed)
+    ///             .then_some(Msg::ConfirmDiscard)
+    ///     })
+    ///     .run()
+    /// ```
+    pub fn with_quit_confirmation<F>(mut self, confirm: F) -> Self
+    where
+        F: Fn(&M) -> Option<M::Message> + Send + 'static,
+    {
+        self.confirm_quit = Some(Box::new(confirm));
+        self
+    }
+
     /// Enable alternate screen mode.
     pub fn with_alt_screen(mut self) -> Self {
         self.options.alt_screen = true;
@@ -233,6 +593,15 @@ impl<M: Model> Program<M> {
         self
     }
 
+    /// Drive the model with line-based prompts in accessible mode.
+    ///
+    /// See [`AccessibleAdapter`]. Without an adapter, accessible mode writes
+    /// the view as plain text but takes no input.
+    pub fn with_accessible_adapter(mut self, adapter: AccessibleAdapter<M>) -> Self {
+        self.accessible = Some(adapter);
+        self
+    }
+
     /// Enable reduced motion mode.
     ///
     /// When enabled, animations and spinners are disabled or simplified.
@@ -242,18 +611,122 @@ impl<M: Model> Program<M> {
         self
     }
 
+    /// Disable synchronized output.
+    ///
+    /// Use this for terminals that print the synchronized-update sequences
+    /// instead of ignoring them.
+    pub fn without_synchronized_output(mut self) -> Self {
+        self.options.synchronized_output = false;
+        self
+    }
+
+    /// Record every terminal event and timer firing to a file.
+    ///
+    /// The recording can be replayed against the same model with
+    /// [`TestProgram::replay`] to reproduce a session exactly, for example
+    /// from a bug report. See the [`recording`](super::recording) module for
+    /// the file format.
+    ///
+    /// [`TestProgram::replay`]: crate::testing::TestProgram::replay
+    pub fn record_to(mut self, path: impl Into<PathBuf>) -> Self {
+        self.options.record = Some(path.into());
+        self
+    }
+
+    /// Enable the debug inspector.
+    ///
+    /// Pressing the inspector's toggle key (F12 by default) shows a panel
+    /// over the view with recent messages, active subscriptions, pending
+    /// ticks, render timings and the terminal size.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// Program::new(app)
+    ///     .with_inspector(Inspector::new().debug_messages())
+    ///     .run()?;
+    /// ```
+    pub fn with_inspector(mut self, inspector: Inspector<M::Message>) -> Self {
+        self.inspector = Some(InspectorState::new(inspector));
+        self
+    }
+
+    /// Set how the view is written when there is no terminal.
+    ///
+    /// Defaults to [`NonInteractiveOutput::ChangedLines`] every 500ms.
+    pub fn with_non_interactive_output(mut self, output: NonInteractiveOutput) -> Self {
+        self.options.non_interactive_output = output;
+        self
+    }
+
+    /// Set when the program stops without a terminal, if the model does not
+    /// quit.
+    ///
+    /// By default it stops once nothing is left that could update the model.
+    pub fn with_headless_exit(mut self, exit: HeadlessExit) -> Self {
+        self.options.headless_exit = exit;
+        self
+    }
+
+    /// Run on `backend` instead of the process's own terminal.
+    ///
+    /// The program is always interactive on a custom backend, even when
+    /// stdin and stdout are not terminals. It installs no signal or panic
+    /// handlers and ignores Ctrl+Z, so several programs can run at once on
+    /// separate threads, for example one per SSH session.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let backend = MemoryBackend::new(40, 10);
+    /// backend.push_event(Event::Key(KeyEvent { code: KeyCode::Esc, modifiers: KeyModifiers::NONE }));
+    ///
+    /// let model = Program::new(app).with_backend(backend.clone()).run()?;
+    /// assert!(backend.output().contains("Pick a flavor"));
+    /// ```
+    pub fn with_backend(mut self, backend: impl Backend + 'static) -> Self {
+        self.terminal = Terminal::new(Box::new(backend));
+        self.custom_backend = true;
+        self
+    }
+
+    /// Draw the UI on `output` instead of stdout.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// // `cat list.txt | pick | xargs ...`: keys come from the terminal,
+    /// // the picker is drawn on stderr and stdout carries the choice
+    /// let (_, outcome) = Program::new(picker)
+    ///     .with_input(InputSource::Tty)
+    ///     .with_output(OutputStream::Stderr)
+    ///     .run_with_outcome::<String>()?;
+    /// if let Some(choice) = outcome.value() {
+    ///     println!("{choice}");
+    /// }
+    /// ```
+    pub fn with_output(mut self, output: OutputStream) -> Self {
+        self.options.output = output;
+        self
+    }
+
+    /// Read keys from `input` instead of stdin.
+    pub fn with_input(mut self, input: InputSource) -> Self {
+        self.options.input = input;
+        self
+    }
+
     /// Set the tick rate for periodic updates.
     pub fn with_tick_rate(mut self, duration: Duration) -> Self {
         self.options.tick_rate = duration;
         self
     }
 
-    /// Check if running in an interactive terminal.
+    /// Check if running in an interactive terminal, reading from stdin and
+    /// drawing on stdout; see [`ProgramOptions::is_interactive`] for other
+    /// streams.
     pub fn is_interactive() -> bool {
-        use std::io::IsTerminal;
-        std::io::stdin().is_terminal()
-            && std::io::stdout().is_terminal()
-            && std::env::var("CI").is_err()
+        ProgramOptions::default().is_interactive()
     }
 
     /// Apply the message filter if one is set.
@@ -269,210 +742,962 @@ impl<M: Model> Program<M> {
 
     /// Run the program, blocking until it exits.
     ///
-    /// Returns the final model state.
-    pub fn run(mut self) -> io::Result<M> {
-        if !Self::is_interactive() || self.options.accessible {
-            return self.run_non_interactive();
+    /// Without an interactive terminal (in CI, or with piped input or
+    /// output) the program still runs headlessly; see
+    /// [`ProgramOptions::non_interactive_output`].
+    ///
+    /// Returns the final model state, or a [`ProgramError`] if the program
+    /// failed, panicked or was interrupted. Use [`Program::run_with_outcome`]
+    /// to also learn how the model quit.
+    pub fn run(self) -> Result<M, ProgramError> {
+        let (model, exit) = self.run_to_exit()?;
+        match exit.signal() {
+            Some(signal) => Err(ProgramError::Interrupted(signal)),
+            None => Ok(model),
         }
+    }
 
-        self.setup_terminal()?;
-        let result = self.run_interactive();
-        self.teardown_terminal()?;
+    /// Run the program like [`Program::run`], returning the final model and
+    /// how the program ended.
+    ///
+    /// `T` is the type of the value the model passes to [`Cmd::quit_with`];
+    /// a value of another type fails the run with
+    /// [`ProgramError::QuitValueType`]. An unhandled interrupt is reported as
+    /// [`Outcome::Interrupted`] rather than as an error.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// let (_, outcome) = Program::new(picker).run_with_outcome::<String>()?;
+    /// if let Some(branch) = outcome.value() {
+    ///     println!("{}", branch);
+    /// }
+    /// std::process::exit(outcome.exit_code().into());
+    /// ```
+    ///
+    /// [`Cmd::quit_with`]: super::Cmd::quit_with
+    pub fn run_with_outcome<T: 'static>(self) -> Result<(M, Outcome<T>), ProgramError> {
+        let (model, exit) = self.run_to_exit()?;
+        Ok((model, exit.into_outcome()?))
+    }
 
-        result.map(|_| self.model)
+    /// Run the program on the caller's tokio runtime until it exits.
+    ///
+    /// Like [`Program::run`], but terminal input is read from crossterm's
+    /// `EventStream` and the loop awaits instead of blocking the thread.
+    /// Async commands and stream subscriptions are spawned on the current
+    /// runtime, so they can use its timers and I/O; blocking commands still
+    /// run on the worker pool. The runtime must have its timer enabled.
+    ///
+    /// The returned future can be raced against others, such as a shutdown
+    /// signal: dropping it restores the terminal. In accessible mode with an
+    /// adapter, prompts are read from stdin and block the calling thread, as
+    /// with `run`.
+    ///
+    /// Requires the `tokio` feature.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// tokio::select! {
+    ///     result = Program::new(app).run_async() => {
+    ///         result?;
+    ///     },
+    ///     _ = tokio::signal::ctrl_c() => {},
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside a tokio runtime.
+    #[cfg(feature = "tokio")]
+    pub async fn run_async(self) -> Result<M, ProgramError> {
+        let (model, exit) = self.run_to_exit_async().await?;
+        match exit.signal() {
+            Some(signal) => Err(ProgramError::Interrupted(signal)),
+            None => Ok(model),
+        }
     }
 
-    /// Run in interactive mode with full TUI.
-    fn run_interactive(&mut self) -> io::Result<()> {
-        let mut stdout = io::stdout();
-        let frame_duration = Duration::from_secs(1) / self.options.fps;
+    /// Run the program like [`Program::run_async`], returning the final
+    /// model and how the program ended; see [`Program::run_with_outcome`].
+    ///
+    /// Requires the `tokio` feature.
+    #[cfg(feature = "tokio")]
+    pub async fn run_async_with_outcome<T: 'static>(self) -> Result<(M, Outcome<T>), ProgramError> {
+        let (model, exit) = self.run_to_exit_async().await?;
+        Ok((model, exit.into_outcome()?))
+    }
 
-        // Create pending_ticks before init so Cmd::tick works from init()
-        let mut pending_ticks: Vec<PendingTick<M::Message>> = Vec::new();
-        let mut active_subs: HashMap<String, ActiveSub<M::Message>> = HashMap::new();
+    /// Run in whichever mode suits the environment until the program exits.
+    fn run_to_exit(mut self) -> Result<(M, Exit), ProgramError> {
+        if self.options.accessible {
+            return match self.accessible.take() {
+                Some(adapter) => self.run_accessible(adapter),
+                None => self.run_headless(),
+            };
+        }
+        if !self.custom_backend && !self.options.is_interactive() {
+            return self.run_headless();
+        }
 
-        // Run init command (may schedule ticks)
-        if let Some(cmd) = self.model.init() {
-            if self.process_command_with_ticks(cmd, &mut pending_ticks)? {
-                return Ok(());
+        let _panic_hook = self.enter_terminal()?;
+        let result = panic::catch_unwind(AssertUnwindSafe(|| self.run_interactive()));
+        self.conclude_interactive(result)
+    }
+
+    /// Run on the current tokio runtime until the program exits.
+    #[cfg(feature = "tokio")]
+    async fn run_to_exit_async(mut self) -> Result<(M, Exit), ProgramError> {
+        self.runtime = Some(tokio::runtime::Handle::current());
+        let wake = self.notify_on_wake();
+
+        if self.options.accessible {
+            if let Some(adapter) = self.accessible.take() {
+                return self.run_accessible(adapter);
             }
         }
+        if self.options.accessible || (!self.custom_backend && !self.options.is_interactive()) {
+            let mut out = self.options.output.plain_writer();
+            let result = CatchUnwind::new(self.headless_loop_async(&mut out, &wake)).await;
+            return self.conclude(result);
+        }
 
-        // Initial render
-        self.render(&mut stdout)?;
+        let _panic_hook = self.enter_terminal()?;
+        let result = CatchUnwind::new(self.run_interactive_async(&wake)).await;
+        self.conclude_interactive(result)
+    }
 
-        // Initialize subscriptions
-        self.refresh_subscriptions(&mut active_subs);
+    /// Install signal handling and set the terminal up for the TUI.
+    ///
+    /// The returned hook restores the terminal if the program panics; keep
+    /// it alive until the terminal is torn down. Signals and panics are
+    /// process-wide, so neither is handled for a custom backend.
+    fn enter_terminal(&mut self) -> io::Result<Option<PanicHook>> {
+        let panic_hook = if self.custom_backend {
+            if let Some(waker) = self.terminal.backend().waker() {
+                self.set_waker(waker);
+            }
+            None
+        } else {
+            self.terminal =
+                Terminal::new(Box::new(CrosstermBackend::with_output(self.options.output)?));
+            if let Some(waker) = self.terminal.backend().waker() {
+                self.set_waker(waker);
+            }
+
+            // Listen for signals before touching the terminal, so a signal
+            // arriving at any point still lets us restore it
+            self.signals = Some(SignalListener::install(self.events_tx.waker.get().cloned())?);
+
+            // Restore the terminal before a panic message is printed
+            Some(PanicHook::for_terminal(
+                TerminalModes::from_options(&self.options),
+                self.options.output,
+                self.terminal.active(),
+            ))
+        };
+
+        self.renderer = Renderer::new(self.options.alt_screen, self.options.synchronized_output);
+        self.setup_terminal()?;
+        Ok(panic_hook)
+    }
+
+    /// Run in interactive mode with full TUI.
+    fn run_interactive(&mut self) -> io::Result<()> {
+        if !self.start_interactive()? {
+            self.event_loop()?;
+        }
+        self.finish_interactive()
+    }
+
+    /// Run in interactive mode, awaiting terminal input.
+    #[cfg(feature = "tokio")]
+    async fn run_interactive_async(&mut self, wake: &tokio::sync::Notify) -> io::Result<()> {
+        if !self.start_interactive()? {
+            if self.custom_backend {
+                self.backend_loop_async(wake).await?;
+            } else {
+                self.event_loop_async(wake).await?;
+            }
+        }
+        self.finish_interactive()
+    }
 
+    /// Process events until the program quits, like [`Program::event_loop`],
+    /// awaiting terminal input instead of polling for it.
+    #[cfg(feature = "tokio")]
+    async fn event_loop_async(&mut self, wake: &tokio::sync::Notify) -> io::Result<()> {
+        let mut events = event::EventStream::new();
         loop {
-            let now = Instant::now();
-            let mut messages = Vec::new();
-            let mut needs_sub_refresh = false;
-
-            // Check for pending ticks (from Cmd::tick)
-            pending_ticks.retain(|(scheduled, _, msg_fn)| {
-                if now >= *scheduled {
-                    messages.push(msg_fn(now));
-                    false
-                } else {
-                    true
-                }
-            });
+            let now = self.now();
+            if self.step(now)? {
+                return Ok(());
+            }
 
-            // Check for subscription fires
-            for sub in active_subs.values_mut() {
-                if now >= sub.next_fire {
-                    messages.push((sub.msg_fn)());
-                    sub.next_fire = now + sub.interval;
-                }
+            let timeout = self.poll_timeout(now);
+            let next = future::poll_fn(|cx| {
+                futures_core::Stream::poll_next(std::pin::Pin::new(&mut events), cx)
+            });
+            tokio::select! {
+                event = next => match event {
+                    Some(event) => {
+                        if self.handle_terminal_event(Event::from(event?))? {
+                            return Ok(());
+                        }
+                    },
+                    // The terminal's input is gone; nothing more can arrive
+                    None => return Ok(()),
+                },
+                () = tokio::time::sleep(timeout) => {},
+                () = wake.notified() => {},
             }
+        }
+    }
 
-            // Process accumulated messages (applying filter)
-            for msg in messages {
-                // Apply the message filter
-                let msg = match self.apply_filter(msg) {
-                    Some(m) => m,
-                    None => continue, // Message was blocked
-                };
+    /// Process events from a custom backend until the program quits.
+    ///
+    /// Backends can only be polled with a timeout, so input is checked
+    /// without waiting and the loop sleeps briefly in between, waking early
+    /// for messages.
+    #[cfg(feature = "tokio")]
+    async fn backend_loop_async(&mut self, wake: &tokio::sync::Notify) -> io::Result<()> {
+        loop {
+            let now = self.now();
+            if self.step(now)? {
+                return Ok(());
+            }
 
-                if let Some(cmd) = self.model.update(msg) {
-                    if self.process_command_with_ticks(cmd, &mut pending_ticks)? {
-                        return Ok(());
-                    }
+            while let Some(quit) = self.poll_input(Duration::ZERO)? {
+                if quit {
+                    return Ok(());
                 }
-                needs_sub_refresh = true;
-                self.render(&mut stdout)?;
             }
 
-            // Refresh subscriptions if model was updated
-            if needs_sub_refresh {
-                self.refresh_subscriptions(&mut active_subs);
+            let timeout = self.poll_timeout(self.now()).min(MESSAGE_POLL_INTERVAL);
+            tokio::select! {
+                () = tokio::time::sleep(timeout) => {},
+                () = wake.notified() => {},
             }
+        }
+    }
 
-            // Calculate poll timeout (min of ticks, subs, and frame duration)
-            let tick_timeout = pending_ticks
-                .iter()
-                .map(|(scheduled, ..)| scheduled.saturating_duration_since(now))
-                .min();
+    /// Start recording, run the init command and draw the first frame.
+    ///
+    /// Returns true if the program should quit.
+    fn start_interactive(&mut self) -> io::Result<bool> {
+        let size = self.terminal.backend().size().ok();
+        if let Some(path) = &self.options.record {
+            self.recorder = Some(Recorder::create(path, self.now(), size)?);
+        }
+        if let Some(inspector) = &mut self.inspector {
+            inspector.set_size(size);
+        }
 
-            let sub_timeout =
-                active_subs.values().map(|sub| sub.next_fire.saturating_duration_since(now)).min();
+        // Run init command (may schedule ticks)
+        if self.start()? {
+            return Ok(true);
+        }
 
-            let timeout = [tick_timeout, sub_timeout]
-                .into_iter()
-                .flatten()
-                .min()
-                .unwrap_or(frame_duration)
-                .min(frame_duration);
+        // Initial render
+        self.render(self.now())?;
+        Ok(false)
+    }
 
-            // Poll for events
-            if event::poll(timeout)? {
-                let crossterm_event = event::read()?;
-                let event = Event::from(crossterm_event.clone());
-
-                // Convert to message and update (applying filter)
-                if let Some(msg) = self.model.handle_event(event) {
-                    // Apply the message filter
-                    if let Some(msg) = self.apply_filter(msg) {
-                        if let Some(cmd) = self.model.update(msg) {
-                            if self.process_command_with_ticks(cmd, &mut pending_ticks)? {
-                                return Ok(());
-                            }
-                        }
-                        // Refresh subscriptions after update
-                        self.refresh_subscriptions(&mut active_subs);
-                        self.render(&mut stdout)?;
-                    }
-                }
+    /// Show the final state before the terminal is restored.
+    fn finish_interactive(&mut self) -> io::Result<()> {
+        if self.needs_render {
+            self.render(self.now())?;
+        }
+        Ok(())
+    }
 
-                // Handle special events
-                match crossterm_event {
-                    CrosstermEvent::Resize(..) => {
-                        self.render(&mut stdout)?;
-                    },
-                    CrosstermEvent::Key(key) if key.kind == KeyEventKind::Press => {
-                        // Ctrl+C handling as fallback
-                        if key.code == crossterm::event::KeyCode::Char('c')
-                            && key.modifiers.contains(crossterm::event::KeyModifiers::CONTROL)
-                        {
-                            // Check if the model handled it
-                            // If not, we could force quit here
-                        }
-                    },
-                    _ => {},
-                }
+    /// Process events until the program quits.
+    ///
+    /// Updates from timers, subscriptions and background work are coalesced
+    /// into at most one render per frame interval; terminal input is
+    /// rendered immediately to keep typing responsive.
+    fn event_loop(&mut self) -> io::Result<()> {
+        loop {
+            let now = self.now();
+            if self.step(now)? {
+                return Ok(());
+            }
+
+            // Poll for events
+            let timeout = self.poll_timeout(now);
+            if self.poll_input(timeout)? == Some(true) {
+                return Ok(());
             }
         }
     }
 
-    /// Refresh active subscriptions based on current model state.
-    fn refresh_subscriptions(&self, active_subs: &mut HashMap<String, ActiveSub<M::Message>>) {
-        let new_subs = self.model.subscriptions();
-        let entries: Vec<SubEntry<M::Message>> = new_subs.into_entries();
+    /// Wait up to `timeout` for an event from the backend and handle it.
+    ///
+    /// Returns `None` if no event arrived, otherwise whether the program
+    /// should quit. The program quits once the backend's input has ended.
+    fn poll_input(&mut self, timeout: Duration) -> io::Result<Option<bool>> {
+        match self.terminal.backend().poll_event(timeout) {
+            Ok(Some(event)) => self.handle_terminal_event(event).map(Some),
+            Ok(None) => Ok(None),
+            // Nothing more can arrive, as when a remote session disconnects
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                self.exit = Some(Exit::Quit);
+                Ok(Some(true))
+            },
+            Err(err) => Err(err),
+        }
+    }
 
-        // Collect new subscription IDs
-        let new_ids: std::collections::HashSet<_> = entries.iter().map(|e| e.id.clone()).collect();
+    /// Handle signals, deliver everything ready at `now` and draw a frame
+    /// if one is due. Returns true if the program should quit.
+    fn step(&mut self, now: Instant) -> io::Result<bool> {
+        // Handle process signals
+        for signal in self.signals.as_ref().map(SignalListener::take_pending).unwrap_or_default() {
+            if self.handle_signal(signal)? {
+                return Ok(true);
+            }
+            self.refresh_subscriptions();
+            self.request_render();
+        }
 
-        // Remove subscriptions that are no longer active
-        active_subs.retain(|id, _| new_ids.contains(id));
+        // Process due timers, subscriptions and background results
+        if self.process_pending(now, &mut |program| {
+            program.request_render();
+            Ok(())
+        })? {
+            return Ok(true);
+        }
 
-        // Add or update subscriptions
-        let now = Instant::now();
-        for entry in entries {
-            active_subs.entry(entry.id).or_insert_with(|| ActiveSub {
-                next_fire: now + entry.interval,
-                interval: entry.interval,
-                msg_fn: entry.msg_fn,
-            });
+        if self.needs_render && self.next_frame().is_none_or(|frame| now >= frame) {
+            self.render(now)?;
         }
+        Ok(false)
     }
 
-    /// Process a command, returning true if we should quit.
-    fn process_command(&mut self, cmd: Cmd<M::Message>) -> io::Result<bool> {
-        let mut pending_ticks = Vec::new();
-        self.process_command_with_ticks(cmd, &mut pending_ticks)
+    /// Handle terminal input and draw the result immediately.
+    ///
+    /// Returns true if the program should quit.
+    fn handle_terminal_event(&mut self, event: Event) -> io::Result<bool> {
+        if self.handle_input(event)? {
+            return Ok(true);
+        }
+        // Refresh subscriptions after update
+        self.refresh_subscriptions();
+        self.request_render();
+        self.render(self.now())?;
+        Ok(false)
+    }
+
+    /// Run the model's init command and start its subscriptions.
+    ///
+    /// Returns true if the program should quit.
+    pub(crate) fn start(&mut self) -> io::Result<bool> {
+        if let Some(cmd) = self.model.init() {
+            if self.process_command(cmd)? {
+                return Ok(true);
+            }
+        }
+
+        self.refresh_subscriptions();
+        Ok(false)
     }
 
-    /// Process a command with tick handling.
-    fn process_command_with_ticks(
+    /// Deliver everything that is ready at `now`: due ticks, subscription
+    /// messages and results from background work.
+    ///
+    /// `after_update` runs after each message reaches the model (the event
+    /// loop uses it to render). Returns true if the program should quit.
+    pub(crate) fn process_pending(
         &mut self,
-        cmd: Cmd<M::Message>,
-        pending_ticks: &mut Vec<PendingTick<M::Message>>,
+        now: Instant,
+        after_update: &mut dyn FnMut(&mut Self) -> io::Result<()>,
     ) -> io::Result<bool> {
-        match cmd.execute() {
-            CmdResult::None => Ok(false),
-            CmdResult::Quit => Ok(true),
-            CmdResult::Message(msg) => {
-                if let Some(next_cmd) = self.model.update(msg) {
-                    self.process_command_with_ticks(next_cmd, pending_ticks)
-                } else {
+        let mut updated = false;
+
+        // Only timers are replayed from a recording; messages from channels
+        // and watched paths are left out rather than recorded as ticks
+        let timers_fired = self.timers_due(now);
+        let due = self.collect_due_messages(now);
+        let commands = self.take_due_commands(now);
+        if timers_fired || !commands.is_empty() {
+            if let Some(recorder) = &mut self.recorder {
+                recorder.record(now, StepKind::Tick)?;
+            }
+        }
+
+        // Run debounced commands that were not replaced in time
+        for cmd in commands {
+            if self.process_command(cmd)? {
+                return Ok(true);
+            }
+            updated = true;
+        }
+
+        // Process accumulated messages (applying filter)
+        for msg in due {
+            if self.deliver(msg)? {
+                return Ok(true);
+            }
+            updated = true;
+            after_update(self)?;
+        }
+
+        // Deliver results from background commands
+        while let Ok(event) = self.events_rx.try_recv() {
+            if self.handle_runtime_event(event)? {
+                return Ok(true);
+            }
+            updated = true;
+            after_update(self)?;
+        }
+
+        // Refresh subscriptions if model was updated
+        if updated {
+            self.refresh_subscriptions();
+        }
+
+        self.prune_keys();
+        Ok(false)
+    }
+
+    /// Handle a terminal event, returning true if we should quit.
+    ///
+    /// Ctrl+C and Ctrl+Z fall back to [`Signal::Interrupt`] and
+    /// [`Signal::Suspend`] when the model ignores them.
+    pub(crate) fn handle_input(&mut self, event: Event) -> io::Result<bool> {
+        // The inspector's toggle key is not passed on, nor recorded
+        if let Some(inspector) = &mut self.inspector {
+            if inspector.handle_event(&event) {
+                return Ok(false);
+            }
+        }
+
+        let now = self.now();
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record(now, StepKind::Event(event.clone()))?;
+        }
+
+        let fallback = key_signal(&event);
+
+        // The terminal may have reflowed the frame
+        if let Event::Resize { .. } = event {
+            self.renderer.invalidate();
+        }
+
+        // Convert to message and update (applying filter)
+        match (self.model.handle_event(event), fallback) {
+            (Some(msg), _) => self.deliver(msg),
+            (None, Some(signal)) => self.handle_signal(signal),
+            (None, None) => Ok(false),
+        }
+    }
+
+    /// Wait up to `timeout` for background work to produce an event, then
+    /// handle it. Returns true if the program should quit.
+    pub(crate) fn wait_for_event(&mut self, timeout: Duration) -> io::Result<bool> {
+        match self.events_rx.recv_timeout(timeout) {
+            Ok(event) => {
+                let quit = self.handle_runtime_event(event)?;
+                if !quit {
+                    self.refresh_subscriptions();
+                }
+                Ok(quit)
+            },
+            Err(_) => Ok(false),
+        }
+    }
+
+    /// Drive the program from a virtual clock set to `now` instead of the
+    /// system clock. A program with a virtual clock never touches the terminal.
+    pub(crate) fn set_clock(&mut self, now: Instant) {
+        self.clock = Some(now);
+    }
+
+    /// Wake the event loop with `waker` when messages arrive, unless it
+    /// already has a way to be woken.
+    fn set_waker(&self, waker: Waker) {
+        let _ = self.events_tx.waker.set(waker);
+    }
+
+    /// Wake the event loop through the returned notifier, for loops awaiting
+    /// on the tokio runtime.
+    #[cfg(feature = "tokio")]
+    fn notify_on_wake(&self) -> Arc<tokio::sync::Notify> {
+        let wake = Arc::new(tokio::sync::Notify::new());
+        let notify = Arc::clone(&wake);
+        self.set_waker(Waker::new(move || notify.notify_one()));
+        wake
+    }
+
+    /// Whether the event loop is woken as soon as background work, a
+    /// handle or a signal has something for it, rather than having to check.
+    fn can_wake(&self) -> bool {
+        // Without a terminal the loop waits on the event channel itself
+        self.is_headless() || self.events_tx.waker.get().is_some()
+    }
+
+    /// Whether the program runs without a terminal (under the test harness
+    /// or non-interactively).
+    fn is_headless(&self) -> bool {
+        self.clock.is_some() || self.headless
+    }
+
+    /// Whether nothing is left that could send the model a message, apart
+    /// from the sources the headless exit policy ignores.
+    fn is_idle(&self) -> bool {
+        let policy = &self.options.headless_exit;
+        self.in_flight == 0
+            && self.pending_ticks.is_empty()
+            && self.active_subs.values().all(|sub| {
+                policy.ignore_intervals && matches!(sub.source, ActiveSource::Interval { .. })
+            })
+            && (policy.ignore_handles || Arc::strong_count(&self.handle_token) == 1)
+    }
+
+    /// The current time, from the virtual clock when one is set.
+    pub(crate) fn now(&self) -> Instant {
+        self.clock.unwrap_or_else(Instant::now)
+    }
+
+    /// The earliest time a tick or subscription is due, if any.
+    pub(crate) fn next_due(&self) -> Option<Instant> {
+        let ticks = self.pending_ticks.iter().map(|tick| tick.scheduled);
+        let subs = self.active_subs.values().filter_map(|sub| match &sub.source {
+            ActiveSource::Interval { next_fire, .. } => Some(*next_fire),
+            ActiveSource::Watch { next_check, .. } => Some(*next_check),
+            _ => None,
+        });
+
+        ticks.chain(subs).min()
+    }
+
+    /// Take the lines printed with `Cmd::println` that were not drawn yet.
+    pub(crate) fn take_printed(&mut self) -> Vec<String> {
+        self.renderer.take_printed()
+    }
+
+    /// The lines printed with `Cmd::println` that the next frame draws.
+    pub(crate) fn queued_printed(&self) -> &[String] {
+        self.renderer.printed()
+    }
+
+    /// Draw frames on `backend` from a blank screen, as in interactive mode,
+    /// while the test harness drives the program.
+    pub(crate) fn draw_on(&mut self, backend: MemoryBackend) {
+        self.terminal = Terminal::new(Box::new(backend));
+        self.renderer = Renderer::new(self.options.alt_screen, false);
+    }
+
+    /// The model being run.
+    pub(crate) fn model(&self) -> &M {
+        &self.model
+    }
+
+    /// Why the program stopped, if it failed or was interrupted.
+    pub(crate) fn failure(&self) -> Option<&ProgramError> {
+        self.failure.as_ref()
+    }
+
+    /// Consume the program, returning the model.
+    pub(crate) fn into_model(self) -> M {
+        self.model
+    }
+
+    /// Whether a tick or interval subscription delivers a message at `now`.
+    fn timers_due(&self, now: Instant) -> bool {
+        let ticks = self
+            .pending_ticks
+            .iter()
+            .any(|tick| now >= tick.scheduled && matches!(tick.action, TickAction::Message(_)));
+        ticks
+            || self.active_subs.values().any(|sub| {
+                matches!(sub.source, ActiveSource::Interval { next_fire, .. } if now >= next_fire)
+            })
+    }
+
+    /// Collect messages from ticks and subscriptions that are due at `now`.
+    fn collect_due_messages(&mut self, now: Instant) -> Vec<M::Message> {
+        let mut messages = Vec::new();
+
+        // Check for pending ticks (from Cmd::tick)
+        self.pending_ticks.retain(|tick| match &tick.action {
+            TickAction::Message(msg_fn) if now >= tick.scheduled => {
+                messages.push(msg_fn(now));
+                false
+            },
+            _ => true,
+        });
+
+        // Check for subscription fires
+        for sub in self.active_subs.values_mut() {
+            match &mut sub.source {
+                ActiveSource::Interval { next_fire, interval, msg_fn } => {
+                    if now >= *next_fire {
+                        messages.push(msg_fn());
+                        *next_fire = now + *interval;
+                    }
+                },
+                ActiveSource::Watch { watcher, next_check, poll_interval, msg_fn } => {
+                    if now >= *next_check {
+                        messages.extend(watcher.poll(now).into_iter().map(&*msg_fn));
+                        // Check again early if a debounced change becomes ready first
+                        *next_check = watcher
+                            .next_flush()
+                            .map_or(now + *poll_interval, |flush| flush.min(now + *poll_interval));
+                    }
+                },
+                ActiveSource::Channel(_) | ActiveSource::Stream(_) => {},
+            }
+        }
+
+        messages
+    }
+
+    /// Take the debounced commands whose wait is over at `now`.
+    fn take_due_commands(&mut self, now: Instant) -> Vec<Cmd<M::Message>> {
+        let (due, pending): (Vec<_>, Vec<_>) =
+            std::mem::take(&mut self.pending_ticks).into_iter().partition(|tick| {
+                now >= tick.scheduled && matches!(tick.action, TickAction::Debounced { .. })
+            });
+        self.pending_ticks = pending;
+
+        due.into_iter()
+            .filter_map(|tick| match tick.action {
+                TickAction::Debounced { cmd, .. } => Some(*cmd),
+                TickAction::Message(_) => None,
+            })
+            .collect()
+    }
+
+    /// Calculate how long to wait for terminal input.
+    ///
+    /// This is the time until the next tick or subscription fires or a
+    /// pending frame is due. Messages from background commands, program
+    /// handles, and channel and stream subscriptions wake the loop; if it
+    /// cannot be woken, the wait is capped at the frame duration and kept
+    /// short while any of them may send.
+    fn poll_timeout(&self, now: Instant) -> Duration {
+        let can_wake = self.can_wake();
+        let mut timeout =
+            self.next_due().map_or(Duration::MAX, |due| due.saturating_duration_since(now));
+        if !can_wake {
+            timeout = timeout.min(self.frame_duration());
+        }
+
+        // Wake up in time to draw a pending frame
+        if self.needs_render {
+            let frame = self
+                .next_frame()
+                .map_or(Duration::ZERO, |frame| frame.saturating_duration_since(now));
+            timeout = timeout.min(frame);
+        }
+
+        let external_sources = !can_wake
+            && (self.in_flight > 0
+                || Arc::strong_count(&self.handle_token) > 1
+                || self.active_subs.values().any(|sub| {
+                    matches!(sub.source, ActiveSource::Channel(_) | ActiveSource::Stream(_))
+                }));
+
+        if external_sources { timeout.min(MESSAGE_POLL_INTERVAL) } else { timeout }
+    }
+
+    /// Refresh active subscriptions based on current model state.
+    fn refresh_subscriptions(&mut self) {
+        let new_subs = self.model.subscriptions();
+        let entries: Vec<SubEntry<M::Message>> = new_subs.into_entries();
+
+        // Collect new subscription IDs
+        let new_ids: std::collections::HashSet<_> = entries.iter().map(|e| e.id.clone()).collect();
+
+        // Remove subscriptions that are no longer active
+        self.active_subs.retain(|id, _| new_ids.contains(id));
+
+        // Start subscriptions that are not already running
+        let now = self.now();
+        for entry in entries {
+            if self.active_subs.contains_key(&entry.id) {
+                continue;
+            }
+
+            let token = self.next_sub_token;
+            self.next_sub_token += 1;
+
+            let source = match entry.kind {
+                SubKind::Interval { interval, msg_fn } => {
+                    ActiveSource::Interval { next_fire: now + interval, interval, msg_fn }
+                },
+                SubKind::Channel(recv) => {
+                    ActiveSource::Channel(self.spawn_channel(entry.id.clone(), token, recv))
+                },
+                SubKind::Stream(make) => {
+                    ActiveSource::Stream(self.spawn_stream(entry.id.clone(), token, make))
+                },
+                SubKind::Watch(watch) => ActiveSource::Watch {
+                    watcher: PathWatcher::new(watch.path, watch.debounce),
+                    next_check: now + watch.poll_interval,
+                    poll_interval: watch.poll_interval,
+                    msg_fn: watch.msg_fn,
+                },
+            };
+            self.active_subs.insert(entry.id, ActiveSub { token, source });
+        }
+    }
+
+    /// Forward a channel subscription's messages from a background thread,
+    /// which stops once the returned token is cancelled.
+    fn spawn_channel(&self, id: String, token: u64, recv: RecvFn<M::Message>) -> CancelToken {
+        let cancel = CancelToken::default();
+        let tx = self.events_tx.clone();
+        let forward = {
+            let cancel = cancel.clone();
+            move || {
+                let items = tx.clone();
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    while let Some(msg) = recv(&cancel) {
+                        let event = RuntimeEvent::Subscription { id: id.clone(), token, msg };
+                        if items.send(event).is_err() {
+                            break;
+                        }
+                    }
+                }));
+
+                if let Err(payload) = result {
+                    let _ = tx
+                        .send(RuntimeEvent::Panicked { message: panic_message(payload.as_ref()) });
+                }
+            }
+        };
+
+        let spawned = thread::Builder::new().name("teapot-channel".to_string()).spawn(forward);
+        if let Err(err) = spawned {
+            let _ = self.events_tx.send(RuntimeEvent::Panicked {
+                message: format!("failed to spawn channel subscription thread: {}", err),
+            });
+        }
+        cancel
+    }
+
+    /// Drive a stream subscription on the executor, forwarding its items.
+    fn spawn_stream(&mut self, id: String, token: u64, make: MakeStream<M::Message>) -> TaskHandle {
+        let tx = self.events_tx.clone();
+        self.spawn_task(async move {
+            let items = tx.clone();
+            let result = CatchUnwind::new(async move {
+                let mut stream = make();
+                while let Some(msg) = future::poll_fn(|cx| stream.as_mut().poll_next(cx)).await {
+                    let event = RuntimeEvent::Subscription { id: id.clone(), token, msg };
+                    if items.send(event).is_err() {
+                        break;
+                    }
+                }
+            })
+            .await;
+
+            if let Err(payload) = result {
+                let _ =
+                    tx.send(RuntimeEvent::Panicked { message: panic_message(payload.as_ref()) });
+            }
+        })
+    }
+
+    /// Deliver a message to the model, returning true if we should quit.
+    ///
+    /// The message filter is applied first; commands returned by `update`
+    /// are processed immediately.
+    pub(crate) fn deliver(&mut self, msg: M::Message) -> io::Result<bool> {
+        let Some(msg) = self.apply_filter(msg) else {
+            return Ok(false); // Message was blocked
+        };
+
+        let now = self.now();
+        if let Some(inspector) = &mut self.inspector {
+            inspector.record_message(&msg, now);
+        }
+
+        match self.model.update(msg) {
+            Some(cmd) => self.process_command(cmd),
+            None => Ok(false),
+        }
+    }
+
+    /// Handle a process signal, returning true if we should quit.
+    ///
+    /// The model gets the first chance to handle the signal; otherwise the
+    /// default behaviour documented on [`Signal`] applies.
+    fn handle_signal(&mut self, signal: Signal) -> io::Result<bool> {
+        if signal == Signal::Resume && self.suspended {
+            // The terminal must be usable again whatever the model decides
+            self.resume()?;
+        }
+
+        if let Some(msg) = self.model.handle_signal(signal) {
+            return self.deliver(msg);
+        }
+
+        match signal {
+            Signal::Interrupt | Signal::Terminate | Signal::Hangup => {
+                // A stopped job is usually killed with SIGTERM followed by SIGCONT
+                self.exit = Some(if self.suspended {
+                    Exit::Killed(signal)
+                } else {
+                    Exit::Interrupted(signal)
+                });
+                Ok(true)
+            },
+            Signal::Suspend => {
+                self.suspend()?;
+                Ok(false)
+            },
+            Signal::Resume => Ok(false),
+        }
+    }
+
+    /// Restore the terminal and stop the process until it is continued.
+    ///
+    /// The terminal is set up again when the resulting SIGCONT is handled.
+    fn suspend(&mut self) -> io::Result<()> {
+        if !signal::can_suspend() || self.is_headless() || self.custom_backend {
+            return Ok(());
+        }
+
+        self.teardown_terminal()?;
+        self.suspended = true;
+        signal::suspend_process()
+    }
+
+    /// Set the terminal up again after the process was continued.
+    fn resume(&mut self) -> io::Result<()> {
+        self.setup_terminal()?;
+        self.suspended = false;
+
+        // Whatever was on screen before is gone; redraw from scratch
+        self.renderer.reset();
+        Ok(())
+    }
+
+    /// Handle an event sent by background work, returning true if we should quit.
+    fn handle_runtime_event(&mut self, event: RuntimeEvent<M::Message>) -> io::Result<bool> {
+        match event {
+            RuntimeEvent::Message { msg, sequence, cancel } => {
+                self.in_flight = self.in_flight.saturating_sub(1);
+                // Results of cancelled work are dropped
+                if !is_cancelled(&cancel) && self.deliver(msg)? {
+                    return Ok(true);
+                }
+                match sequence {
+                    Some(id) => self.complete_sequence_step(id),
+                    None => Ok(false),
+                }
+            },
+            RuntimeEvent::Cancelled { sequence } => {
+                self.in_flight = self.in_flight.saturating_sub(1);
+                match sequence {
+                    Some(id) => self.complete_sequence_step(id),
+                    None => Ok(false),
+                }
+            },
+            RuntimeEvent::Failed { error, sequence, cancel } => {
+                self.in_flight = self.in_flight.saturating_sub(1);
+                if !is_cancelled(&cancel) {
+                    self.failure = Some(ProgramError::User(error));
+                    return Ok(true);
+                }
+                match sequence {
+                    Some(id) => self.complete_sequence_step(id),
+                    None => Ok(false),
+                }
+            },
+            RuntimeEvent::Panicked { message } => {
+                self.failure = Some(ProgramError::Panic(message));
+                Ok(true)
+            },
+            RuntimeEvent::Subscription { id, token, msg } => {
+                // Drop items that raced with the subscription being removed
+                match self.active_subs.get(&id) {
+                    Some(sub) if sub.token == token => self.deliver(msg),
+                    _ => Ok(false),
+                }
+            },
+            RuntimeEvent::Output { msg, cancel } => {
+                if is_cancelled(&cancel) {
                     Ok(false)
+                } else {
+                    self.deliver(msg)
                 }
             },
+            RuntimeEvent::External(msg) => self.deliver(msg),
+            RuntimeEvent::Quit => self.request_exit(Exit::Quit),
+            RuntimeEvent::Repaint => {
+                // Force a full redraw of the current frame
+                self.renderer.invalidate();
+                Ok(false)
+            },
+            // Only wakes the loop to notice it may have become idle
+            RuntimeEvent::HandleDropped => Ok(false),
+        }
+    }
+
+    /// Process a command, returning true if we should quit.
+    fn process_command(&mut self, cmd: Cmd<M::Message>) -> io::Result<bool> {
+        self.process_command_in(cmd, None)
+    }
+
+    /// Process a command as a step of the given sequence (if any).
+    ///
+    /// Background work spawned by the command is counted against the
+    /// sequence, which only moves on once all of it has completed.
+    fn process_command_in(
+        &mut self,
+        cmd: Cmd<M::Message>,
+        sequence: Option<u64>,
+    ) -> io::Result<bool> {
+        match cmd.execute() {
+            CmdResult::None => Ok(false),
+            CmdResult::Quit(exit) => self.request_exit(exit),
+            CmdResult::Perform(f) => {
+                self.spawn_blocking(f, sequence);
+                Ok(false)
+            },
             CmdResult::Tick { duration, msg_fn } => {
-                let scheduled = Instant::now() + duration;
-                pending_ticks.push((scheduled, duration, msg_fn));
+                self.pending_ticks.push(PendingTick {
+                    scheduled: self.now() + duration,
+                    action: TickAction::Message(msg_fn),
+                    cancel: self.scope.as_ref().map(|scope| scope.token.clone()),
+                });
                 Ok(false)
             },
-            CmdResult::Batch(cmds) => {
-                for cmd in cmds {
-                    if self.process_command_with_ticks(cmd, pending_ticks)? {
-                        return Ok(true);
-                    }
-                }
+            CmdResult::Debounce { key, duration, cmd } => {
+                // Restart the wait, replacing the command
+                self.pending_ticks.retain(|tick| !tick.is_debounced(&key));
+                self.pending_ticks.push(PendingTick {
+                    scheduled: self.now() + duration,
+                    action: TickAction::Debounced { key, cmd: Box::new(cmd) },
+                    cancel: self.scope.as_ref().map(|scope| scope.token.clone()),
+                });
                 Ok(false)
             },
-            CmdResult::Sequence(cmds) => {
+            CmdResult::Throttle { key, duration, cmd } => {
+                let now = self.now();
+                self.throttled.retain(|_, until| *until > now);
+                if self.throttled.contains_key(&key) {
+                    return Ok(false);
+                }
+                self.throttled.insert(key, now + duration);
+                self.process_command_in(cmd, sequence)
+            },
+            CmdResult::Batch(cmds) => {
                 for cmd in cmds {
-                    if self.process_command_with_ticks(cmd, pending_ticks)? {
+                    if self.process_command_in(cmd, sequence)? {
                         return Ok(true);
                     }
                 }
                 Ok(false)
             },
-            CmdResult::Async(_future) => {
-                // For now, we don't support async commands in the sync runtime
-                // This would require tokio integration
+            CmdResult::Sequence(cmds) => self.start_sequence(cmds, sequence),
+            CmdResult::Async(future) => {
+                self.spawn_async(future, sequence);
                 Ok(false)
             },
             CmdResult::RunProcess { mut command, on_exit } => {
@@ -485,151 +1710,1711 @@ impl<M: Model> Program<M> {
                 // Re-setup terminal after process completes
                 self.setup_terminal()?;
 
-                // Force a full redraw by clearing the last view
-                self.last_view.clear();
+                // The process may have drawn anything; redraw from scratch
+                self.renderer.reset();
+
+                // Call the callback with the result
+                self.deliver(on_exit(result))
+            },
+            CmdResult::SpawnProcess { command, on_event } => {
+                self.track_background(sequence);
+                let tx = self.events_tx.clone();
+                let token = self.scope.as_ref().map(|scope| scope.token.clone());
+                if let Some(process) = process::spawn(command, on_event, tx, sequence, token) {
+                    if let Some(work) = self.keyed_work() {
+                        work.processes.retain(|process| !process.is_finished());
+                        work.processes.push(process.clone());
+                    }
+                    self.processes.retain(|process| !process.is_finished());
+                    self.processes.push(process);
+                }
+                Ok(false)
+            },
+            CmdResult::Println(text) => {
+                self.renderer.print_above(&text);
+                Ok(false)
+            },
+            CmdResult::Terminal(control) => {
+                // Only a terminal set up by this program receives control
+                // sequences; headless and accessible runs ignore them
+                if self.terminal.is_enabled() && !self.options.accessible {
+                    control.write(self.terminal.backend())?;
+                }
+                Ok(false)
+            },
+            CmdResult::Keyed { key, cmd } => {
+                self.cancel_key(&key);
+                let work = KeyedWork::default();
+                let scope = Scope { key: key.clone(), token: work.token.clone() };
+                self.keys.insert(key, work);
+
+                let outer = self.scope.replace(scope);
+                let result = self.process_command_in(cmd, sequence);
+                self.scope = outer;
+                result
+            },
+            CmdResult::Cancel(key) => {
+                self.cancel_key(&key);
+                Ok(false)
+            },
+            CmdResult::CancelDebounce(key) => {
+                self.pending_ticks.retain(|tick| !tick.is_debounced(&key));
+                Ok(false)
+            },
+            CmdResult::Fail(err) => {
+                self.failure = Some(ProgramError::User(err));
+                Ok(true)
+            },
+        }
+    }
+
+    /// Cancel the work running under `key`.
+    fn cancel_key(&mut self, key: &str) {
+        let Some(work) = self.keys.remove(key) else {
+            return;
+        };
+
+        work.token.cancel();
+        for task in work.tasks {
+            task.cancel();
+        }
+        for process in work.processes {
+            let _ = process.kill();
+        }
+        self.pending_ticks.retain(|tick| !is_cancelled(&tick.cancel));
+    }
+
+    /// Forget keys whose work has finished, so they do not accumulate.
+    fn prune_keys(&mut self) {
+        // Running work, its pending messages and its timers each hold a
+        // clone of the key's token
+        self.keys.retain(|_, work| work.token.is_shared());
+    }
+
+    /// Work running under the key of the command being processed.
+    fn keyed_work(&mut self) -> Option<&mut KeyedWork> {
+        let scope = self.scope.as_ref()?;
+        self.keys.get_mut(&scope.key)
+    }
+
+    /// Spawn a blocking command on the worker pool.
+    fn spawn_blocking(&mut self, f: BlockingFn<M::Message>, sequence: Option<u64>) {
+        self.track_background(sequence);
+
+        let tx = self.events_tx.clone();
+        let cancel = self.scope.as_ref().map(|scope| scope.token.clone());
+        self.pool.get_or_insert_with(WorkerPool::with_default_size).execute(move || {
+            let result = {
+                let token = cancel.clone().unwrap_or_default();
+                panic::catch_unwind(AssertUnwindSafe(|| f(&token)))
+            };
+            let _ = tx.send(Self::completion_event(result, sequence, cancel));
+        });
+    }
+
+    /// Spawn an async command on the executor.
+    fn spawn_async(&mut self, future: AsyncFn<M::Message>, sequence: Option<u64>) {
+        self.track_background(sequence);
+
+        let mut guard = CancelGuard { tx: Some(self.events_tx.clone()), sequence };
+        let cancel = self.scope.as_ref().map(|scope| scope.token.clone());
+        let task = self.spawn_task(async move {
+            let result = CatchUnwind::new(future).await;
+            if let Some(tx) = guard.tx.take() {
+                let _ = tx.send(Self::completion_event(result, sequence, cancel));
+            }
+        });
+
+        if let Some(work) = self.keyed_work() {
+            work.tasks.retain(|task| !task.is_finished());
+            work.tasks.push(task);
+        }
+    }
+
+    /// Spawn a future on the caller's runtime under `run_async`, or on the
+    /// built-in executor otherwise.
+    fn spawn_task<F>(&mut self, future: F) -> TaskHandle
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        #[cfg(feature = "tokio")]
+        if let Some(runtime) = &self.runtime {
+            return runtime.spawn(future).abort_handle().into();
+        }
+        self.executor.get_or_insert_with(Executor::new).spawn(future)
+    }
+
+    /// Count a newly spawned background command, including against its sequence.
+    fn track_background(&mut self, sequence: Option<u64>) {
+        if let Some(seq) = sequence.and_then(|id| self.sequences.get_mut(&id)) {
+            seq.pending += 1;
+        }
+        self.in_flight += 1;
+    }
+
+    /// Build the event reporting the outcome of a background command.
+    fn completion_event(
+        result: Result<Result<M::Message, BoxError>, Box<dyn Any + Send>>,
+        sequence: Option<u64>,
+        cancel: Option<CancelToken>,
+    ) -> RuntimeEvent<M::Message> {
+        match result {
+            Ok(Ok(msg)) => RuntimeEvent::Message { msg, sequence, cancel },
+            Ok(Err(error)) => RuntimeEvent::Failed { error, sequence, cancel },
+            Err(payload) => RuntimeEvent::Panicked { message: panic_message(payload.as_ref()) },
+        }
+    }
+
+    /// Kill background processes that are still running.
+    fn kill_processes(&mut self) {
+        for process in self.processes.drain(..) {
+            let _ = process.kill();
+        }
+    }
+
+    /// Start running a sequence of commands, nested in `parent` if given.
+    fn start_sequence(
+        &mut self,
+        cmds: Vec<Cmd<M::Message>>,
+        parent: Option<u64>,
+    ) -> io::Result<bool> {
+        let id = self.next_sequence_id;
+        self.next_sequence_id += 1;
+        let scope = self.scope.clone();
+        self.sequences.insert(
+            id,
+            PendingSequence { pending: 0, rest: cmds.into(), parent, counted: false, scope },
+        );
+        self.resume_sequence(id)
+    }
+
+    /// Run the next steps of a sequence until one has to wait for background work.
+    fn resume_sequence(&mut self, id: u64) -> io::Result<bool> {
+        loop {
+            let Some(seq) = self.sequences.get_mut(&id) else {
+                return Ok(false);
+            };
+
+            if seq.pending > 0 {
+                // Waiting on background work: hold the parent sequence too
+                if !seq.counted {
+                    seq.counted = true;
+                    if let Some(parent) = seq.parent.and_then(|p| self.sequences.get_mut(&p)) {
+                        parent.pending += 1;
+                    }
+                }
+                return Ok(false);
+            }
+
+            // A cancelled sequence skips its remaining steps
+            if seq.scope.as_ref().is_some_and(|scope| scope.token.is_cancelled()) {
+                seq.rest.clear();
+            }
+
+            match seq.rest.pop_front() {
+                Some(cmd) => {
+                    let outer = std::mem::replace(&mut self.scope, seq.scope.clone());
+                    let result = self.process_command_in(cmd, Some(id));
+                    self.scope = outer;
+                    if result? {
+                        return Ok(true);
+                    }
+                },
+                None => {
+                    let seq = self.sequences.remove(&id);
+                    return match seq.and_then(|s| if s.counted { s.parent } else { None }) {
+                        Some(parent) => self.complete_sequence_step(parent),
+                        None => Ok(false),
+                    };
+                },
+            }
+        }
+    }
+
+    /// Record that one piece of background work for a sequence step finished.
+    fn complete_sequence_step(&mut self, id: u64) -> io::Result<bool> {
+        let Some(seq) = self.sequences.get_mut(&id) else {
+            return Ok(false);
+        };
+        seq.pending = seq.pending.saturating_sub(1);
+        if seq.pending == 0 { self.resume_sequence(id) } else { Ok(false) }
+    }
+
+    /// Run without a terminal (CI, piped input or output).
+    ///
+    /// The full update loop runs — init, commands, ticks and subscriptions —
+    /// while the view is written to stdout as plain text. The program exits
+    /// when the model quits, or once nothing is left that could update it:
+    /// no background work, timers, subscriptions or program handles.
+    fn run_headless(mut self) -> Result<(M, Exit), ProgramError> {
+        let mut out = self.options.output.plain_writer();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| self.headless_loop(&mut out)));
+
+        self.conclude(result)
+    }
+
+    /// Run in accessible mode, prompting through `adapter`.
+    ///
+    /// Prompts and printed lines are written without ANSI codes to the
+    /// plain-text writer for [`ProgramOptions::output`], and answers are read
+    /// a line at a time from [`ProgramOptions::input`]. The program exits
+    /// when the model quits, the adapter reports it complete, or the input
+    /// ends.
+    fn run_accessible(self, adapter: AccessibleAdapter<M>) -> Result<(M, Exit), ProgramError> {
+        let input = self.options.input.reader();
+        let mut out = self.options.output.plain_writer();
+        self.run_accessible_on(adapter, input, &mut out)
+    }
+
+    /// Run in accessible mode like [`Program::run_accessible`], reading
+    /// answers from `input` once it is opened and writing to `out`.
+    fn run_accessible_on(
+        mut self,
+        adapter: AccessibleAdapter<M>,
+        input: io::Result<Box<dyn BufRead>>,
+        out: &mut impl Write,
+    ) -> Result<(M, Exit), ProgramError> {
+        let mut input = input?;
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            self.accessible_loop(&adapter, &mut input, out)
+        }));
+
+        self.conclude(result)
+    }
+
+    /// Stop background work and report how the run ended.
+    ///
+    /// An I/O error or panic in the loop takes precedence over a failure
+    /// recorded while it ran.
+    fn conclude(
+        mut self,
+        result: thread::Result<io::Result<()>>,
+    ) -> Result<(M, Exit), ProgramError> {
+        self.kill_processes();
+        match result {
+            Err(payload) => Err(ProgramError::Panic(panic_message(payload.as_ref()))),
+            Ok(Err(err)) => Err(err.into()),
+            Ok(Ok(())) => match self.failure.take() {
+                Some(err) => Err(err),
+                None => Ok((self.model, self.exit.unwrap_or(Exit::Quit))),
+            },
+        }
+    }
+
+    /// Restore the terminal, then turn the result of the interactive loop
+    /// into the final model and exit.
+    ///
+    /// Failing to restore the terminal, for example after a hangup closed
+    /// it, is only reported when the program otherwise ended normally, so a
+    /// panic, error or signal is never masked.
+    fn conclude_interactive(
+        mut self,
+        result: thread::Result<io::Result<()>>,
+    ) -> Result<(M, Exit), ProgramError> {
+        let restored = self.teardown_terminal();
+        match (self.conclude(result), restored) {
+            (Ok((_, exit)), Err(err)) if exit.signal().is_none() => Err(err.into()),
+            (concluded, _) => concluded,
+        }
+    }
+
+    /// Quit unless the quit confirmation hook asks the model first.
+    fn request_exit(&mut self, exit: Exit) -> io::Result<bool> {
+        if let Some(msg) = self.confirm_quit.as_ref().and_then(|confirm| confirm(&self.model)) {
+            return self.deliver(msg);
+        }
+        self.exit = Some(exit);
+        Ok(true)
+    }
+
+    /// Prompt for input until the model is complete, reading from `input`.
+    fn accessible_loop(
+        &mut self,
+        adapter: &AccessibleAdapter<M>,
+        input: &mut impl BufRead,
+        out: &mut impl Write,
+    ) -> io::Result<()> {
+        self.headless = true;
+
+        let mut quit = self.start()?;
+        while !quit {
+            // Let background work finish so the prompt reflects its results
+            loop {
+                quit = self.process_pending(Instant::now(), &mut |_| Ok(()))?;
+                if quit || self.in_flight == 0 {
+                    break;
+                }
+                quit = self.wait_for_event(self.poll_timeout(Instant::now()))?;
+                if quit {
+                    break;
+                }
+            }
+
+            for line in self.take_printed() {
+                writeln!(out, "{}", strip_ansi(&line))?;
+            }
+            if quit || adapter.is_complete(&self.model) {
+                break;
+            }
+
+            write!(out, "{}", strip_ansi(&adapter.prompt(&self.model)))?;
+            out.flush()?;
+
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 {
+                // Input ended; leave the prompt line terminated
+                writeln!(out)?;
+                break;
+            }
+
+            match adapter.parse(&self.model, line.trim_end_matches(['\r', '\n'])) {
+                Some(msg) => quit = self.deliver(msg)?,
+                None => writeln!(out, "Invalid input, please try again.")?,
+            }
+            if !quit {
+                self.refresh_subscriptions();
+            }
+        }
+
+        for line in self.take_printed() {
+            writeln!(out, "{}", strip_ansi(&line))?;
+        }
+        out.flush()
+    }
+
+    /// Process events without a terminal, writing output to `out`.
+    fn headless_loop(&mut self, out: &mut impl Write) -> io::Result<()> {
+        self.headless = true;
+        let mut printer = HeadlessPrinter::new(self.options.non_interactive_output, Instant::now());
+        let deadline = self.headless_deadline();
+
+        let mut quit = self.start()?;
+        while !quit {
+            match self.headless_step(&mut printer, out, deadline)? {
+                Some(timeout) => quit = self.wait_for_event(timeout)?,
+                None => break,
+            }
+        }
+
+        printer.print_lines(out, &self.take_printed())?;
+        printer.finish(out, &self.model.frame().content)
+    }
+
+    /// Run without a terminal like [`Program::headless_loop`], sleeping on
+    /// the tokio runtime between steps until woken.
+    #[cfg(feature = "tokio")]
+    async fn headless_loop_async(
+        &mut self,
+        out: &mut impl Write,
+        wake: &tokio::sync::Notify,
+    ) -> io::Result<()> {
+        self.headless = true;
+        let mut printer = HeadlessPrinter::new(self.options.non_interactive_output, Instant::now());
+        let deadline = self.headless_deadline();
+
+        if !self.start()? {
+            while let Some(timeout) = self.headless_step(&mut printer, out, deadline)? {
+                tokio::select! {
+                    () = tokio::time::sleep(timeout) => {},
+                    () = wake.notified() => {},
+                }
+            }
+        }
+
+        printer.print_lines(out, &self.take_printed())?;
+        printer.finish(out, &self.model.frame().content)
+    }
+
+    /// When a headless run started now must stop, from the exit policy.
+    fn headless_deadline(&self) -> Option<Instant> {
+        let timeout = self.options.headless_exit.timeout?;
+        Instant::now().checked_add(timeout)
+    }
+
+    /// Deliver everything that is ready and write the view if it is due.
+    ///
+    /// Returns how long to wait for more work, or `None` once the program
+    /// quit, became idle or reached `deadline`.
+    fn headless_step(
+        &mut self,
+        printer: &mut HeadlessPrinter,
+        out: &mut impl Write,
+        deadline: Option<Instant>,
+    ) -> io::Result<Option<Duration>> {
+        let now = Instant::now();
+        let quit = self.process_pending(now, &mut |_| Ok(()))?;
+        printer.print_lines(out, &self.take_printed())?;
+        if quit || self.is_idle() || deadline.is_some_and(|deadline| now >= deadline) {
+            return Ok(None);
+        }
+
+        if printer.is_due(now) {
+            printer.update(out, &self.model.frame().content, now)?;
+        }
+
+        let timeout = printer
+            .next_due()
+            .into_iter()
+            .chain(deadline)
+            .min()
+            .map_or(Duration::MAX, |due| due.saturating_duration_since(now));
+        Ok(Some(self.poll_timeout(now).min(timeout)))
+    }
+
+    /// Set up the terminal for TUI mode.
+    fn setup_terminal(&mut self) -> io::Result<()> {
+        if self.is_headless() {
+            return Ok(());
+        }
+
+        self.terminal.enable(TerminalModes::from_options(&self.options))
+    }
+
+    /// Tear down the terminal, restoring original state.
+    fn teardown_terminal(&mut self) -> io::Result<()> {
+        self.terminal.restore()
+    }
+
+    /// Render the current view.
+    pub(crate) fn render(&mut self, now: Instant) -> io::Result<()> {
+        // Nothing is drawn while the process is stopped
+        if self.suspended {
+            return Ok(());
+        }
+
+        let started = Instant::now();
+        let mut frame = self.model.frame();
+        if let Some(inspector) = &mut self.inspector {
+            inspector.record_view(started.elapsed());
+        }
+        if let Some(inspector) = self.inspector.as_ref().filter(|inspector| inspector.is_visible())
+        {
+            let info = self.inspector_info(now);
+            frame.content = inspector.overlay(&frame.content, &info, now, self.options.alt_screen);
+        }
+        let written = self.renderer.render(self.terminal.backend(), &frame)?;
+        self.metrics.record_render(written, started.elapsed(), self.frame_duration());
+
+        self.needs_render = false;
+        self.last_render = Some(now);
+        Ok(())
+    }
+
+    /// Gather the runtime state shown by the inspector.
+    fn inspector_info(&self, now: Instant) -> InspectorInfo {
+        let mut subscriptions: Vec<(String, Option<Duration>)> = self
+            .active_subs
+            .iter()
+            .map(|(id, sub)| {
+                let due = match &sub.source {
+                    ActiveSource::Interval { next_fire, .. } => Some(*next_fire),
+                    ActiveSource::Watch { next_check, .. } => Some(*next_check),
+                    ActiveSource::Channel(_) | ActiveSource::Stream(_) => None,
+                };
+                (id.clone(), due.map(|due| due.saturating_duration_since(now)))
+            })
+            .collect();
+        subscriptions.sort();
+
+        InspectorInfo {
+            subscriptions,
+            ticks: self
+                .pending_ticks
+                .iter()
+                .map(|tick| tick.scheduled.saturating_duration_since(now))
+                .collect(),
+            metrics: self.metrics.snapshot(),
+        }
+    }
+
+    /// Note that the model changed and a frame should be drawn.
+    fn request_render(&mut self) {
+        self.metrics.record_update(self.needs_render);
+        self.needs_render = true;
+    }
+
+    /// When the next frame may be drawn, if a frame was drawn before.
+    fn next_frame(&self) -> Option<Instant> {
+        self.last_render.map(|last| last + self.frame_duration())
+    }
+
+    /// Time between frames at the configured frame rate.
+    fn frame_duration(&self) -> Duration {
+        Duration::from_secs(1) / self.options.fps.max(1)
+    }
+}
+
+impl<M> Program<M>
+where
+    M: Model + Accessible<Message = <M as Model>::Message> + 'static,
+{
+    /// Drive the model through its [`Accessible`] implementation in
+    /// accessible mode.
+    ///
+    /// The program prompts with `accessible_prompt`, turns each answer into
+    /// a message with `parse_accessible_input`, and exits once
+    /// `is_accessible_complete` returns true.
+    pub fn with_accessible_model(self) -> Self {
+        self.with_accessible_adapter(AccessibleAdapter::from_model())
+    }
+}
+
+/// Signal requested by a key press the model did not handle.
+fn key_signal(event: &Event) -> Option<Signal> {
+    let Event::Key(key) = event else {
+        return None;
+    };
+    if !key.modifiers.ctrl() {
+        return None;
+    }
+
+    match key.code {
+        KeyCode::Char('c') => Some(Signal::Interrupt),
+        KeyCode::Char('z') => Some(Signal::Suspend),
+        _ => None,
+    }
+}
+
+/// Whether work tagged with `token` was cancelled.
+fn is_cancelled(token: &Option<CancelToken>) -> bool {
+    token.as_ref().is_some_and(CancelToken::is_cancelled)
+}
+
+/// Extract a readable message from a panic payload.
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        (*s).to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::pin::Pin;
+
+    use super::*;
+    use crate::{
+        Sub,
+        runtime::{ProcessEvent, subscription::BoxStream},
+    };
+
+    struct TestModel {
+        count: i32,
+    }
+
+    enum TestMsg {
+        Inc,
+        #[allow(dead_code)]
+        Quit,
+    }
+
+    impl Model for TestModel {
+        type Message = TestMsg;
+
+        fn init(&self) -> Option<Cmd<Self::Message>> {
+            None
+        }
+
+        fn update(&mut self, msg: Self::Message) -> Option<Cmd<Self::Message>> {
+            match msg {
+                TestMsg::Inc => self.count += 1,
+                TestMsg::Quit => return Some(Cmd::quit()),
+            }
+            None
+        }
+
+        fn view(&self) -> String {
+            format!("Count: {}", self.count)
+        }
+    }
+
+    #[test]
+    fn test_program_creation() {
+        let model = TestModel { count: 0 };
+        let program = Program::new(model);
+        assert_eq!(program.model.count, 0);
+    }
+
+    #[test]
+    fn test_program_options() {
+        let options = ProgramOptions::fullscreen();
+        assert!(options.alt_screen);
+        assert!(options.mouse);
+
+        let options = ProgramOptions::inline();
+        assert!(!options.alt_screen);
+        assert!(!options.mouse);
+    }
+
+    #[test]
+    fn test_program_builder_pattern() {
+        let model = TestModel { count: 0 };
+        let program = Program::new(model)
+            .with_alt_screen()
+            .with_mouse()
+            .with_fps(30)
+            .with_bracketed_paste()
+            .with_focus_change()
+            .with_reduce_motion()
+            .with_tick_rate(Duration::from_millis(50));
+
+        assert!(program.options.alt_screen);
+        assert!(program.options.mouse);
+        assert_eq!(program.options.fps, 30);
+        assert!(program.options.bracketed_paste);
+        assert!(program.options.focus_change);
+        assert!(program.options.reduce_motion);
+        assert_eq!(program.options.tick_rate, Duration::from_millis(50));
+    }
+
+    #[test]
+    fn test_interactive_needs_chosen_streams() {
+        let pipeline =
+            Terminals { stderr: true, tty_input: true, tty_output: true, ..Default::default() };
+        let all = Terminals { stdin: true, stdout: true, ..pipeline };
+
+        for input in [InputSource::Stdin, InputSource::Tty] {
+            for output in [OutputStream::Stdout, OutputStream::Stderr, OutputStream::Tty] {
+                let options = ProgramOptions { input, output, ..Default::default() };
+                // `cat list.txt | app | xargs ...`: only stdin and stdout are pipes
+                let expected = input == InputSource::Tty && output != OutputStream::Stdout;
+                assert_eq!(options.is_interactive_in(&pipeline), expected, "{input:?} {output:?}");
+                assert!(options.is_interactive_in(&all), "{input:?} {output:?}");
+                assert!(!options.is_interactive_in(&Terminals::default()), "{input:?} {output:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_fps_clamping() {
+        let model = TestModel { count: 0 };
+
+        let program = Program::new(model).with_fps(0);
+        assert_eq!(program.options.fps, 1);
+
+        let model = TestModel { count: 0 };
+        let program = Program::new(model).with_fps(200);
+        assert_eq!(program.options.fps, 120);
+    }
+
+    #[test]
+    fn test_message_filter() {
+        let model = TestModel { count: 0 };
+
+        // Test that filter is None by default
+        let program = Program::new(model);
+        assert!(program.filter.is_none());
+
+        // Test that filter can be set
+        let model = TestModel { count: 0 };
+        let program = Program::new(model).with_filter(|_model, msg| {
+            // Pass all messages through
+            Some(msg)
+        });
+        assert!(program.filter.is_some());
+    }
+
+    #[test]
+    fn test_apply_filter_pass_through() {
+        let model = TestModel { count: 0 };
+        let program = Program::new(model).with_filter(|_model, msg| Some(msg));
+
+        // Filter should pass the message through
+        let result = program.apply_filter(TestMsg::Inc);
+        assert!(matches!(result, Some(TestMsg::Inc)));
+    }
+
+    #[test]
+    fn test_apply_filter_block() {
+        let model = TestModel { count: 0 };
+        let program = Program::new(model).with_filter(|_model, _msg| {
+            // Block all messages
+            None
+        });
+
+        // Filter should block the message
+        let result = program.apply_filter(TestMsg::Inc);
+        assert!(result.is_none());
+    }
+
+    /// Model that records the order in which messages arrive.
+    struct RecordingModel {
+        log: Vec<&'static str>,
+    }
+
+    impl Model for RecordingModel {
+        type Message = &'static str;
+
+        fn init(&self) -> Option<Cmd<Self::Message>> {
+            None
+        }
+
+        fn update(&mut self, msg: Self::Message) -> Option<Cmd<Self::Message>> {
+            self.log.push(msg);
+            None
+        }
+
+        fn view(&self) -> String {
+            self.log.join(",")
+        }
+    }
+
+    /// Deliver background results until no commands are in flight.
+    fn settle<M: Model>(program: &mut Program<M>) {
+        while program.in_flight > 0 {
+            let event = program
+                .events_rx
+                .recv_timeout(Duration::from_secs(1))
+                .expect("background command did not complete");
+            program.handle_runtime_event(event).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_async_command_delivers_message() {
+        let mut program = Program::new(TestModel { count: 0 });
+
+        let quit = program.process_command(Cmd::perform_async(async { TestMsg::Inc })).unwrap();
+        assert!(!quit);
+
+        settle(&mut program);
+        assert_eq!(program.model.count, 1);
+    }
+
+    #[test]
+    fn test_async_command_in_batch_and_map() {
+        let mut program = Program::new(RecordingModel { log: Vec::new() });
+
+        let child: Cmd<()> = Cmd::perform_async(async {});
+        let cmd = Cmd::batch(vec![
+            Cmd::perform_async(async { "a" }),
+            child.map(|()| "mapped"),
+            Cmd::perform(|| "sync"),
+        ]);
+        program.process_command(cmd).unwrap();
+        settle(&mut program);
+
+        let mut log = program.model.log.clone();
+        log.sort_unstable();
+        assert_eq!(log, vec!["a", "mapped", "sync"]);
+    }
+
+    #[test]
+    fn test_sequence_waits_for_async_command() {
+        let mut program = Program::new(RecordingModel { log: Vec::new() });
+
+        let cmd = Cmd::sequence(vec![
+            Cmd::perform_async(async {
+                std::thread::sleep(Duration::from_millis(20));
+                "first"
+            }),
+            Cmd::perform(|| "second"),
+            Cmd::sequence(vec![Cmd::perform_async(async { "third" }), Cmd::perform(|| "fourth")]),
+            Cmd::perform(|| "fifth"),
+        ]);
+        program.process_command(cmd).unwrap();
+
+        // Nothing after the first async step may run before it completes
+        assert!(program.model.log.is_empty());
+
+        settle(&mut program);
+        assert_eq!(program.model.log, vec!["first", "second", "third", "fourth", "fifth"]);
+        assert!(program.sequences.is_empty());
+    }
+
+    /// Future that stays pending, keeping its waker alive, until `release`.
+    struct Parked {
+        waker: Arc<std::sync::Mutex<Option<std::task::Waker>>>,
+    }
+
+    impl Future for Parked {
+        type Output = &'static str;
+
+        fn poll(
+            self: Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<Self::Output> {
+            *self.waker.lock().unwrap() = Some(cx.waker().clone());
+            std::task::Poll::Pending
+        }
+    }
+
+    #[test]
+    fn test_keyed_command_replaces_earlier_work() {
+        let mut program = Program::new(RecordingModel { log: Vec::new() });
+        let (started_tx, started_rx) = mpsc::channel();
+        let slow = Cmd::perform_cancellable(move |token| {
+            let _ = started_tx.send(());
+            while !token.is_cancelled() {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            "stale"
+        });
+        program.process_command(slow.keyed("search")).unwrap();
+        started_rx.recv_timeout(Duration::from_secs(1)).unwrap();
+
+        // A parked future, kept alive by its waker, is dropped on cancel
+        let waker = Arc::default();
+        let parked = Cmd::perform_async(Parked { waker: Arc::clone(&waker) });
+        program
+            .process_command(
+                Cmd::batch(vec![parked, Cmd::tick(Duration::ZERO, |_| "tick")]).keyed("other"),
+            )
+            .unwrap();
+
+        program.process_command(Cmd::perform(|| "fresh").keyed("search")).unwrap();
+        program.process_command(Cmd::cancel("other")).unwrap();
+        assert!(program.pending_ticks.is_empty());
+
+        settle(&mut program);
+        assert_eq!(program.model.log, vec!["fresh"]);
+        // The parked future was dropped along with its clone of the slot
+        assert_eq!(Arc::strong_count(&waker), 1);
+    }
+
+    #[test]
+    fn test_cancelled_sequence_skips_remaining_steps() {
+        let mut program = Program::new(RecordingModel { log: Vec::new() });
+
+        let cmd = Cmd::sequence(vec![
+            Cmd::perform(|| {
+                std::thread::sleep(Duration::from_millis(20));
+                "first"
+            }),
+            Cmd::perform(|| "second"),
+        ]);
+        // The enclosing sequence continues once the cancelled one unwinds
+        let outer = Cmd::sequence(vec![cmd.keyed("load"), Cmd::perform(|| "after")]);
+        program.process_command(outer).unwrap();
+        program.process_command(Cmd::cancel("load")).unwrap();
+
+        settle(&mut program);
+        assert_eq!(program.model.log, vec!["after"]);
+        assert!(program.sequences.is_empty());
+    }
+
+    #[test]
+    fn test_debounce_runs_last_command() {
+        let mut program = Program::new(RecordingModel { log: Vec::new() });
+        let start = Instant::now();
+        let window = Duration::from_millis(300);
+        let debounce = |msg| Cmd::debounce("filter", window, Cmd::perform(move || msg));
+
+        program.set_clock(start);
+        program.process_command(debounce("a")).unwrap();
+        program.set_clock(start + Duration::from_millis(100));
+        program.process_command(debounce("b")).unwrap();
+        assert_eq!(program.next_due(), Some(start + Duration::from_millis(400)));
+
+        // The wait restarted with the second command
+        program.process_pending(start + Duration::from_millis(350), &mut |_| Ok(())).unwrap();
+        assert_eq!(program.in_flight, 0);
+
+        program.process_pending(start + Duration::from_millis(400), &mut |_| Ok(())).unwrap();
+        settle(&mut program);
+        assert_eq!(program.model.log, vec!["b"]);
+
+        // A waiting command can be cancelled
+        program.process_command(debounce("c")).unwrap();
+        program.process_command(Cmd::cancel_debounce("filter")).unwrap();
+        assert!(program.pending_ticks.is_empty());
+    }
+
+    #[test]
+    fn test_debounce_keys_are_separate_from_keyed_commands() {
+        let mut program = Program::new(RecordingModel { log: Vec::new() });
+        let window = Duration::from_millis(300);
+        program.process_command(Cmd::debounce("search", window, Cmd::perform(|| "a"))).unwrap();
+
+        // Cancelling keyed work leaves the debounced command waiting
+        program.process_command(Cmd::cancel("search")).unwrap();
+        assert_eq!(program.pending_ticks.len(), 1);
+
+        program.process_command(Cmd::cancel_debounce("search")).unwrap();
+        assert!(program.pending_ticks.is_empty());
+    }
+
+    #[test]
+    fn test_finished_keys_are_forgotten() {
+        let mut program = Program::new(RecordingModel { log: Vec::new() });
+        for query in ["a", "ab", "abc"] {
+            program.process_command(Cmd::perform(move || query).keyed(query)).unwrap();
+        }
+        program
+            .process_command(Cmd::tick(Duration::from_secs(60), |_| "late").keyed("tick"))
+            .unwrap();
+        assert_eq!(program.keys.len(), 4);
+
+        settle(&mut program);
+        program.process_pending(Instant::now(), &mut |_| Ok(())).unwrap();
+        assert_eq!(program.model.log.len(), 3);
+        // Only the key with a timer still waiting is kept
+        assert_eq!(program.keys.keys().collect::<Vec<_>>(), ["tick"]);
+    }
+
+    #[test]
+    fn test_throttle_runs_first_command() {
+        let mut program = Program::new(RecordingModel { log: Vec::new() });
+        let start = Instant::now();
+        let throttle =
+            |msg| Cmd::throttle("save", Duration::from_millis(500), Cmd::perform(move || msg));
+
+        for (offset, msg) in [(0, "a"), (100, "b"), (499, "c"), (500, "d"), (600, "e")] {
+            program.set_clock(start + Duration::from_millis(offset));
+            program.process_command(throttle(msg)).unwrap();
+            settle(&mut program);
+        }
+        assert_eq!(program.model.log, vec!["a", "d"]);
+    }
+
+    #[test]
+    fn test_perform_does_not_block_event_loop() {
+        let mut program = Program::new(RecordingModel { log: Vec::new() });
+
+        let start = Instant::now();
+        program
+            .process_command(Cmd::perform(|| {
+                std::thread::sleep(Duration::from_millis(200));
+                "slow"
+            }))
+            .unwrap();
+        assert!(start.elapsed() < Duration::from_millis(200));
+        assert!(program.model.log.is_empty());
+
+        settle(&mut program);
+        assert_eq!(program.model.log, vec!["slow"]);
+    }
+
+    #[test]
+    fn test_batch_runs_blocking_commands_concurrently() {
+        let mut program = Program::new(RecordingModel { log: Vec::new() });
+        let (a_tx, a_rx) = mpsc::channel();
+        let (b_tx, b_rx) = mpsc::channel();
+
+        // Each command waits for the other, which only succeeds if both run at once
+        program
+            .process_command(Cmd::batch(vec![
+                Cmd::perform(move || {
+                    a_tx.send(()).unwrap();
+                    if b_rx.recv_timeout(Duration::from_secs(1)).is_ok() { "a" } else { "a-late" }
+                }),
+                Cmd::perform(move || {
+                    b_tx.send(()).unwrap();
+                    if a_rx.recv_timeout(Duration::from_secs(1)).is_ok() { "b" } else { "b-late" }
+                }),
+            ]))
+            .unwrap();
+        settle(&mut program);
+
+        let mut log = program.model.log.clone();
+        log.sort_unstable();
+        assert_eq!(log, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_panicking_command_is_reported() {
+        let mut program = Program::new(RecordingModel { log: Vec::new() });
+
+        program.process_command(Cmd::perform(|| panic!("worker exploded"))).unwrap();
+        let event = program.events_rx.recv_timeout(Duration::from_secs(1)).unwrap();
+
+        assert!(program.handle_runtime_event(event).unwrap());
+        assert!(matches!(
+            program.failure(),
+            Some(ProgramError::Panic(message)) if message.contains("worker exploded")
+        ));
+    }
+
+    #[test]
+    fn test_failing_commands_stop_the_program() {
+        let mut program = Program::new(RecordingModel { log: Vec::new() });
+        assert!(program.process_command(Cmd::fail("config missing")).unwrap());
+        assert_eq!(program.failure().unwrap().to_string(), "config missing");
+
+        let mut program = Program::new(RecordingModel { log: Vec::new() });
+        let cmd = Cmd::try_perform(|| "42x".parse::<i32>().map(|_| "parsed"));
+        program.process_command(cmd).unwrap();
+        let event = program.events_rx.recv_timeout(Duration::from_secs(1)).unwrap();
+
+        assert!(program.handle_runtime_event(event).unwrap());
+        assert!(program.failure().unwrap().downcast_ref::<std::num::ParseIntError>().is_some());
+        assert!(program.model.log.is_empty());
+    }
+
+    #[test]
+    fn test_unhandled_interrupt_is_reported() {
+        let mut program = Program::new(TestModel { count: 0 });
+        assert!(program.handle_signal(Signal::Interrupt).unwrap());
+        assert!(program.failure().is_none());
+        assert!(matches!(program.exit, Some(Exit::Interrupted(Signal::Interrupt))));
+
+        program.suspended = true;
+        assert!(program.handle_signal(Signal::Terminate).unwrap());
+        assert!(matches!(program.exit, Some(Exit::Killed(Signal::Terminate))));
+    }
+
+    #[test]
+    fn test_quit_with_value() {
+        let mut program = Program::new(RecordingModel { log: Vec::new() });
+        let cmd = Cmd::batch(vec![Cmd::none(), Cmd::quit_with("main").map(|msg| msg)]);
+        assert!(program.process_command(cmd).unwrap());
+
+        let outcome = program.exit.take().unwrap().into_outcome::<&str>().unwrap();
+        assert_eq!(outcome, Outcome::Completed("main"));
+        assert_eq!(outcome.exit_code(), 0);
+    }
+
+    #[test]
+    fn test_quit_confirmation() {
+        let mut program = Program::new(RecordingModel { log: Vec::new() })
+            .with_quit_confirmation(|model| (!model.log.contains(&"confirm")).then_some("confirm"));
+
+        assert!(!program.process_command(Cmd::quit_cancelled()).unwrap());
+        assert_eq!(program.model.log, vec!["confirm"]);
+        assert!(program.exit.is_none());
+
+        assert!(program.process_command(Cmd::quit_cancelled()).unwrap());
+        assert!(matches!(program.exit, Some(Exit::Cancelled)));
+    }
+
+    #[test]
+    fn test_handle_sends_messages() {
+        let mut program = Program::new(TestModel { count: 0 });
+        let handle = program.handle();
+
+        let sender = handle.clone();
+        std::thread::spawn(move || {
+            assert!(sender.send(TestMsg::Inc));
+            assert!(sender.send(TestMsg::Inc));
+        })
+        .join()
+        .unwrap();
+
+        for _ in 0..2 {
+            let event = program.events_rx.recv_timeout(Duration::from_secs(1)).unwrap();
+            assert!(!program.handle_runtime_event(event).unwrap());
+        }
+        assert_eq!(program.model.count, 2);
+    }
+
+    #[test]
+    fn test_handle_quit_and_repaint() {
+        let backend = MemoryBackend::new(80, 24);
+        let mut program = Program::new(TestModel { count: 0 }).with_backend(backend.clone());
+        let handle = program.handle();
+        program.render(Instant::now()).unwrap();
+
+        assert!(handle.repaint());
+        let event = program.events_rx.try_recv().unwrap();
+        assert!(!program.handle_runtime_event(event).unwrap());
+
+        // The unchanged view is drawn again
+        let drawn = backend.output().len();
+        program.render(Instant::now()).unwrap();
+        assert!(backend.output().len() > drawn);
+
+        assert!(handle.quit());
+        let event = program.events_rx.try_recv().unwrap();
+        assert!(program.handle_runtime_event(event).unwrap());
+    }
+
+    #[test]
+    fn test_handle_after_exit() {
+        let program = Program::new(TestModel { count: 0 });
+        let handle = program.handle();
+        drop(program);
+
+        assert!(!handle.send(TestMsg::Inc));
+        assert!(!handle.quit());
+    }
+
+    #[test]
+    fn test_poll_timeout_shortened_by_handles() {
+        let program = Program::new(TestModel { count: 0 }).with_fps(10);
+        let frame = Duration::from_millis(100);
+        assert_eq!(program.poll_timeout(Instant::now()), frame);
+
+        let _handle = program.handle();
+        assert_eq!(program.poll_timeout(Instant::now()), MESSAGE_POLL_INTERVAL);
+    }
+
+    #[test]
+    fn test_poll_timeout_not_shortened_when_woken() {
+        let program = Program::new(TestModel { count: 0 }).with_fps(10);
+        program.set_waker(Waker::new(|| {}));
+        let _handle = program.handle();
+        assert_eq!(program.poll_timeout(Instant::now()), Duration::MAX);
+    }
+
+    #[test]
+    fn test_handles_wake_the_event_loop() {
+        let program = Program::new(TestModel { count: 0 }).with_backend(MemoryBackend::new(40, 10));
+        let handle = program.handle();
+        let sender = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            handle.send(TestMsg::Inc);
+            handle.quit();
+        });
+
+        // The loop waits for input without a timeout, so only waking it
+        // lets the program see the messages
+        let (model, outcome) = program.run_with_outcome::<()>().unwrap();
+        sender.join().unwrap();
+        assert_eq!(model.count, 1);
+        assert_eq!(outcome, Outcome::Quit);
+    }
+
+    #[test]
+    fn test_updates_are_coalesced_into_frames() {
+        let mut program = Program::new(TestModel { count: 0 })
+            .with_fps(10)
+            .with_backend(MemoryBackend::new(80, 24));
+        let handle = program.handle();
+        let start = Instant::now();
+        program.render(start).unwrap();
+
+        // A burst of messages within one frame interval is drawn once
+        for _ in 0..5 {
+            handle.send(TestMsg::Inc);
+        }
+        program
+            .process_pending(start, &mut |program| {
+                program.request_render();
+                Ok(())
+            })
+            .unwrap();
+        assert!(program.needs_render);
+        assert_eq!(program.poll_timeout(start), MESSAGE_POLL_INTERVAL);
+        assert_eq!(program.next_frame(), Some(start + Duration::from_millis(100)));
+
+        program.render(start + Duration::from_millis(100)).unwrap();
+        let metrics = handle.metrics();
+        assert_eq!(metrics.updates, 5);
+        assert_eq!(metrics.merged_updates, 4);
+        assert_eq!(metrics.renders, 2);
+        assert_eq!(metrics.frames, 2);
+        assert!(!program.needs_render);
+    }
+
+    #[test]
+    fn test_resume_without_suspend_is_ignored() {
+        let backend = MemoryBackend::new(40, 10);
+        let mut program = Program::new(TestModel { count: 0 }).with_backend(backend.clone());
+
+        // An external SIGCONT for a program that was never stopped
+        assert!(!program.handle_signal(Signal::Resume).unwrap());
+        assert!(!program.terminal.is_enabled());
+        assert!(!backend.is_raw_mode());
+        assert!(backend.output().is_empty());
+    }
+
+    /// A terminal that is gone by the time it should be restored.
+    struct HungUpBackend(MemoryBackend);
+
+    impl Write for HungUpBackend {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.0.flush()
+        }
+    }
+
+    impl Backend for HungUpBackend {
+        fn enable_raw_mode(&mut self) -> io::Result<()> {
+            self.0.enable_raw_mode()
+        }
+
+        fn disable_raw_mode(&mut self) -> io::Result<()> {
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+
+        fn size(&self) -> io::Result<(u16, u16)> {
+            self.0.size()
+        }
+
+        fn poll_event(&mut self, timeout: Duration) -> io::Result<Option<Event>> {
+            self.0.poll_event(timeout)
+        }
+    }
+
+    #[test]
+    fn test_failed_restore_keeps_outcome() {
+        use crate::terminal::{KeyEvent, KeyModifiers};
+
+        let backend = MemoryBackend::new(40, 10);
+        backend.push_event(Event::Key(KeyEvent {
+            code: KeyCode::Char('c'),
+            modifiers: KeyModifiers::CONTROL,
+        }));
+        let program =
+            Program::new(TestModel { count: 0 }).with_backend(HungUpBackend(backend.clone()));
+        let (_, outcome) = program.run_with_outcome::<()>().unwrap();
+        assert_eq!(outcome, Outcome::Interrupted(Signal::Interrupt));
+
+        // Without anything else to report, the failure to restore is returned
+        let program = Program::new(TestModel { count: 0 }).with_backend(HungUpBackend(backend));
+        program.handle().send(TestMsg::Quit);
+        let err = program.run().err().expect("restoring the terminal failed");
+        assert!(matches!(err, ProgramError::Io(err) if err.kind() == io::ErrorKind::BrokenPipe));
+    }
+
+    #[test]
+    fn test_runs_on_custom_backend() {
+        use crate::terminal::{KeyEvent, KeyModifiers};
+
+        let backend = MemoryBackend::new(40, 10);
+        let program =
+            Program::new(TestModel { count: 0 }).with_alt_screen().with_backend(backend.clone());
+        program.handle().send(TestMsg::Inc);
+        backend.push_event(Event::Key(KeyEvent {
+            code: KeyCode::Char('c'),
+            modifiers: KeyModifiers::CONTROL,
+        }));
+
+        let (model, outcome) = program.run_with_outcome::<()>().unwrap();
+        assert_eq!(model.count, 1);
+        assert_eq!(outcome, Outcome::Interrupted(Signal::Interrupt));
+
+        let output = backend.output();
+        assert!(output.starts_with("\x1b[?1049h"));
+        assert!(output.contains("Count: 0"));
+        assert!(output.contains("Count: 1"));
+        assert!(output.contains("\x1b[?1049l"));
+        assert!(!backend.is_raw_mode());
+    }
+
+    #[test]
+    fn test_quits_when_stream_input_ends() {
+        use crate::terminal::StreamBackend;
+
+        let input = io::Cursor::new(b"ab".to_vec());
+        let backend = StreamBackend::new(input, Vec::new());
+        let program = Program::new(TickerModel { log: Vec::new() }).with_backend(backend);
+
+        let (model, outcome) = program.run_with_outcome::<()>().unwrap();
+        let keys: Vec<_> = model.log.iter().filter(|msg| *msg != "tick").collect();
+        assert_eq!(keys, ["a", "b"]);
+        assert_eq!(outcome, Outcome::Quit);
+    }
+
+    /// Model exposing a channel and a stream subscription while `listening`.
+    struct ListeningModel {
+        listening: bool,
+        received: Vec<u32>,
+        receiver: crate::runtime::SharedReceiver<u32>,
+        stream: Arc<std::sync::Mutex<Option<BoxStream<u32>>>>,
+    }
+
+    impl Model for ListeningModel {
+        type Message = u32;
+
+        fn init(&self) -> Option<Cmd<Self::Message>> {
+            None
+        }
+
+        fn update(&mut self, msg: Self::Message) -> Option<Cmd<Self::Message>> {
+            self.received.push(msg);
+            None
+        }
+
+        fn view(&self) -> String {
+            String::new()
+        }
 
-                // Call the callback with the result
-                let msg = on_exit(result);
-                if let Some(next_cmd) = self.model.update(msg) {
-                    self.process_command_with_ticks(next_cmd, pending_ticks)
-                } else {
-                    Ok(false)
-                }
-            },
+        fn subscriptions(&self) -> Sub<Self::Message> {
+            if !self.listening {
+                return Sub::none();
+            }
+            let stream = Arc::clone(&self.stream);
+            Sub::batch(vec![
+                Sub::channel("channel", self.receiver.clone()),
+                Sub::stream_with("stream", move || {
+                    stream.lock().unwrap().take().unwrap_or_else(|| Box::pin(Pending))
+                }),
+            ])
         }
     }
 
-    /// Run in non-interactive mode (CI, piped input).
-    ///
-    /// In non-interactive mode, the program displays the initial view
-    /// and returns immediately. This is appropriate for:
-    /// - CI environments
-    /// - Piped input/output
-    /// - Scripts
-    ///
-    /// For accessible mode with screen readers, use `Form::run_accessible()`
-    /// or implement custom accessible handling using the `Accessible` trait.
-    fn run_non_interactive(self) -> io::Result<M> {
-        use super::accessible::strip_ansi;
+    /// Stream that never yields.
+    struct Pending;
 
-        // In non-interactive mode, display a stripped view (no ANSI codes)
-        // This ensures clean output for CI/scripts
-        let view = self.model.view();
-        let clean_view = if self.options.accessible { strip_ansi(&view) } else { view };
-        println!("{}", clean_view);
+    impl futures_core::Stream for Pending {
+        type Item = u32;
 
-        // Note: For interactive accessible mode, use Form::run_accessible()
-        // or implement custom handling with the Accessible trait
-        if self.options.accessible {
-            eprintln!("Note: For interactive accessible mode, use Form::run_accessible()");
+        fn poll_next(
+            self: Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<Option<u32>> {
+            std::task::Poll::Pending
         }
-
-        Ok(self.model)
     }
 
-    /// Set up the terminal for TUI mode.
-    fn setup_terminal(&self) -> io::Result<()> {
-        terminal::enable_raw_mode()?;
-
-        let mut stdout = io::stdout();
+    /// Stream that yields queued items and records when it is dropped.
+    struct TestStream {
+        items: std::sync::mpsc::Receiver<u32>,
+        dropped: Arc<std::sync::atomic::AtomicBool>,
+    }
 
-        if self.options.alt_screen {
-            execute!(stdout, terminal::EnterAlternateScreen, cursor::MoveTo(0, 0))?;
+    impl futures_core::Stream for TestStream {
+        type Item = u32;
+
+        fn poll_next(
+            self: Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<Option<u32>> {
+            match self.items.try_recv() {
+                Ok(item) => std::task::Poll::Ready(Some(item)),
+                Err(mpsc::TryRecvError::Disconnected) => std::task::Poll::Ready(None),
+                Err(mpsc::TryRecvError::Empty) => {
+                    // Poll again shortly; good enough for a test
+                    cx.waker().wake_by_ref();
+                    std::thread::sleep(Duration::from_millis(1));
+                    std::task::Poll::Pending
+                },
+            }
         }
+    }
 
-        if self.options.mouse {
-            execute!(stdout, event::EnableMouseCapture)?;
+    impl Drop for TestStream {
+        fn drop(&mut self) {
+            self.dropped.store(true, std::sync::atomic::Ordering::SeqCst);
         }
+    }
 
-        if self.options.bracketed_paste {
-            execute!(stdout, event::EnableBracketedPaste)?;
+    #[test]
+    fn test_channel_subscription() {
+        let (tx, rx) = mpsc::channel();
+        let model = ListeningModel {
+            listening: true,
+            received: Vec::new(),
+            receiver: rx.into(),
+            stream: Arc::default(),
+        };
+        let mut program = Program::new(model).with_fps(10);
+        program.set_waker(Waker::new(|| {}));
+        program.refresh_subscriptions();
+
+        // Messages are forwarded as they arrive, so the loop need not poll
+        assert_eq!(program.poll_timeout(Instant::now()), Duration::MAX);
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        while program.model.received.len() < 2 {
+            let event = program.events_rx.recv_timeout(Duration::from_secs(1)).unwrap();
+            program.handle_runtime_event(event).unwrap();
         }
+        assert_eq!(program.model.received, vec![1, 2]);
+
+        // Once the subscription is dropped the channel is no longer read
+        program.model.listening = false;
+        program.refresh_subscriptions();
+        tx.send(3).unwrap();
+        assert_eq!(program.model.receiver.recv(&CancelToken::default()), Some(3));
+        assert!(program.events_rx.try_recv().is_err());
+    }
 
-        if self.options.focus_change {
-            execute!(stdout, event::EnableFocusChange)?;
+    #[test]
+    fn test_stream_subscription() {
+        let (item_tx, item_rx) = mpsc::channel();
+        let dropped = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let stream = TestStream { items: item_rx, dropped: Arc::clone(&dropped) };
+        let (_tx, rx) = mpsc::channel();
+        let model = ListeningModel {
+            listening: true,
+            received: Vec::new(),
+            receiver: rx.into(),
+            stream: Arc::new(std::sync::Mutex::new(Some(Box::pin(stream)))),
+        };
+        let mut program = Program::new(model);
+        program.refresh_subscriptions();
+
+        item_tx.send(10).unwrap();
+        item_tx.send(20).unwrap();
+        while program.model.received.len() < 2 {
+            let event = program.events_rx.recv_timeout(Duration::from_secs(1)).unwrap();
+            program.handle_runtime_event(event).unwrap();
         }
+        assert_eq!(program.model.received, vec![10, 20]);
 
-        execute!(stdout, cursor::Hide)?;
+        // Re-declaring keeps the running stream without building another
+        program.refresh_subscriptions();
+        assert!(program.model.stream.lock().unwrap().is_none());
+        assert!(!dropped.load(std::sync::atomic::Ordering::SeqCst));
 
-        Ok(())
+        // Removing the subscription cancels and drops the stream
+        program.model.listening = false;
+        program.refresh_subscriptions();
+        assert!(dropped.load(std::sync::atomic::Ordering::SeqCst));
     }
 
-    /// Tear down the terminal, restoring original state.
-    fn teardown_terminal(&self) -> io::Result<()> {
-        let mut stdout = io::stdout();
+    #[test]
+    fn test_async_command_respects_filter() {
+        let mut program = Program::new(TestModel { count: 0 }).with_filter(|_model, _msg| None);
+
+        program.process_command(Cmd::perform_async(async { TestMsg::Inc })).unwrap();
+        settle(&mut program);
+        assert_eq!(program.model.count, 0);
+    }
+
+    /// Model that asks for confirmation instead of quitting on Ctrl+C.
+    struct ConfirmModel {
+        confirming: bool,
+    }
 
-        execute!(stdout, cursor::Show)?;
+    impl Model for ConfirmModel {
+        type Message = ();
 
-        if self.options.focus_change {
-            execute!(stdout, event::DisableFocusChange)?;
+        fn init(&self) -> Option<Cmd<Self::Message>> {
+            None
         }
 
-        if self.options.bracketed_paste {
-            execute!(stdout, event::DisableBracketedPaste)?;
+        fn update(&mut self, _msg: Self::Message) -> Option<Cmd<Self::Message>> {
+            self.confirming = true;
+            None
         }
 
-        if self.options.mouse {
-            execute!(stdout, event::DisableMouseCapture)?;
+        fn view(&self) -> String {
+            String::new()
         }
 
-        if self.options.alt_screen {
-            execute!(stdout, terminal::LeaveAlternateScreen)?;
+        fn handle_signal(&self, signal: Signal) -> Option<Self::Message> {
+            (signal == Signal::Interrupt).then_some(())
         }
+    }
 
-        terminal::disable_raw_mode()?;
+    #[test]
+    fn test_signals_quit_by_default() {
+        let mut program = Program::new(TestModel { count: 0 });
 
-        // Ensure cursor is at column 0 for clean output after TUI exits
-        execute!(stdout, cursor::MoveToColumn(0))?;
+        assert!(program.handle_signal(Signal::Interrupt).unwrap());
+        assert!(program.handle_signal(Signal::Terminate).unwrap());
+        assert!(program.handle_signal(Signal::Hangup).unwrap());
+    }
 
-        Ok(())
+    #[test]
+    fn test_model_handles_signal() {
+        let mut program = Program::new(ConfirmModel { confirming: false });
+
+        assert!(!program.handle_signal(Signal::Interrupt).unwrap());
+        assert!(program.model.confirming);
+
+        // Signals the model ignores keep their default behaviour
+        assert!(program.handle_signal(Signal::Terminate).unwrap());
     }
 
-    /// Render the current view.
-    fn render(&mut self, stdout: &mut impl Write) -> io::Result<()> {
-        let view = self.model.view();
+    #[test]
+    fn test_key_signal() {
+        use crate::terminal::{KeyEvent, KeyModifiers};
 
-        // Only redraw if the view changed
-        if view != self.last_view {
-            if self.options.alt_screen {
-                execute!(stdout, terminal::Clear(ClearType::All), cursor::MoveTo(0, 0))?;
-            } else {
-                // For inline mode, clear previous lines
-                let prev_lines = self.last_view.lines().count();
-                if prev_lines > 0 {
-                    execute!(
-                        stdout,
-                        cursor::MoveUp(prev_lines as u16),
-                        terminal::Clear(ClearType::FromCursorDown)
-                    )?;
-                }
-            }
+        let key = |c, modifiers| Event::Key(KeyEvent { code: KeyCode::Char(c), modifiers });
+
+        assert_eq!(key_signal(&key('c', KeyModifiers::CONTROL)), Some(Signal::Interrupt));
+        assert_eq!(key_signal(&key('z', KeyModifiers::CONTROL)), Some(Signal::Suspend));
+        assert_eq!(key_signal(&key('x', KeyModifiers::CONTROL)), None);
+        assert_eq!(key_signal(&key('c', KeyModifiers::NONE)), None);
+    }
+
+    #[test]
+    fn test_run_process_command() {
+        use std::process::Command;
+
+        // Just verify we can create a run_process command
+        let mut cmd = Command::new("echo");
+        cmd.arg("test");
+
+        let _cmd: Cmd<TestMsg> = Cmd::run_process(cmd, |result| {
+            if result.map(|s| s.success()).unwrap_or(false) { TestMsg::Inc } else { TestMsg::Quit }
+        });
+    }
+    #[test]
+    fn test_terminal_controls_ignored_without_terminal() {
+        let mut program = Program::new(TestModel { count: 0 });
+
+        let cmd = Cmd::batch(vec![
+            Cmd::set_title("title"),
+            Cmd::copy_to_clipboard("copied"),
+            Cmd::notify("Done", "all tasks finished"),
+            Cmd::bell(),
+            Cmd::perform(|| TestMsg::Inc),
+        ]);
+        assert!(!program.process_command(cmd).unwrap());
+        settle(&mut program);
+        assert_eq!(program.model.count, 1);
+    }
 
-            write!(stdout, "{}", view)?;
-            stdout.flush()?;
+    /// Model logging key presses and interval ticks in arrival order.
+    struct TickerModel {
+        log: Vec<String>,
+    }
+
+    impl Model for TickerModel {
+        type Message = String;
 
-            self.last_view = view;
+        fn init(&self) -> Option<Cmd<Self::Message>> {
+            None
         }
 
-        Ok(())
+        fn update(&mut self, msg: Self::Message) -> Option<Cmd<Self::Message>> {
+            self.log.push(msg);
+            None
+        }
+
+        fn view(&self) -> String {
+            self.log.join(" ")
+        }
+
+        fn handle_event(&self, event: Event) -> Option<Self::Message> {
+            match event {
+                Event::Key(key) => match key.code {
+                    KeyCode::Char(c) => Some(c.to_string()),
+                    _ => None,
+                },
+                Event::Resize { width, height } => Some(format!("{}x{}", width, height)),
+                _ => None,
+            }
+        }
+
+        fn subscriptions(&self) -> Sub<Self::Message> {
+            Sub::every_millis("tick", 100, || "tick".to_string())
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_recording_replays_identically() {
+        use crate::{
+            runtime::Recording,
+            terminal::{KeyEvent, KeyModifiers},
+            testing::TestProgram,
+        };
+
+        let key = |c, modifiers| Event::Key(KeyEvent { code: KeyCode::Char(c), modifiers });
+
+        let path = std::env::temp_dir().join(format!("teapot-recording-{}", std::process::id()));
+        let start = Instant::now();
+        let mut program = Program::new(TickerModel { log: Vec::new() });
+        program.set_clock(start);
+        program.recorder = Some(Recorder::create(&path, start, Some((40, 10))).unwrap());
+        program.start().unwrap();
+
+        // Drive the program as the event loop would, with irregular timing
+        let at = |millis| start + Duration::from_millis(millis);
+        let step = |program: &mut Program<TickerModel>, millis, event: Option<Event>| {
+            program.set_clock(at(millis));
+            match event {
+                Some(event) => program.handle_input(event).unwrap(),
+                None => program.process_pending(at(millis), &mut |_| Ok(())).unwrap(),
+            };
+            program.refresh_subscriptions();
+        };
+        step(&mut program, 130, None);
+        step(&mut program, 170, Some(key('a', KeyModifiers::NONE)));
+        step(&mut program, 245, None);
+        step(&mut program, 250, Some(Event::Resize { width: 60, height: 20 }));
+        step(&mut program, 320, Some(key('b', KeyModifiers::CONTROL)));
+        step(&mut program, 333, None);
+        step(&mut program, 350, None);
+
+        let recorded = program.into_model().log;
+        assert_eq!(recorded, vec!["tick", "a", "tick", "60x20", "b", "tick"]);
+
+        let recording = Recording::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(recording.size, Some((40, 10)));
+        // The tick at 350ms fired nothing, so it was not recorded
+        assert_eq!(recording.steps.len(), 6);
+
+        let mut replay = TestProgram::new(TickerModel { log: Vec::new() });
+        replay.replay(&recording);
+        assert_eq!(replay.model().log, recorded);
+        assert_eq!(replay.view(), "tick a tick 60x20 b tick");
+    }
 
-    struct TestModel {
-        count: i32,
+    #[test]
+    fn test_recording_skips_channel_messages() {
+        let path =
+            std::env::temp_dir().join(format!("teapot-recording-channel-{}", std::process::id()));
+        let (tx, rx) = mpsc::channel();
+        let model = ListeningModel {
+            listening: true,
+            received: Vec::new(),
+            receiver: rx.into(),
+            stream: Arc::default(),
+        };
+        let start = Instant::now();
+        let mut program = Program::new(model);
+        program.set_clock(start);
+        program.recorder = Some(Recorder::create(&path, start, None).unwrap());
+        program.start().unwrap();
+
+        tx.send(1).unwrap();
+        let event = program.events_rx.recv_timeout(Duration::from_secs(1)).unwrap();
+        program.handle_runtime_event(event).unwrap();
+        program.process_pending(start + Duration::from_millis(10), &mut |_| Ok(())).unwrap();
+        program.handle().send(2);
+        program.process_pending(start + Duration::from_millis(20), &mut |_| Ok(())).unwrap();
+        assert_eq!(program.model.received, vec![1, 2]);
+        drop(program);
+
+        let recording = crate::runtime::Recording::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(recording.steps.is_empty());
     }
 
-    enum TestMsg {
-        Inc,
-        #[allow(dead_code)]
-        Quit,
+    #[test]
+    fn test_inspector_overlay() {
+        use crate::{
+            output::strip_ansi,
+            terminal::{KeyEvent, KeyModifiers},
+        };
+
+        let key =
+            |c| Event::Key(KeyEvent { code: KeyCode::Char(c), modifiers: KeyModifiers::NONE });
+        let backend = MemoryBackend::new(80, 24);
+        let mut program = Program::new(TickerModel { log: Vec::new() })
+            .with_inspector(Inspector::new().toggle_key(KeyCode::Char('?')).debug_messages())
+            .with_backend(backend.clone());
+        program.start().unwrap();
+
+        program.handle_input(key('a')).unwrap();
+        program.handle_input(Event::Resize { width: 60, height: 20 }).unwrap();
+
+        let render = |program: &mut Program<TickerModel>| {
+            let drawn = backend.output().len();
+            program.render(Instant::now()).unwrap();
+            strip_ansi(&backend.output()[drawn..])
+        };
+        assert!(!render(&mut program).contains("inspector"));
+
+        // The toggle key is consumed and shows the panel
+        program.handle_input(key('?')).unwrap();
+        let out = render(&mut program);
+        assert_eq!(program.model.log, vec!["a", "60x20"]);
+        assert!(out.contains("inspector (?)"));
+        assert!(out.contains("size 60x20"));
+        assert!(out.contains("subscriptions: tick (in "));
+        assert!(out.contains("\"60x20\""));
+
+        program.handle_input(key('?')).unwrap();
+        assert!(!render(&mut program).contains("inspector"));
     }
 
-    impl Model for TestModel {
-        type Message = TestMsg;
+    /// Model driving a background process, replying to it on stdin.
+    #[cfg(unix)]
+    #[derive(Default)]
+    struct ProcessModel {
+        handle: Option<ProcessHandle>,
+        stdout: Vec<String>,
+        stderr: Vec<String>,
+        exit_code: Option<Option<i32>>,
+    }
+
+    #[cfg(unix)]
+    impl Model for ProcessModel {
+        type Message = ProcessEvent;
 
         fn init(&self) -> Option<Cmd<Self::Message>> {
             None
@@ -637,118 +3422,372 @@ mod tests {
 
         fn update(&mut self, msg: Self::Message) -> Option<Cmd<Self::Message>> {
             match msg {
-                TestMsg::Inc => self.count += 1,
-                TestMsg::Quit => return Some(Cmd::quit()),
+                ProcessEvent::Started(handle) => {
+                    let _ = handle.write_line("hello");
+                    self.handle = Some(handle);
+                },
+                ProcessEvent::Stdout(line) => self.stdout.push(line),
+                ProcessEvent::Stderr(line) => self.stderr.push(line),
+                ProcessEvent::Exited(status) => {
+                    self.exit_code = Some(status.ok().and_then(|s| s.code()))
+                },
             }
             None
         }
 
         fn view(&self) -> String {
-            format!("Count: {}", self.count)
+            self.stdout.join("\n")
         }
     }
 
+    #[cfg(unix)]
     #[test]
-    fn test_program_creation() {
-        let model = TestModel { count: 0 };
-        let program = Program::new(model);
-        assert_eq!(program.model.count, 0);
+    fn test_spawn_process_streams_output() {
+        use std::process::Command;
+
+        let mut program = Program::new(ProcessModel::default());
+        let mut sh = Command::new("sh");
+        sh.args(["-c", "read name; echo \"hi $name\"; echo done; echo oops >&2; exit 3"]);
+
+        program.process_command(Cmd::spawn_process(sh, |event| event)).unwrap();
+        settle(&mut program);
+
+        assert_eq!(program.model.stdout, vec!["hi hello", "done"]);
+        assert_eq!(program.model.stderr, vec!["oops"]);
+        assert_eq!(program.model.exit_code, Some(Some(3)));
+        let handle = program.model.handle.as_ref().unwrap();
+        assert!(handle.is_finished());
+        assert!(handle.write_line("late").is_err());
     }
 
+    #[cfg(unix)]
     #[test]
-    fn test_program_options() {
-        let options = ProgramOptions::fullscreen();
-        assert!(options.alt_screen);
-        assert!(options.mouse);
+    fn test_spawn_process_kill_and_sequence() {
+        use std::process::Command;
 
-        let options = ProgramOptions::inline();
-        assert!(!options.alt_screen);
-        assert!(!options.mouse);
+        let mut program = Program::new(ProcessModel::default());
+        let mut sleep = Command::new("sleep");
+        sleep.arg("10");
+
+        let cmd = Cmd::sequence(vec![
+            Cmd::spawn_process(sleep, |event| event),
+            Cmd::perform(|| ProcessEvent::Stdout("after".to_string())),
+        ]);
+        program.process_command(cmd).unwrap();
+
+        // Deliver the start event, then kill the process
+        let event = program.events_rx.recv_timeout(Duration::from_secs(1)).unwrap();
+        program.handle_runtime_event(event).unwrap();
+        program.model.handle.as_ref().unwrap().kill().unwrap();
+
+        let start = Instant::now();
+        settle(&mut program);
+        assert!(start.elapsed() < Duration::from_secs(5));
+        // Killed by a signal, so there is no exit code
+        assert_eq!(program.model.exit_code, Some(None));
+        assert_eq!(program.model.stdout, vec!["after"]);
     }
 
     #[test]
-    fn test_program_builder_pattern() {
-        let model = TestModel { count: 0 };
-        let program = Program::new(model)
-            .with_alt_screen()
-            .with_mouse()
-            .with_fps(30)
-            .with_bracketed_paste()
-            .with_focus_change()
-            .with_reduce_motion()
-            .with_tick_rate(Duration::from_millis(50));
+    fn test_spawn_process_reports_spawn_failure() {
+        use std::process::Command;
 
-        assert!(program.options.alt_screen);
-        assert!(program.options.mouse);
-        assert_eq!(program.options.fps, 30);
-        assert!(program.options.bracketed_paste);
-        assert!(program.options.focus_change);
-        assert!(program.options.reduce_motion);
-        assert_eq!(program.options.tick_rate, Duration::from_millis(50));
+        let mut program = Program::new(RecordingModel { log: Vec::new() });
+        let missing = Command::new("teapot-no-such-program");
+
+        let cmd = Cmd::spawn_process(missing, |event| match event {
+            ProcessEvent::Exited(Err(_)) => "failed",
+            _ => "unexpected",
+        });
+        program.process_command(cmd).unwrap();
+        settle(&mut program);
+
+        assert_eq!(program.model.log, vec!["failed"]);
     }
 
-    #[test]
-    fn test_fps_clamping() {
-        let model = TestModel { count: 0 };
+    /// Model that waits for a build step, then reports it.
+    struct BuildModel {
+        done: bool,
+    }
 
-        let program = Program::new(model).with_fps(0);
-        assert_eq!(program.options.fps, 1);
+    impl Model for BuildModel {
+        type Message = ();
 
-        let model = TestModel { count: 0 };
-        let program = Program::new(model).with_fps(200);
-        assert_eq!(program.options.fps, 120);
+        fn init(&self) -> Option<Cmd<Self::Message>> {
+            Some(Cmd::perform(|| std::thread::sleep(Duration::from_millis(20))))
+        }
+
+        fn update(&mut self, _msg: Self::Message) -> Option<Cmd<Self::Message>> {
+            self.done = true;
+            Some(Cmd::println("\x1b[32mbuild finished\x1b[0m"))
+        }
+
+        fn view(&self) -> String {
+            format!("Build\n  status: {}", if self.done { "done" } else { "running" })
+        }
     }
 
     #[test]
-    fn test_message_filter() {
-        let model = TestModel { count: 0 };
+    fn test_headless_runs_until_idle() {
+        let mut program = Program::new(BuildModel { done: false });
+        let mut out = Vec::new();
+        program.headless_loop(&mut out).unwrap();
+
+        assert!(program.model.done);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "Build\n  status: running\nbuild finished\n  status: done\n"
+        );
+    }
 
-        // Test that filter is None by default
-        let program = Program::new(model);
-        assert!(program.filter.is_none());
+    /// Model whose init command needs the tokio timer.
+    #[cfg(feature = "tokio")]
+    struct SleepModel {
+        woke: bool,
+    }
 
-        // Test that filter can be set
-        let model = TestModel { count: 0 };
-        let program = Program::new(model).with_filter(|_model, msg| {
-            // Pass all messages through
-            Some(msg)
-        });
-        assert!(program.filter.is_some());
+    #[cfg(feature = "tokio")]
+    impl Model for SleepModel {
+        type Message = ();
+
+        fn init(&self) -> Option<Cmd<Self::Message>> {
+            Some(Cmd::perform_async(tokio::time::sleep(Duration::from_millis(20))))
+        }
+
+        fn update(&mut self, _msg: Self::Message) -> Option<Cmd<Self::Message>> {
+            self.woke = true;
+            None
+        }
+
+        fn view(&self) -> String {
+            format!("woke: {}", self.woke)
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_async_commands_run_on_the_caller_runtime() {
+        let mut program = Program::new(SleepModel { woke: false })
+            .with_non_interactive_output(NonInteractiveOutput::Final);
+        program.runtime = Some(tokio::runtime::Handle::current());
+
+        let wake = program.notify_on_wake();
+        let mut out = Vec::new();
+        program.headless_loop_async(&mut out, &wake).await.unwrap();
+
+        assert!(program.model.woke);
+        assert_eq!(String::from_utf8(out).unwrap(), "woke: true\n");
+    }
+
+    /// Model that finishes a build while a spinner ticks forever.
+    struct SpinnerModel {
+        build: BuildModel,
+        frames: u32,
+    }
+
+    enum SpinnerMsg {
+        Built,
+        Frame,
+    }
+
+    impl Model for SpinnerModel {
+        type Message = SpinnerMsg;
+
+        fn init(&self) -> Option<Cmd<Self::Message>> {
+            self.build.init().map(|cmd| cmd.map(|()| SpinnerMsg::Built))
+        }
+
+        fn update(&mut self, msg: Self::Message) -> Option<Cmd<Self::Message>> {
+            match msg {
+                SpinnerMsg::Built => {
+                    self.build.update(()).map(|cmd| cmd.map(|()| SpinnerMsg::Built))
+                },
+                SpinnerMsg::Frame => {
+                    self.frames += 1;
+                    None
+                },
+            }
+        }
+
+        fn view(&self) -> String {
+            self.build.view()
+        }
+
+        fn subscriptions(&self) -> Sub<Self::Message> {
+            Sub::interval("spinner", Duration::from_millis(5), || SpinnerMsg::Frame)
+        }
     }
 
     #[test]
-    fn test_apply_filter_pass_through() {
-        let model = TestModel { count: 0 };
-        let program = Program::new(model).with_filter(|_model, msg| Some(msg));
+    fn test_headless_exit_ignores_intervals() {
+        let mut program =
+            Program::new(SpinnerModel { build: BuildModel { done: false }, frames: 0 })
+                .with_non_interactive_output(NonInteractiveOutput::Final)
+                .with_headless_exit(HeadlessExit {
+                    ignore_intervals: true,
+                    ..HeadlessExit::default()
+                });
+        let mut out = Vec::new();
+        program.headless_loop(&mut out).unwrap();
+
+        assert!(program.model.build.done);
+        assert_eq!(String::from_utf8(out).unwrap(), "build finished\nBuild\n  status: done\n");
+    }
 
-        // Filter should pass the message through
-        let result = program.apply_filter(TestMsg::Inc);
-        assert!(matches!(result, Some(TestMsg::Inc)));
+    #[test]
+    fn test_headless_exit_after_timeout() {
+        let mut program =
+            Program::new(SpinnerModel { build: BuildModel { done: false }, frames: 0 })
+                .with_non_interactive_output(NonInteractiveOutput::Final)
+                .with_headless_exit(HeadlessExit {
+                    ignore_handles: true,
+                    timeout: Some(Duration::from_millis(100)),
+                    ..HeadlessExit::default()
+                });
+        let _handle = program.handle();
+        let mut out = Vec::new();
+        program.headless_loop(&mut out).unwrap();
+
+        assert!(program.model.build.done);
+        assert!(program.model.frames > 0);
     }
 
     #[test]
-    fn test_apply_filter_block() {
-        let model = TestModel { count: 0 };
-        let program = Program::new(model).with_filter(|_model, _msg| {
-            // Block all messages
+    fn test_headless_snapshots_until_quit() {
+        let mut program = Program::new(BuildModel { done: false })
+            .with_non_interactive_output(NonInteractiveOutput::Snapshots(Duration::from_secs(60)));
+        let handle = program.handle();
+        handle.quit();
+
+        let mut out = Vec::new();
+        program.headless_loop(&mut out).unwrap();
+
+        // Quit arrived before the build finished
+        assert!(!program.model.done);
+        assert_eq!(String::from_utf8(out).unwrap(), "Build\n  status: running\n\n");
+    }
+
+    /// Model that asks for numbers to add, looking each one up in the background.
+    struct TallyModel {
+        total: i32,
+    }
+
+    enum TallyMsg {
+        Enter(i32),
+        Add(i32),
+        Quit,
+    }
+
+    impl Model for TallyModel {
+        type Message = TallyMsg;
+
+        fn init(&self) -> Option<Cmd<Self::Message>> {
             None
-        });
+        }
 
-        // Filter should block the message
-        let result = program.apply_filter(TestMsg::Inc);
-        assert!(result.is_none());
+        fn update(&mut self, msg: Self::Message) -> Option<Cmd<Self::Message>> {
+            match msg {
+                TallyMsg::Enter(n) => Some(Cmd::perform(move || TallyMsg::Add(n))),
+                TallyMsg::Add(n) => {
+                    self.total += n;
+                    Some(Cmd::println(format!("\x1b[1madded {}\x1b[0m", n)))
+                },
+                TallyMsg::Quit => Some(Cmd::quit()),
+            }
+        }
+
+        fn view(&self) -> String {
+            format!("Total: {}", self.total)
+        }
+    }
+
+    impl Accessible for TallyModel {
+        type Message = TallyMsg;
+
+        fn accessible_prompt(&self) -> String {
+            format!("Total is {}. Number to add?\n> ", self.total)
+        }
+
+        fn parse_accessible_input(&self, input: &str) -> Option<Self::Message> {
+            match input.trim() {
+                "q" => Some(TallyMsg::Quit),
+                n => n.parse().ok().map(TallyMsg::Enter),
+            }
+        }
+
+        fn is_accessible_complete(&self) -> bool {
+            self.total >= 10
+        }
     }
 
     #[test]
-    fn test_run_process_command() {
-        use std::process::Command;
+    fn test_accessible_reads_answers_from_tty() {
+        // A file standing in for the controlling terminal
+        let path = std::env::temp_dir().join(format!("teapot-tty-{}", std::process::id()));
+        std::fs::write(&path, "4\n6\n").unwrap();
+        let tty = std::fs::File::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let mut program = Program::new(TallyModel { total: 0 })
+            .with_accessible_model()
+            .with_input(InputSource::Tty)
+            .with_output(OutputStream::Stderr);
+        let adapter = program.accessible.take().unwrap();
+        let input = program.options.input.reader_with(|| Ok(tty));
+        let mut out = Vec::new();
+        let (model, _) = program.run_accessible_on(adapter, input, &mut out).unwrap();
+        assert_eq!(model.total, 10);
+        assert!(String::from_utf8(out).unwrap().ends_with("added 6\n"));
+
+        // Without a terminal to read from, the program fails instead of
+        // reading stdin
+        let mut program = Program::new(TallyModel { total: 0 })
+            .with_accessible_model()
+            .with_input(InputSource::Tty);
+        let adapter = program.accessible.take().unwrap();
+        let input = program.options.input.reader_with(|| Err(io::ErrorKind::NotFound.into()));
+        let result = program.run_accessible_on(adapter, input, &mut Vec::new());
+        assert!(
+            matches!(result, Err(ProgramError::Io(err)) if err.kind() == io::ErrorKind::NotFound)
+        );
+    }
 
-        // Just verify we can create a run_process command
-        let mut cmd = Command::new("echo");
-        cmd.arg("test");
+    fn run_accessible_with(program: &mut Program<TallyModel>, input: &str) -> String {
+        let adapter = program.accessible.take().expect("no accessible adapter");
+        let mut out = Vec::new();
+        program.accessible_loop(&adapter, &mut input.as_bytes(), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
 
-        let _cmd: Cmd<TestMsg> = Cmd::run_process(cmd, |result| {
-            if result.map(|s| s.success()).unwrap_or(false) { TestMsg::Inc } else { TestMsg::Quit }
-        });
+    #[test]
+    fn test_accessible_model_prompts_until_complete() {
+        let mut program = Program::new(TallyModel { total: 0 }).with_accessible_model();
+        let output = run_accessible_with(&mut program, "4\nfour\n6\n1\n");
+
+        assert_eq!(program.model.total, 10);
+        assert_eq!(
+            output,
+            "Total is 0. Number to add?\n> added 4\n\
+             Total is 4. Number to add?\n> Invalid input, please try again.\n\
+             Total is 4. Number to add?\n> added 6\n"
+        );
+    }
+
+    #[test]
+    fn test_accessible_adapter_quit_and_end_of_input() {
+        let mut program = Program::new(TallyModel { total: 0 }).with_accessible_model();
+        let output = run_accessible_with(&mut program, "2\nq\n3\n");
+        assert_eq!(program.model.total, 2);
+        assert!(output.ends_with("Total is 2. Number to add?\n> "));
+
+        // A custom adapter, with input ending before the model completes
+        let mut program =
+            Program::new(TallyModel { total: 0 }).with_accessible_adapter(AccessibleAdapter::new(
+                |model: &TallyModel| format!("[{}] ", model.total),
+                |_, input| input.parse().ok().map(TallyMsg::Add),
+            ));
+        let output = run_accessible_with(&mut program, "5\n7");
+        assert_eq!(program.model.total, 12);
+        assert_eq!(output, "[0] added 5\n[5] added 7\n[12] \n");
     }
 }
@@ -0,0 +1,559 @@
+//! Recording and replaying terminal sessions.
+//!
+//! A program started with [`Program::record_to`] writes every terminal event
+//! it receives, and every moment its timers and interval subscriptions
+//! fired, to a text file. Replaying that file with [`TestProgram::replay`]
+//! feeds the same events to a fresh model at the same virtual times, so the
+//! model goes through the same `update` calls and renders the same frames.
+//! This makes a recording a complete bug report for input-driven problems.
+//!
+//! Messages from any other source are not recorded:
+//!
+//! - results of background commands and processes
+//! - messages, quits and repaints sent through a `ProgramHandle`
+//! - channel, stream and file-watch subscriptions
+//! - process signals
+//!
+//! During replay these arrive as the replaying program produces them, if at
+//! all, so a model relying on them may diverge from the recorded session.
+//!
+//! # Format
+//!
+//! Recordings are UTF-8 text, one entry per line. Times are seconds since
+//! the program started, with microsecond precision. Lines starting with `#`
+//! are comments.
+//!
+//! ```text
+//! teapot-recording 1
+//! size 80 24
+//! 0.512003 key "j"
+//! 0.730120 key "c" ctrl
+//! 1.000113 tick
+//! 1.204551 mouse down-left 10 4 shift
+//! 1.733018 paste "two\nlines"
+//! 2.010448 resize 100 30
+//! 2.500000 focus-lost
+//! ```
+//!
+//! Keys are either a quoted character or a name such as `enter`, `page-up`
+//! or `f5`. Modifiers (`shift`, `ctrl`, `alt`, `super`, `hyper`, `meta`)
+//! are joined with `+`.
+//!
+//! [`Program::record_to`]: super::Program::record_to
+//! [`TestProgram::replay`]: crate::testing::TestProgram::replay
+
+use std::{
+    fmt::{self, Write as _},
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use crate::terminal::{
+    Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+
+/// First line of every recording, naming the format version.
+const HEADER: &str = "teapot-recording 1";
+
+/// Modifier names, in the order they are written.
+const MODIFIERS: [(&str, KeyModifiers); 6] = [
+    ("shift", KeyModifiers::SHIFT),
+    ("ctrl", KeyModifiers::CONTROL),
+    ("alt", KeyModifiers::ALT),
+    ("super", KeyModifiers::SUPER),
+    ("hyper", KeyModifiers::HYPER),
+    ("meta", KeyModifiers::META),
+];
+
+/// Named keys; characters and function keys are handled separately.
+const KEYS: [(&str, KeyCode); 22] = [
+    ("backspace", KeyCode::Backspace),
+    ("enter", KeyCode::Enter),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("page-up", KeyCode::PageUp),
+    ("page-down", KeyCode::PageDown),
+    ("tab", KeyCode::Tab),
+    ("back-tab", KeyCode::BackTab),
+    ("delete", KeyCode::Delete),
+    ("insert", KeyCode::Insert),
+    ("esc", KeyCode::Esc),
+    ("caps-lock", KeyCode::CapsLock),
+    ("scroll-lock", KeyCode::ScrollLock),
+    ("num-lock", KeyCode::NumLock),
+    ("print-screen", KeyCode::PrintScreen),
+    ("pause", KeyCode::Pause),
+    ("menu", KeyCode::Menu),
+    ("null", KeyCode::Null),
+];
+
+/// What happened at a recorded moment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StepKind {
+    /// A terminal event reached the program.
+    Event(Event),
+    /// Timers (`Cmd::tick`, `Cmd::debounce`) and interval subscriptions
+    /// that were due fired.
+    Tick,
+}
+
+/// One entry of a recording.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    /// Time since the program started.
+    pub at: Duration,
+    /// What happened.
+    pub kind: StepKind,
+}
+
+/// A recorded terminal session.
+///
+/// # Example
+///
+/// ```rust
+/// use teapot::runtime::{Recording, StepKind};
+///
+/// let recording: Recording = "teapot-recording 1\nsize 80 24\n0.250000 key \"q\"\n"
+///     .parse()
+///     .unwrap();
+///
+/// assert_eq!(recording.size, Some((80, 24)));
+/// assert!(matches!(recording.steps[0].kind, StepKind::Event(_)));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Recording {
+    /// Size of the terminal when recording started, if known.
+    pub size: Option<(u16, u16)>,
+    /// Recorded steps, in order.
+    pub steps: Vec<Step>,
+}
+
+impl Recording {
+    /// Read a recording from a file.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        std::fs::read_to_string(path)?.parse()
+    }
+
+    /// Write the recording to a file.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.to_string())
+    }
+}
+
+impl fmt::Display for Recording {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        if let Some((width, height)) = self.size {
+            writeln!(f, "size {} {}", width, height)?;
+        }
+        for step in &self.steps {
+            writeln!(f, "{}", format_step(step))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Recording {
+    type Err = io::Error;
+
+    fn from_str(text: &str) -> io::Result<Self> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+
+        match lines.next() {
+            Some((_, HEADER)) => {},
+            other => {
+                let number = other.map_or(1, |(number, _)| number);
+                return Err(invalid(number, format!("expected {:?}", HEADER)));
+            },
+        }
+
+        let mut recording = Recording::default();
+        for (number, line) in lines {
+            let tokens = tokenize(line).map_err(|message| invalid(number, message))?;
+            match tokens.as_slice() {
+                [Token::Word(size), width, height] if size == "size" => {
+                    let width = number_token(width).map_err(|message| invalid(number, message))?;
+                    let height =
+                        number_token(height).map_err(|message| invalid(number, message))?;
+                    recording.size = Some((width, height));
+                },
+                tokens => {
+                    let step = parse_step(tokens).map_err(|message| invalid(number, message))?;
+                    recording.steps.push(step);
+                },
+            }
+        }
+        Ok(recording)
+    }
+}
+
+/// Writes steps to a recording file as they happen.
+pub(crate) struct Recorder {
+    out: BufWriter<File>,
+    start: Instant,
+}
+
+impl Recorder {
+    /// Create the recording file, with times measured from `start`.
+    pub fn create(path: &Path, start: Instant, size: Option<(u16, u16)>) -> io::Result<Self> {
+        let mut out = BufWriter::new(File::create(path)?);
+        writeln!(out, "{}", HEADER)?;
+        if let Some((width, height)) = size {
+            writeln!(out, "size {} {}", width, height)?;
+        }
+        out.flush()?;
+        Ok(Self { out, start })
+    }
+
+    /// Append a step that happened at `now`.
+    ///
+    /// Each step is flushed, so the recording survives a crash.
+    pub fn record(&mut self, now: Instant, kind: StepKind) -> io::Result<()> {
+        let step = Step { at: now.saturating_duration_since(self.start), kind };
+        writeln!(self.out, "{}", format_step(&step))?;
+        self.out.flush()
+    }
+}
+
+/// A token of a recording line.
+#[derive(Debug, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+}
+
+fn invalid(line: usize, message: impl fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("recording line {}: {}", line, message))
+}
+
+/// Format a step as a line, without the line ending.
+fn format_step(step: &Step) -> String {
+    // Rounded up, so a tick is never replayed before its timer is due
+    let micros = step.at.as_nanos().div_ceil(1000);
+    let mut line = format!("{}.{:06} ", micros / 1_000_000, micros % 1_000_000);
+
+    match &step.kind {
+        StepKind::Tick => line.push_str("tick"),
+        StepKind::Event(Event::Key(key)) => {
+            line.push_str("key ");
+            match key.code {
+                KeyCode::Char(c) => {
+                    let _ = write!(line, "{:?}", c.to_string());
+                },
+                KeyCode::F(n) => {
+                    let _ = write!(line, "f{}", n);
+                },
+                code => {
+                    let name = KEYS.iter().find(|(_, key)| *key == code).map_or("null", |(n, _)| n);
+                    line.push_str(name);
+                },
+            }
+            push_modifiers(&mut line, key.modifiers);
+        },
+        StepKind::Event(Event::Mouse(mouse)) => {
+            let _ = write!(line, "mouse {} {} {}", mouse_kind(mouse.kind), mouse.column, mouse.row);
+            push_modifiers(&mut line, mouse.modifiers);
+        },
+        StepKind::Event(Event::Resize { width, height }) => {
+            let _ = write!(line, "resize {} {}", width, height);
+        },
+        StepKind::Event(Event::FocusGained) => line.push_str("focus-gained"),
+        StepKind::Event(Event::FocusLost) => line.push_str("focus-lost"),
+        StepKind::Event(Event::Paste(text)) => {
+            let _ = write!(line, "paste {:?}", text);
+        },
+    }
+    line
+}
+
+fn push_modifiers(line: &mut String, modifiers: KeyModifiers) {
+    let names: Vec<&str> = MODIFIERS
+        .iter()
+        .filter(|(_, modifier)| modifiers.contains(*modifier))
+        .map(|(name, _)| *name)
+        .collect();
+    if !names.is_empty() {
+        line.push(' ');
+        line.push_str(&names.join("+"));
+    }
+}
+
+fn mouse_kind(kind: MouseEventKind) -> String {
+    let button = |button: MouseButton| match button {
+        MouseButton::Left => "left",
+        MouseButton::Right => "right",
+        MouseButton::Middle => "middle",
+    };
+    match kind {
+        MouseEventKind::Down(b) => format!("down-{}", button(b)),
+        MouseEventKind::Up(b) => format!("up-{}", button(b)),
+        MouseEventKind::Drag(b) => format!("drag-{}", button(b)),
+        MouseEventKind::Moved => "moved".to_string(),
+        MouseEventKind::ScrollDown => "scroll-down".to_string(),
+        MouseEventKind::ScrollUp => "scroll-up".to_string(),
+        MouseEventKind::ScrollLeft => "scroll-left".to_string(),
+        MouseEventKind::ScrollRight => "scroll-right".to_string(),
+    }
+}
+
+/// Parse the tokens of a step line.
+fn parse_step(tokens: &[Token]) -> Result<Step, String> {
+    let (time, kind, args) = match tokens {
+        [Token::Word(time), Token::Word(kind), args @ ..] => (time, kind.as_str(), args),
+        _ => return Err("expected a time and an entry kind".to_string()),
+    };
+    let at = parse_time(time)?;
+
+    let kind = match (kind, args) {
+        ("tick", []) => StepKind::Tick,
+        ("key", [code, modifiers @ ..]) => {
+            let code = match code {
+                Token::Quoted(text) => {
+                    let mut chars = text.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(c), None) => KeyCode::Char(c),
+                        _ => return Err(format!("expected one character, got {:?}", text)),
+                    }
+                },
+                Token::Word(name) => parse_key_name(name)?,
+            };
+            let modifiers = parse_modifiers(modifiers)?;
+            StepKind::Event(Event::Key(KeyEvent { code, modifiers }))
+        },
+        ("mouse", [Token::Word(kind), column, row, modifiers @ ..]) => {
+            StepKind::Event(Event::Mouse(MouseEvent {
+                kind: parse_mouse_kind(kind)?,
+                column: number_token(column)?,
+                row: number_token(row)?,
+                modifiers: parse_modifiers(modifiers)?,
+            }))
+        },
+        ("resize", [width, height]) => StepKind::Event(Event::Resize {
+            width: number_token(width)?,
+            height: number_token(height)?,
+        }),
+        ("focus-gained", []) => StepKind::Event(Event::FocusGained),
+        ("focus-lost", []) => StepKind::Event(Event::FocusLost),
+        ("paste", [Token::Quoted(text)]) => StepKind::Event(Event::Paste(text.clone())),
+        (kind, _) => return Err(format!("unrecognized entry {:?}", kind)),
+    };
+    Ok(Step { at, kind })
+}
+
+/// Parse `seconds.micros`.
+fn parse_time(text: &str) -> Result<Duration, String> {
+    let error = || format!("invalid time {:?}", text);
+    let (secs, frac) = text.split_once('.').unwrap_or((text, ""));
+    if frac.len() > 9 || !frac.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(error());
+    }
+    let secs: u64 = secs.parse().map_err(|_| error())?;
+    let nanos: u32 =
+        if frac.is_empty() { 0 } else { format!("{:0<9}", frac).parse().map_err(|_| error())? };
+    Ok(Duration::new(secs, nanos))
+}
+
+fn parse_key_name(name: &str) -> Result<KeyCode, String> {
+    if let Some((_, code)) = KEYS.iter().find(|(key, _)| *key == name) {
+        return Ok(*code);
+    }
+    name.strip_prefix('f')
+        .and_then(|n| n.parse().ok())
+        .map(KeyCode::F)
+        .ok_or_else(|| format!("unknown key {:?}", name))
+}
+
+fn parse_modifiers(tokens: &[Token]) -> Result<KeyModifiers, String> {
+    match tokens {
+        [] => Ok(KeyModifiers::NONE),
+        [Token::Word(names)] => names.split('+').try_fold(KeyModifiers::NONE, |all, name| {
+            MODIFIERS
+                .iter()
+                .find(|(modifier, _)| *modifier == name)
+                .map(|(_, modifier)| all | *modifier)
+                .ok_or_else(|| format!("unknown modifier {:?}", name))
+        }),
+        _ => Err("unexpected trailing input".to_string()),
+    }
+}
+
+fn parse_mouse_kind(kind: &str) -> Result<MouseEventKind, String> {
+    let button = |name: &str| match name {
+        "left" => Ok(MouseButton::Left),
+        "right" => Ok(MouseButton::Right),
+        "middle" => Ok(MouseButton::Middle),
+        _ => Err(format!("unknown mouse button {:?}", name)),
+    };
+    match kind.split_once('-') {
+        Some(("down", b)) => Ok(MouseEventKind::Down(button(b)?)),
+        Some(("up", b)) => Ok(MouseEventKind::Up(button(b)?)),
+        Some(("drag", b)) => Ok(MouseEventKind::Drag(button(b)?)),
+        _ => match kind {
+            "moved" => Ok(MouseEventKind::Moved),
+            "scroll-down" => Ok(MouseEventKind::ScrollDown),
+            "scroll-up" => Ok(MouseEventKind::ScrollUp),
+            "scroll-left" => Ok(MouseEventKind::ScrollLeft),
+            "scroll-right" => Ok(MouseEventKind::ScrollRight),
+            _ => Err(format!("unknown mouse event {:?}", kind)),
+        },
+    }
+}
+
+fn number_token(token: &Token) -> Result<u16, String> {
+    match token {
+        Token::Word(word) => word.parse().map_err(|_| format!("invalid number {:?}", word)),
+        Token::Quoted(text) => Err(format!("expected a number, got {:?}", text)),
+    }
+}
+
+/// Split a line into words and quoted strings, unescaping the strings.
+fn tokenize(line: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            tokens.push(Token::Quoted(unescape(&mut chars)?));
+        } else {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+            tokens.push(Token::Word(word));
+        }
+    }
+    Ok(tokens)
+}
+
+/// Read a quoted string written with `{:?}`, up to its closing quote.
+fn unescape(chars: &mut impl Iterator<Item = char>) -> Result<String, String> {
+    let mut text = String::new();
+    loop {
+        match chars.next() {
+            None => return Err("unterminated string".to_string()),
+            Some('"') => return Ok(text),
+            Some('\\') => {
+                let c = match chars.next() {
+                    Some('n') => '\n',
+                    Some('r') => '\r',
+                    Some('t') => '\t',
+                    Some('0') => '\0',
+                    Some(c @ ('\\' | '"' | '\'')) => c,
+                    Some('u') => {
+                        let hex: String = chars
+                            .by_ref()
+                            .skip_while(|c| *c == '{')
+                            .take_while(|c| *c != '}')
+                            .collect();
+                        u32::from_str_radix(&hex, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| format!("invalid escape \\u{{{}}}", hex))?
+                    },
+                    other => return Err(format!("invalid escape {:?}", other)),
+                };
+                text.push(c);
+            },
+            Some(c) => text.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> StepKind {
+        StepKind::Event(Event::Key(KeyEvent { code, modifiers }))
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let recording = Recording {
+            size: Some((80, 24)),
+            steps: vec![
+                Step {
+                    at: Duration::from_micros(512_003),
+                    kind: key(KeyCode::Char('j'), KeyModifiers::NONE),
+                },
+                Step {
+                    at: Duration::from_millis(730),
+                    kind: key(KeyCode::Char(' '), KeyModifiers::CONTROL | KeyModifiers::SHIFT),
+                },
+                Step { at: Duration::from_secs(1), kind: StepKind::Tick },
+                Step { at: Duration::from_secs(1), kind: key(KeyCode::PageUp, KeyModifiers::NONE) },
+                Step { at: Duration::from_secs(1), kind: key(KeyCode::F(12), KeyModifiers::ALT) },
+                Step {
+                    at: Duration::from_secs(1),
+                    kind: key(KeyCode::Char('"'), KeyModifiers::NONE),
+                },
+                Step {
+                    at: Duration::from_secs(2),
+                    kind: StepKind::Event(Event::Mouse(MouseEvent {
+                        kind: MouseEventKind::Down(MouseButton::Left),
+                        column: 10,
+                        row: 4,
+                        modifiers: KeyModifiers::SHIFT,
+                    })),
+                },
+                Step {
+                    at: Duration::from_secs(3),
+                    kind: StepKind::Event(Event::Paste(
+                        "two \"quoted\"\nlines\t\u{1b}é".to_string(),
+                    )),
+                },
+                Step {
+                    at: Duration::from_secs(4),
+                    kind: StepKind::Event(Event::Resize { width: 100, height: 30 }),
+                },
+                Step { at: Duration::from_secs(5), kind: StepKind::Event(Event::FocusLost) },
+            ],
+        };
+
+        let text = recording.to_string();
+        assert!(text.starts_with("teapot-recording 1\nsize 80 24\n0.512003 key \"j\"\n"));
+        assert!(text.contains("0.730000 key \" \" shift+ctrl\n"));
+        assert!(text.contains("2.000000 mouse down-left 10 4 shift\n"));
+
+        assert_eq!(text.parse::<Recording>().unwrap(), recording);
+    }
+
+    #[test]
+    fn test_times_round_up() {
+        let step = Step { at: Duration::from_nanos(1_000_000_001), kind: StepKind::Tick };
+        assert_eq!(format_step(&step), "1.000001 tick");
+        assert_eq!(parse_time("1.5").unwrap(), Duration::from_millis(1500));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!("size 80 24\n".parse::<Recording>().is_err());
+
+        let err =
+            "teapot-recording 1\n# comment\n\n0.1 key \"ab\"\n".parse::<Recording>().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("line 4"));
+
+        assert!("teapot-recording 1\n0.1 key enter hyperdrive\n".parse::<Recording>().is_err());
+        assert!("teapot-recording 1\n0.1 paste \"open\n".parse::<Recording>().is_err());
+        assert!("teapot-recording 1\nsoon tick\n".parse::<Recording>().is_err());
+    }
+}
@@ -0,0 +1,474 @@
+//! Line-diffing renderer.
+//!
+//! The renderer remembers the lines of the previous frame and only rewrites
+//! the lines that changed, instead of clearing the screen on every update.
+//! Each frame is built in memory and written with a single write and
+//! flush, so it reaches the terminal in one piece however the output is
+//! buffered. When the terminal supports it, the frame is also wrapped in
+//! synchronized-update sequences so the terminal shows it atomically.
+//! Together these avoid flicker on slow terminals and over SSH.
+//!
+//! In inline mode, lines printed with `Cmd::println` are written above the
+//! frame, where they scroll into the terminal's history while the frame
+//! stays at the bottom.
+//!
+//! The terminal cursor stays hidden unless the frame places it, in which
+//! case it is moved to its position after the lines are drawn.
+//!
+//! Output is written with explicit `\r\n` line breaks, since raw mode
+//! disables the terminal's newline translation.
+
+use std::{
+    io::{self, Write},
+    mem,
+};
+
+use crossterm::{
+    cursor::{self, SetCursorStyle},
+    queue,
+    terminal::{self, ClearType},
+};
+
+use super::frame::{Cursor, CursorShape, Frame};
+
+/// Draws frames to the terminal, repainting only changed lines.
+pub(crate) struct Renderer {
+    /// Draw at absolute positions on the alternate screen instead of
+    /// relative to the cursor
+    alt_screen: bool,
+    /// Wrap frames in synchronized-update sequences
+    synchronized: bool,
+    /// Lines of the frame currently on screen
+    lines: Vec<String>,
+    /// Row of the cursor within the frame (inline mode)
+    cursor_row: usize,
+    /// The visible cursor, if the frame on screen shows one
+    cursor: Option<Cursor>,
+    /// The window title last set
+    title: Option<String>,
+    /// Whether the next frame must repaint every line
+    dirty: bool,
+    /// Lines waiting to be printed above the frame
+    printed: Vec<String>,
+    /// Bytes of the frame being drawn, kept to reuse its allocation
+    buffer: Vec<u8>,
+}
+
+impl Renderer {
+    /// Create a renderer for the given screen mode.
+    pub fn new(alt_screen: bool, synchronized: bool) -> Self {
+        Self {
+            alt_screen,
+            synchronized,
+            lines: Vec::new(),
+            cursor_row: 0,
+            cursor: None,
+            title: None,
+            dirty: true,
+            printed: Vec::new(),
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Repaint every line on the next render, for example after a resize or
+    /// when something else has written over the frame.
+    pub fn invalidate(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Forget the frame on screen, so the next render starts afresh at the
+    /// cursor. Used after the terminal was handed to another program.
+    pub fn reset(&mut self) {
+        self.lines.clear();
+        self.cursor_row = 0;
+        self.cursor = None;
+        self.title = None;
+        self.dirty = true;
+    }
+
+    /// Queue text to be printed above the frame on the next render.
+    ///
+    /// Ignored on the alternate screen, which has no history to scroll into.
+    pub fn print_above(&mut self, text: &str) {
+        if !self.alt_screen {
+            self.printed.extend(
+                text.split('\n').map(|line| line.strip_suffix('\r').unwrap_or(line).to_string()),
+            );
+        }
+    }
+
+    /// The lines queued by [`Renderer::print_above`], not drawn yet.
+    pub fn printed(&self) -> &[String] {
+        &self.printed
+    }
+
+    /// Take the lines queued by [`Renderer::print_above`] without drawing them.
+    pub fn take_printed(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.printed)
+    }
+
+    /// Draw a frame, returning whether anything was written.
+    pub fn render(&mut self, out: &mut impl Write, frame: &Frame) -> io::Result<bool> {
+        let mut buffer = mem::take(&mut self.buffer);
+        buffer.clear();
+        let result = self.encode(&mut buffer, frame).and_then(|changed| {
+            if changed {
+                out.write_all(&buffer)?;
+                out.flush()?;
+            }
+            Ok(changed)
+        });
+        self.buffer = buffer;
+        result
+    }
+
+    /// Write the sequences drawing `frame` to `out`, returning whether
+    /// anything changed.
+    fn encode(&mut self, out: &mut Vec<u8>, frame: &Frame) -> io::Result<bool> {
+        let lines: Vec<&str> =
+            frame.content.split('\n').map(|line| line.strip_suffix('\r').unwrap_or(line)).collect();
+        let title = frame.title.as_ref().filter(|title| self.title.as_ref() != Some(*title));
+
+        if !self.dirty
+            && self.printed.is_empty()
+            && title.is_none()
+            && self.cursor == frame.cursor
+            && self.lines.iter().map(String::as_str).eq(lines.iter().copied())
+        {
+            return Ok(false);
+        }
+
+        if self.synchronized {
+            queue!(out, terminal::BeginSynchronizedUpdate)?;
+        }
+
+        // Keep the cursor out of sight while lines are rewritten
+        if self.cursor.is_some() {
+            queue!(out, cursor::Hide)?;
+        }
+
+        if let Some(title) = title {
+            queue!(out, terminal::SetTitle(title))?;
+            self.title = Some(title.clone());
+        }
+
+        if !self.printed.is_empty() {
+            self.draw_printed(out)?;
+        }
+
+        if self.alt_screen {
+            self.draw_fullscreen(out, &lines)?;
+        } else {
+            self.draw_inline(out, &lines)?;
+        }
+
+        self.lines = lines.into_iter().map(String::from).collect();
+        self.cursor_row = self.lines.len().saturating_sub(1);
+        self.dirty = false;
+
+        self.cursor = frame.cursor;
+        if let Some(cursor) = frame.cursor {
+            self.place_cursor(out, cursor)?;
+        }
+
+        if self.synchronized {
+            queue!(out, terminal::EndSynchronizedUpdate)?;
+        }
+
+        Ok(true)
+    }
+
+    /// Move the cursor to its place in the frame and show it.
+    fn place_cursor(&mut self, out: &mut impl Write, cursor: Cursor) -> io::Result<()> {
+        let row = usize::from(cursor.row).min(self.lines.len().saturating_sub(1));
+
+        if self.alt_screen {
+            queue!(out, cursor::MoveTo(cursor.column, row as u16))?;
+        } else {
+            // The cursor is on the last line after drawing inline
+            if row < self.cursor_row {
+                queue!(out, cursor::MoveUp((self.cursor_row - row) as u16))?;
+            }
+            queue!(out, cursor::MoveToColumn(cursor.column))?;
+            self.cursor_row = row;
+        }
+
+        queue!(out, cursor_style(cursor.shape), cursor::Show)
+    }
+
+    /// Whether a line differs from the one on screen at the same row.
+    fn changed(&self, row: usize, line: &str) -> bool {
+        self.dirty || self.lines.get(row).is_none_or(|previous| previous != line)
+    }
+
+    /// Draw a frame on the alternate screen using absolute positions.
+    fn draw_fullscreen(&self, out: &mut impl Write, lines: &[&str]) -> io::Result<()> {
+        if self.dirty {
+            queue!(out, terminal::Clear(ClearType::All))?;
+        }
+
+        for (row, line) in lines.iter().enumerate() {
+            if self.changed(row, line) {
+                // Clearing before writing keeps a full-width line's last cell intact
+                queue!(
+                    out,
+                    cursor::MoveTo(0, row as u16),
+                    terminal::Clear(ClearType::CurrentLine)
+                )?;
+                write!(out, "{}", line)?;
+            }
+        }
+
+        if !self.dirty && self.lines.len() > lines.len() {
+            queue!(
+                out,
+                cursor::MoveTo(0, lines.len() as u16),
+                terminal::Clear(ClearType::FromCursorDown)
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Replace the frame with the printed lines; the frame is then drawn
+    /// afresh below them.
+    fn draw_printed(&mut self, out: &mut impl Write) -> io::Result<()> {
+        queue!(out, cursor::MoveToColumn(0))?;
+        if self.cursor_row > 0 {
+            queue!(out, cursor::MoveUp(self.cursor_row as u16))?;
+        }
+        queue!(out, terminal::Clear(ClearType::FromCursorDown))?;
+
+        for line in self.printed.drain(..) {
+            write!(out, "{}\r\n", line)?;
+        }
+
+        self.lines.clear();
+        self.cursor_row = 0;
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Draw a frame below the shell prompt, relative to the cursor.
+    fn draw_inline(&self, out: &mut impl Write, lines: &[&str]) -> io::Result<()> {
+        // Return to the first line of the frame
+        queue!(out, cursor::MoveToColumn(0))?;
+        if self.cursor_row > 0 {
+            queue!(out, cursor::MoveUp(self.cursor_row as u16))?;
+        }
+
+        for (row, line) in lines.iter().enumerate() {
+            if row > 0 {
+                write!(out, "\r\n")?;
+            }
+            if self.changed(row, line) {
+                queue!(out, terminal::Clear(ClearType::CurrentLine))?;
+                write!(out, "{}", line)?;
+            }
+        }
+
+        // Erase lines left over from a taller previous frame
+        if self.lines.len() > lines.len() {
+            write!(out, "\r\n")?;
+            queue!(out, terminal::Clear(ClearType::FromCursorDown), cursor::MoveUp(1))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The escape sequence selecting a cursor shape.
+fn cursor_style(shape: CursorShape) -> SetCursorStyle {
+    match shape {
+        CursorShape::Default => SetCursorStyle::DefaultUserShape,
+        CursorShape::Block => SetCursorStyle::SteadyBlock,
+        CursorShape::BlinkingBlock => SetCursorStyle::BlinkingBlock,
+        CursorShape::Underline => SetCursorStyle::SteadyUnderScore,
+        CursorShape::BlinkingUnderline => SetCursorStyle::BlinkingUnderScore,
+        CursorShape::Bar => SetCursorStyle::SteadyBar,
+        CursorShape::BlinkingBar => SetCursorStyle::BlinkingBar,
+    }
+}
+
+/// Whether the terminal is likely to handle synchronized-update sequences.
+///
+/// Terminals without support ignore the sequences, but the Linux console and
+/// dumb terminals may print them, so they are left out there.
+pub(crate) fn supports_synchronized_output() -> bool {
+    !matches!(std::env::var("TERM").as_deref(), Ok("dumb") | Ok("linux"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Render a view and return what was written.
+    fn draw(renderer: &mut Renderer, view: &str) -> String {
+        draw_frame(renderer, &Frame::new(view))
+    }
+
+    /// Render a frame and return what was written.
+    fn draw_frame(renderer: &mut Renderer, frame: &Frame) -> String {
+        let mut out = Vec::new();
+        renderer.render(&mut out, frame).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_unchanged_frame_writes_nothing() {
+        let mut renderer = Renderer::new(true, false);
+
+        assert!(!draw(&mut renderer, "a\nb").is_empty());
+        assert!(draw(&mut renderer, "a\nb").is_empty());
+    }
+
+    #[test]
+    fn test_only_changed_lines_are_written() {
+        let mut renderer = Renderer::new(true, false);
+        draw(&mut renderer, "header\ncount: 1\nfooter");
+
+        let out = draw(&mut renderer, "header\ncount: 2\nfooter");
+        assert!(out.contains("count: 2"));
+        assert!(!out.contains("header"));
+        assert!(!out.contains("footer"));
+        // The whole screen is not cleared
+        assert!(!out.contains("\x1b[2J"));
+    }
+
+    #[test]
+    fn test_invalidate_repaints_everything() {
+        let mut renderer = Renderer::new(true, false);
+        draw(&mut renderer, "header\nbody");
+
+        renderer.invalidate();
+        let out = draw(&mut renderer, "header\nbody");
+        assert!(out.contains("\x1b[2J"));
+        assert!(out.contains("header") && out.contains("body"));
+    }
+
+    #[test]
+    fn test_inline_uses_explicit_line_breaks() {
+        let mut renderer = Renderer::new(false, false);
+
+        let out = draw(&mut renderer, "one\ntwo\nthree");
+        assert!(out.contains("one\r\n"));
+        assert!(out.contains("two\r\n"));
+
+        // Shrinking the frame clears the leftover lines
+        let out = draw(&mut renderer, "one");
+        assert!(out.contains("\x1b[2A"));
+        assert!(out.contains("\x1b[J"));
+    }
+
+    #[test]
+    fn test_printed_lines_go_above_the_frame() {
+        let mut renderer = Renderer::new(false, false);
+        draw(&mut renderer, "building...\n[==  ]");
+
+        renderer.print_above("✓ built foo");
+        let out = draw(&mut renderer, "building...\n[==  ]");
+
+        // The frame is erased, the line printed, and the frame redrawn below it
+        let printed = out.find("✓ built foo\r\n").unwrap();
+        assert!(out[..printed].contains("\x1b[1A\x1b[J"));
+        assert!(out[printed..].contains("building...\r\n"));
+        assert!(out[printed..].contains("[==  ]"));
+
+        // Printed lines are dropped on the alternate screen
+        let mut renderer = Renderer::new(true, false);
+        renderer.print_above("ignored");
+        assert!(renderer.take_printed().is_empty());
+    }
+
+    #[test]
+    fn test_synchronized_output() {
+        let mut renderer = Renderer::new(true, true);
+
+        let out = draw(&mut renderer, "frame");
+        assert!(out.starts_with("\x1b[?2026h"));
+        assert!(out.ends_with("\x1b[?2026l"));
+    }
+
+    #[test]
+    fn test_cursor_is_placed_and_shown() {
+        let mut renderer = Renderer::new(true, false);
+
+        let frame = Frame::new("Name:\n> ab").cursor(Cursor::new(4, 1).shape(CursorShape::Bar));
+        let out = draw_frame(&mut renderer, &frame);
+        // Moved to row 2, column 5 (1-based), bar shape, shown
+        assert!(out.ends_with("\x1b[2;5H\x1b[6 q\x1b[?25h"));
+
+        // Moving only the cursor redraws no lines
+        let out = draw_frame(&mut renderer, &Frame::new("Name:\n> ab").cursor(Cursor::new(3, 1)));
+        assert!(!out.contains("Name:"));
+        assert!(out.starts_with("\x1b[?25l"));
+        assert!(out.contains("\x1b[2;4H"));
+
+        // Without a cursor it stays hidden
+        let out = draw_frame(&mut renderer, &Frame::new("Name:\n> abc"));
+        assert!(!out.contains("\x1b[?25h"));
+    }
+
+    #[test]
+    fn test_inline_cursor_row_is_tracked() {
+        let mut renderer = Renderer::new(false, false);
+
+        let frame = Frame::new("> ab\nhelp\nfooter").cursor(Cursor::new(4, 0));
+        let out = draw_frame(&mut renderer, &frame);
+        assert!(out.contains("footer\x1b[2A\x1b[5G"));
+
+        // The next frame starts from the cursor's row, not the last line
+        let out = draw(&mut renderer, "> abc\nhelp\nfooter");
+        assert!(out.contains("\x1b[?25l\x1b[1G"));
+        assert!(!out.contains("\x1b[2A"));
+    }
+
+    #[test]
+    fn test_title_is_set_when_changed() {
+        let mut renderer = Renderer::new(true, false);
+
+        let out = draw_frame(&mut renderer, &Frame::new("a").title("Editor"));
+        assert!(out.contains("\x1b]0;Editor\x07"));
+
+        let out = draw_frame(&mut renderer, &Frame::new("a").title("Editor"));
+        assert!(out.is_empty());
+    }
+
+    /// Counts the writes and flushes reaching the terminal.
+    #[derive(Default)]
+    struct CountingWriter {
+        writes: usize,
+        flushes: usize,
+    }
+
+    impl Write for CountingWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.writes += 1;
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.flushes += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_frame_is_written_at_once() {
+        let mut renderer = Renderer::new(false, true);
+        renderer.print_above("log line");
+        let frame = Frame::new("one\ntwo\nthree").title("Editor").cursor(Cursor {
+            column: 1,
+            row: 1,
+            shape: CursorShape::Bar,
+        });
+
+        let mut out = CountingWriter::default();
+        assert!(renderer.render(&mut out, &frame).unwrap());
+        assert_eq!((out.writes, out.flushes), (1, 1));
+
+        // Nothing is written for an unchanged frame
+        assert!(!renderer.render(&mut out, &frame).unwrap());
+        assert_eq!((out.writes, out.flushes), (1, 1));
+    }
+}
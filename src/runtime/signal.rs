@@ -0,0 +1,236 @@
+//! Process signal handling.
+//!
+//! While a program runs in raw mode, the terminal no longer turns Ctrl+C and
+//! Ctrl+Z into signals, and signals sent by other processes (SIGTERM when a
+//! service manager stops us, SIGHUP when the terminal goes away) would kill
+//! the process without restoring the terminal. The runtime listens for these
+//! signals and translates them, along with unhandled Ctrl+C and Ctrl+Z key
+//! presses, into [`Signal`] values offered to [`Model::handle_signal`].
+//!
+//! Handlers are registered through signal-hook alongside any the host
+//! application installed, and removed again when the program ends.
+//! signal-hook cannot put back a signal's original disposition, so once a
+//! program has run, these signals no longer kill or stop the process unless
+//! the host handles them itself.
+//!
+//! [`Model::handle_signal`]: super::Model::handle_signal
+
+/// A process-level signal delivered to the model.
+///
+/// The runtime offers each signal to [`Model::handle_signal`]. If the model
+/// returns a message, it is delivered to `update` instead of the default
+/// behaviour:
+///
+/// | Signal      | Source                              | Default behaviour          |
+/// |-------------|-------------------------------------|----------------------------|
+/// | `Interrupt` | Unhandled Ctrl+C, SIGINT            | Quit                       |
+/// | `Terminate` | SIGTERM                             | Quit                       |
+/// | `Hangup`    | SIGHUP                              | Quit                       |
+/// | `Suspend`   | Unhandled Ctrl+Z, SIGTSTP           | Suspend the process        |
+/// | `Resume`    | Continued after suspend, SIGCONT    | Restore the terminal       |
+///
+/// Quitting always restores the terminal. Suspending restores the terminal
+/// before stopping the process and sets it up again when the process is
+/// continued (for example with `fg`).
+///
+/// [`Model::handle_signal`]: super::Model::handle_signal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Signal {
+    /// Interrupt request (Ctrl+C or SIGINT).
+    Interrupt,
+    /// Termination request (SIGTERM).
+    Terminate,
+    /// The controlling terminal was closed (SIGHUP).
+    Hangup,
+    /// Suspend request (Ctrl+Z or SIGTSTP).
+    Suspend,
+    /// The process was continued after being suspended (SIGCONT).
+    Resume,
+}
+
+#[cfg(unix)]
+mod imp {
+    use std::{
+        io::{self, Read},
+        os::unix::net::UnixStream,
+        sync::{
+            Arc,
+            atomic::{AtomicBool, Ordering},
+        },
+        thread,
+    };
+
+    use signal_hook::{
+        SigId,
+        consts::{SIGCONT, SIGHUP, SIGINT, SIGTERM, SIGTSTP},
+        flag, low_level,
+    };
+
+    use super::Signal;
+    use crate::terminal::Waker;
+
+    /// Signals listened for, in the order they are reported.
+    const SIGNALS: [(i32, Signal); 5] = [
+        (SIGINT, Signal::Interrupt),
+        (SIGTERM, Signal::Terminate),
+        (SIGHUP, Signal::Hangup),
+        (SIGTSTP, Signal::Suspend),
+        (SIGCONT, Signal::Resume),
+    ];
+
+    /// Records signals received while a program is running.
+    pub(crate) struct SignalListener {
+        pending: Vec<(Arc<AtomicBool>, Signal)>,
+        ids: Vec<SigId>,
+    }
+
+    impl SignalListener {
+        /// Start listening for signals, calling `waker` when one arrives.
+        pub fn install(waker: Option<Waker>) -> io::Result<Self> {
+            let mut pending = Vec::new();
+            let mut ids = Vec::new();
+            for (signal, kind) in SIGNALS {
+                let received = Arc::new(AtomicBool::new(false));
+                ids.push(flag::register(signal, Arc::clone(&received))?);
+                pending.push((received, kind));
+            }
+
+            // Wakers cannot be called from a signal handler: the handler
+            // writes to a pipe, and a thread calls the waker for it
+            if let Some(waker) = waker {
+                let (mut reader, writer) = UnixStream::pair()?;
+                writer.set_nonblocking(true)?;
+                for (signal, _) in SIGNALS {
+                    ids.push(low_level::pipe::register(signal, writer.try_clone()?)?);
+                }
+                thread::Builder::new().name("teapot-signals".to_string()).spawn(move || {
+                    // Ends once the handlers, and their ends of the pipe,
+                    // are unregistered
+                    let mut buf = [0; 16];
+                    loop {
+                        match reader.read(&mut buf) {
+                            Ok(0) => break,
+                            Ok(_) => waker.wake(),
+                            Err(err) if err.kind() == io::ErrorKind::Interrupted => {},
+                            Err(_) => break,
+                        }
+                    }
+                })?;
+            }
+
+            Ok(Self { pending, ids })
+        }
+
+        /// Take the signals received since the last call.
+        pub fn take_pending(&self) -> Vec<Signal> {
+            self.pending
+                .iter()
+                .filter(|(received, _)| received.swap(false, Ordering::SeqCst))
+                .map(|(_, signal)| *signal)
+                .collect()
+        }
+    }
+
+    impl Drop for SignalListener {
+        fn drop(&mut self) {
+            // Only this listener's handlers go; the host's stay in place
+            for id in self.ids.drain(..) {
+                low_level::unregister(id);
+            }
+        }
+    }
+
+    /// Stop the process as the default SIGTSTP action would.
+    ///
+    /// Returns once the process has been continued.
+    pub(crate) fn suspend_process() -> io::Result<()> {
+        low_level::emulate_default_handler(SIGTSTP)
+    }
+}
+
+#[cfg(not(unix))]
+mod imp {
+    use std::io;
+
+    use super::Signal;
+
+    /// Signal listening is only supported on Unix; this listener never fires.
+    pub(crate) struct SignalListener;
+
+    impl SignalListener {
+        /// Start listening for signals.
+        pub fn install(_waker: Option<crate::terminal::Waker>) -> io::Result<Self> {
+            Ok(Self)
+        }
+
+        /// Take the signals received since the last call.
+        pub fn take_pending(&self) -> Vec<Signal> {
+            Vec::new()
+        }
+    }
+
+    /// Suspending is not supported on this platform.
+    pub(crate) fn suspend_process() -> io::Result<()> {
+        Ok(())
+    }
+}
+
+pub(crate) use imp::{SignalListener, suspend_process};
+
+/// Whether suspending the process is supported on this platform.
+pub(crate) fn can_suspend() -> bool {
+    cfg!(unix)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    };
+
+    use signal_hook::{
+        consts::{SIGHUP, SIGINT},
+        flag, low_level,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_listener_reports_signals() {
+        let listener = SignalListener::install(None).unwrap();
+        assert!(listener.take_pending().is_empty());
+
+        low_level::raise(SIGHUP).unwrap();
+        assert_eq!(listener.take_pending(), vec![Signal::Hangup]);
+
+        // Taking pending signals clears them
+        assert!(listener.take_pending().is_empty());
+    }
+
+    #[test]
+    fn test_listener_wakes_on_signals() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let tx = std::sync::Mutex::new(tx);
+        let waker = crate::terminal::Waker::new(move || {
+            let _ = tx.lock().unwrap().send(());
+        });
+        let listener = SignalListener::install(Some(waker)).unwrap();
+
+        low_level::raise(SIGHUP).unwrap();
+        rx.recv_timeout(std::time::Duration::from_secs(5)).unwrap();
+        assert_eq!(listener.take_pending(), vec![Signal::Hangup]);
+    }
+
+    #[test]
+    fn test_host_handler_survives_listener() {
+        let host = Arc::new(AtomicBool::new(false));
+        let id = flag::register(SIGINT, Arc::clone(&host)).unwrap();
+        drop(SignalListener::install(None).unwrap());
+
+        // The process keeps running and the host's handler still sees the signal
+        low_level::raise(SIGINT).unwrap();
+        assert!(host.load(Ordering::SeqCst));
+        low_level::unregister(id);
+    }
+}
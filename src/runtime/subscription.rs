@@ -2,7 +2,8 @@
 //!
 //! Subscriptions represent ongoing sources of messages, such as:
 //! - Periodic timers (animations, auto-refresh)
-//! - External event sources (file watchers, socket listeners)
+//! - External event sources (channels fed by socket listeners, async streams)
+//! - File watchers (live-reloading config or log views)
 //!
 //! Unlike commands which run once, subscriptions continue producing
 //! messages until cancelled or the program exits.
@@ -32,7 +33,114 @@
 //! ]);
 //! ```
 
-use std::time::Duration;
+use std::{
+    path::PathBuf,
+    pin::Pin,
+    sync::{
+        Arc, Mutex,
+        mpsc::{Receiver, RecvTimeoutError},
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures_core::Stream;
+
+use super::{command::CancelToken, watch::FileChange};
+
+/// A boxed stream of messages, as driven by the runtime.
+pub(crate) type BoxStream<M> = Pin<Box<dyn Stream<Item = M> + Send>>;
+
+/// Builds a stream subscription's stream when it starts.
+pub(crate) type MakeStream<M> = Box<dyn FnOnce() -> BoxStream<M> + Send>;
+
+/// Receives a channel subscription's next message, waiting until one
+/// arrives; `None` once the token is cancelled or the channel disconnected.
+pub(crate) type RecvFn<M> = Box<dyn Fn(&CancelToken) -> Option<M> + Send>;
+
+/// How often a channel subscription waiting for a message checks whether
+/// it was cancelled.
+const CHANNEL_CANCEL_CHECK: Duration = Duration::from_millis(100);
+
+/// A cloneable receiving end of a channel, for use with [`Sub::channel`].
+///
+/// `subscriptions()` is called after every update, so the model must be
+/// able to hand out its receiver repeatedly. This wraps a standard
+/// [`Receiver`] so it can be stored in the model and cloned cheaply.
+///
+/// # Example
+///
+/// ```rust
+/// use teapot::runtime::SharedReceiver;
+/// use std::sync::mpsc;
+///
+/// let (tx, rx) = mpsc::channel::<String>();
+/// let receiver: SharedReceiver<String> = rx.into();
+/// let clone = receiver.clone();
+/// ```
+pub struct SharedReceiver<M> {
+    inner: Arc<Mutex<ReceiverState<M>>>,
+}
+
+/// A channel receiver and the message taken from it after its
+/// subscription was cancelled.
+struct ReceiverState<M> {
+    receiver: Receiver<M>,
+    unread: Option<M>,
+}
+
+impl<M> SharedReceiver<M> {
+    /// Wrap a channel receiver.
+    pub fn new(receiver: Receiver<M>) -> Self {
+        Self { inner: Arc::new(Mutex::new(ReceiverState { receiver, unread: None })) }
+    }
+
+    /// Wait for the next message until `cancel` is cancelled or every sender
+    /// is gone.
+    ///
+    /// A message that arrives just as the wait is cancelled is kept for the
+    /// next call, so it stays unread like the rest of the channel.
+    pub(crate) fn recv(&self, cancel: &CancelToken) -> Option<M> {
+        let mut state = match self.inner.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        while !cancel.is_cancelled() {
+            let msg = match state.unread.take() {
+                Some(msg) => msg,
+                None => match state.receiver.recv_timeout(CHANNEL_CANCEL_CHECK) {
+                    Ok(msg) => msg,
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    Err(RecvTimeoutError::Disconnected) => return None,
+                },
+            };
+            if !cancel.is_cancelled() {
+                return Some(msg);
+            }
+            state.unread = Some(msg);
+        }
+        None
+    }
+}
+
+impl<M> Clone for SharedReceiver<M> {
+    fn clone(&self) -> Self {
+        Self { inner: Arc::clone(&self.inner) }
+    }
+}
+
+impl<M> From<Receiver<M>> for SharedReceiver<M> {
+    fn from(receiver: Receiver<M>) -> Self {
+        Self::new(receiver)
+    }
+}
+
+impl<M> std::fmt::Debug for SharedReceiver<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SharedReceiver").finish_non_exhaustive()
+    }
+}
 
 /// A subscription representing an ongoing source of messages.
 ///
@@ -55,10 +163,43 @@ enum SubInner<M> {
         /// Function to generate messages
         msg_fn: Box<dyn Fn() -> M + Send>,
     },
+    /// Messages received from a channel
+    Channel {
+        /// Unique identifier for this subscription
+        id: String,
+        /// Blocking receive function
+        recv: RecvFn<M>,
+    },
+    /// Messages produced by an async stream
+    Stream {
+        /// Unique identifier for this subscription
+        id: String,
+        /// Builds the stream to drive
+        make: MakeStream<M>,
+    },
+    /// File watcher
+    Watch {
+        /// Unique identifier for this subscription
+        id: String,
+        /// Watch configuration
+        watch: WatchSpec<M>,
+    },
     /// Batch of subscriptions
     Batch(Vec<Sub<M>>),
 }
 
+/// Configuration of a file-watch subscription.
+pub(crate) struct WatchSpec<M> {
+    /// File or directory to watch
+    pub path: PathBuf,
+    /// How often to check for changes
+    pub poll_interval: Duration,
+    /// How long a path must be quiet before its change is reported
+    pub debounce: Duration,
+    /// Function to generate messages
+    pub msg_fn: Box<dyn Fn(FileChange) -> M + Send>,
+}
+
 impl<M> Sub<M> {
     /// Create an empty subscription (no events).
     ///
@@ -146,6 +287,190 @@ impl<M> Sub<M> {
         Self::interval(id, Duration::from_secs(secs), msg_fn)
     }
 
+    /// Create a subscription that delivers messages received on a channel.
+    ///
+    /// While the subscription is declared, a background thread waits on the
+    /// channel and delivers each message to `update` as it arrives. Once
+    /// `subscriptions()` stops returning it, the channel is no longer read
+    /// (unread messages stay in the channel).
+    ///
+    /// Because `subscriptions()` is called repeatedly, the receiver is
+    /// passed as a [`SharedReceiver`] that the model keeps and clones.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use teapot::{Sub, runtime::SharedReceiver};
+    /// use std::sync::mpsc;
+    ///
+    /// enum Msg { Line(String) }
+    ///
+    /// struct App { lines: SharedReceiver<Msg> }
+    ///
+    /// let (tx, rx) = mpsc::channel();
+    /// let app = App { lines: rx.into() };
+    ///
+    /// // Another thread (a log tailer, a socket listener) sends messages
+    /// std::thread::spawn(move || tx.send(Msg::Line("hello".into())));
+    ///
+    /// // In Model::subscriptions:
+    /// let sub: Sub<Msg> = Sub::channel("log", app.lines.clone());
+    /// ```
+    pub fn channel(id: impl Into<String>, receiver: impl Into<SharedReceiver<M>>) -> Self
+    where
+        M: Send + 'static,
+    {
+        let receiver = receiver.into();
+        Self {
+            inner: SubInner::Channel {
+                id: id.into(),
+                recv: Box::new(move |cancel| receiver.recv(cancel)),
+            },
+        }
+    }
+
+    /// Create a subscription that delivers the items of an async stream.
+    ///
+    /// The runtime starts driving the stream the first time a subscription
+    /// with this `id` is declared, and cancels it (dropping the stream) once
+    /// `subscriptions()` stops returning it. While a stream with this `id`
+    /// is running, streams passed in later declarations are dropped unused.
+    /// `subscriptions()` is called after every update, so use
+    /// [`Sub::stream_with`] when building the stream is expensive or has
+    /// side effects, such as opening a connection.
+    ///
+    /// Streams run on the same executor as `Cmd::perform_async`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// use teapot::Sub;
+    ///
+    /// enum Msg { Event(ServerEvent) }
+    ///
+    /// fn subscriptions(&self) -> Sub<Msg> {
+    ///     Sub::stream("events", self.client.events().map(Msg::Event))
+    /// }
+    /// ```
+    pub fn stream<S>(id: impl Into<String>, stream: S) -> Self
+    where
+        S: Stream<Item = M> + Send + 'static,
+    {
+        Self {
+            inner: SubInner::Stream {
+                id: id.into(),
+                make: Box::new(move || Box::pin(stream) as BoxStream<M>),
+            },
+        }
+    }
+
+    /// Create a subscription that delivers the items of a stream built by
+    /// `make`.
+    ///
+    /// Like [`Sub::stream`], but `make` is only called when the
+    /// subscription starts, not on every declaration while it is running.
+    ///
+    /// # Example
+    ///
+    /// ```rust,ignore
+    /// use teapot::Sub;
+    ///
+    /// enum Msg { Event(ServerEvent) }
+    ///
+    /// fn subscriptions(&self) -> Sub<Msg> {
+    ///     let client = self.client.clone();
+    ///     Sub::stream_with("events", move || client.connect().map(Msg::Event))
+    /// }
+    /// ```
+    pub fn stream_with<S, F>(id: impl Into<String>, make: F) -> Self
+    where
+        S: Stream<Item = M> + Send + 'static,
+        F: FnOnce() -> S + Send + 'static,
+    {
+        Self {
+            inner: SubInner::Stream {
+                id: id.into(),
+                make: Box::new(move || Box::pin(make()) as BoxStream<M>),
+            },
+        }
+    }
+
+    /// Create a subscription that reports changes to a file or directory.
+    ///
+    /// The path is checked for creation, modification and deletion every
+    /// 250ms; for a directory, its direct entries are watched. Bursts of
+    /// changes are debounced: a change is reported once the path has been
+    /// quiet for 100ms. Watching stops when `subscriptions()` stops
+    /// returning the subscription.
+    ///
+    /// Use [`Sub::watch_path_with`] to configure the timings.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use teapot::{Sub, runtime::FileChange};
+    ///
+    /// enum Msg { ConfigChanged(FileChange) }
+    ///
+    /// let sub: Sub<Msg> = Sub::watch_path("config", "app.toml", Msg::ConfigChanged);
+    /// ```
+    pub fn watch_path<F>(id: impl Into<String>, path: impl Into<PathBuf>, msg_fn: F) -> Self
+    where
+        F: Fn(FileChange) -> M + Send + 'static,
+    {
+        Self::watch_path_with(
+            id,
+            path,
+            Duration::from_millis(250),
+            Duration::from_millis(100),
+            msg_fn,
+        )
+    }
+
+    /// Create a file-watch subscription with custom timings.
+    ///
+    /// `poll_interval` is how often the path is checked; `debounce` is how
+    /// long a path must be quiet before its change is reported.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use teapot::{Sub, runtime::FileChange};
+    /// use std::time::Duration;
+    ///
+    /// enum Msg { LogChanged(FileChange) }
+    ///
+    /// let sub: Sub<Msg> = Sub::watch_path_with(
+    ///     "logs",
+    ///     "/var/log/app",
+    ///     Duration::from_secs(1),
+    ///     Duration::from_millis(500),
+    ///     Msg::LogChanged,
+    /// );
+    /// ```
+    pub fn watch_path_with<F>(
+        id: impl Into<String>,
+        path: impl Into<PathBuf>,
+        poll_interval: Duration,
+        debounce: Duration,
+        msg_fn: F,
+    ) -> Self
+    where
+        F: Fn(FileChange) -> M + Send + 'static,
+    {
+        Self {
+            inner: SubInner::Watch {
+                id: id.into(),
+                watch: WatchSpec {
+                    path: path.into(),
+                    poll_interval,
+                    debounce,
+                    msg_fn: Box::new(msg_fn),
+                },
+            },
+        }
+    }
+
     /// Combine multiple subscriptions into one.
     ///
     /// All subscriptions in the batch will be active simultaneously.
@@ -209,6 +534,20 @@ impl<M> Sub<M> {
                 let f = f.clone();
                 Sub::interval(id, interval, move || f(msg_fn()))
             },
+            SubInner::Channel { id, recv } => Sub {
+                inner: SubInner::Channel { id, recv: Box::new(move |cancel| recv(cancel).map(&f)) },
+            },
+            SubInner::Stream { id, make } => {
+                let make: MakeStream<N> =
+                    Box::new(move || Box::pin(MapStream { stream: make(), f: Box::new(f) }));
+                Sub { inner: SubInner::Stream { id, make } }
+            },
+            SubInner::Watch { id, watch } => {
+                let WatchSpec { path, poll_interval, debounce, msg_fn } = watch;
+                Sub::watch_path_with(id, path, poll_interval, debounce, move |change| {
+                    f(msg_fn(change))
+                })
+            },
             SubInner::Batch(subs) => {
                 Sub::batch(subs.into_iter().map(|s| s.map(f.clone())).collect())
             },
@@ -220,8 +559,11 @@ impl<M> Sub<M> {
         match self.inner {
             SubInner::None => vec![],
             SubInner::Interval { id, interval, msg_fn } => {
-                vec![SubEntry { id, interval, msg_fn }]
+                vec![SubEntry { id, kind: SubKind::Interval { interval, msg_fn } }]
             },
+            SubInner::Channel { id, recv } => vec![SubEntry { id, kind: SubKind::Channel(recv) }],
+            SubInner::Stream { id, make } => vec![SubEntry { id, kind: SubKind::Stream(make) }],
+            SubInner::Watch { id, watch } => vec![SubEntry { id, kind: SubKind::Watch(watch) }],
             SubInner::Batch(subs) => subs.into_iter().flat_map(|s| s.into_entries()).collect(),
         }
     }
@@ -240,19 +582,54 @@ impl<M> std::fmt::Debug for Sub<M> {
             SubInner::Interval { id, interval, .. } => {
                 write!(f, "Sub::Interval({:?}, {:?})", id, interval)
             },
+            SubInner::Channel { id, .. } => write!(f, "Sub::Channel({:?})", id),
+            SubInner::Stream { id, .. } => write!(f, "Sub::Stream({:?})", id),
+            SubInner::Watch { id, watch } => {
+                write!(f, "Sub::Watch({:?}, {:?})", id, watch.path)
+            },
             SubInner::Batch(subs) => write!(f, "Sub::Batch({} subs)", subs.len()),
         }
     }
 }
 
+/// Stream adapter applying a function to each item, used by `Sub::map`.
+struct MapStream<M, N> {
+    stream: BoxStream<M>,
+    f: Box<dyn Fn(M) -> N + Send>,
+}
+
+impl<M, N> Stream for MapStream<M, N> {
+    type Item = N;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<N>> {
+        let this = &mut *self;
+        this.stream.as_mut().poll_next(cx).map(|item| item.map(&this.f))
+    }
+}
+
 /// Internal representation of a single subscription entry.
 pub(crate) struct SubEntry<M> {
     /// Unique identifier
     pub id: String,
-    /// Interval between firings
-    pub interval: Duration,
-    /// Message generator function
-    pub msg_fn: Box<dyn Fn() -> M + Send>,
+    /// What produces the subscription's messages
+    pub kind: SubKind<M>,
+}
+
+/// The message source behind a subscription entry.
+pub(crate) enum SubKind<M> {
+    /// Fires at a fixed interval
+    Interval {
+        /// Interval between firings
+        interval: Duration,
+        /// Message generator function
+        msg_fn: Box<dyn Fn() -> M + Send>,
+    },
+    /// Forwards a channel's messages
+    Channel(RecvFn<M>),
+    /// Drives an async stream, built when the subscription starts
+    Stream(MakeStream<M>),
+    /// Watches a file or directory
+    Watch(WatchSpec<M>),
 }
 
 #[cfg(test)]
@@ -265,6 +642,25 @@ mod tests {
         Other,
     }
 
+    /// Unwrap an interval entry into its interval and message function.
+    fn interval_parts<M>(entry: &SubEntry<M>) -> (Duration, &(dyn Fn() -> M + Send)) {
+        match &entry.kind {
+            SubKind::Interval { interval, msg_fn } => (*interval, msg_fn.as_ref()),
+            _ => panic!("expected an interval subscription"),
+        }
+    }
+
+    /// A stream yielding the items of a vector, then ending.
+    struct IterStream<T>(std::vec::IntoIter<T>);
+
+    impl<T: Unpin> Stream for IterStream<T> {
+        type Item = T;
+
+        fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<T>> {
+            Poll::Ready(self.0.next())
+        }
+    }
+
     #[test]
     fn test_sub_none() {
         let sub: Sub<TestMsg> = Sub::none();
@@ -280,8 +676,9 @@ mod tests {
         let entries = sub.into_entries();
         assert_eq!(entries.len(), 1);
         assert_eq!(entries[0].id, "test");
-        assert_eq!(entries[0].interval, Duration::from_millis(100));
-        assert_eq!((entries[0].msg_fn)(), TestMsg::Tick);
+        let (interval, msg_fn) = interval_parts(&entries[0]);
+        assert_eq!(interval, Duration::from_millis(100));
+        assert_eq!(msg_fn(), TestMsg::Tick);
     }
 
     #[test]
@@ -324,11 +721,102 @@ mod tests {
 
         let entries = parent_sub.into_entries();
         assert_eq!(entries.len(), 1);
-        match (entries[0].msg_fn)() {
+        match interval_parts(&entries[0]).1() {
             Parent::Child(Child::Tick) => {},
         }
     }
 
+    #[test]
+    fn test_sub_channel() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let receiver = SharedReceiver::new(rx);
+        let sub: Sub<TestMsg> = Sub::channel("chan", receiver.clone());
+
+        let entries = sub.into_entries();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].id, "chan");
+        let SubKind::Channel(recv) = &entries[0].kind else {
+            panic!("expected a channel subscription");
+        };
+
+        let cancel = CancelToken::default();
+        tx.send(TestMsg::Other).unwrap();
+        assert_eq!(recv(&cancel), Some(TestMsg::Other));
+        drop(tx);
+        assert_eq!(recv(&cancel), None);
+    }
+
+    #[test]
+    fn test_cancelled_channel_keeps_messages() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let receiver = SharedReceiver::new(rx);
+        tx.send(1).unwrap();
+
+        let cancelled = CancelToken::default();
+        cancelled.cancel();
+        assert_eq!(receiver.recv(&cancelled), None);
+        assert_eq!(receiver.recv(&CancelToken::default()), Some(1));
+    }
+
+    #[test]
+    fn test_sub_channel_map() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let sub: Sub<u32> = Sub::channel("chan", rx);
+        let mapped: Sub<String> = sub.map(|n| n.to_string());
+
+        tx.send(7).unwrap();
+        let entries = mapped.into_entries();
+        let SubKind::Channel(recv) = &entries[0].kind else {
+            panic!("expected a channel subscription");
+        };
+        assert_eq!(recv(&CancelToken::default()), Some("7".to_string()));
+    }
+
+    #[test]
+    fn test_sub_stream_map() {
+        let sub: Sub<u32> = Sub::stream("numbers", IterStream(vec![1, 2].into_iter()));
+        let mapped: Sub<u32> = sub.map(|n| n * 10);
+
+        let mut entries = mapped.into_entries();
+        assert_eq!(entries[0].id, "numbers");
+        let SubKind::Stream(make) = entries.remove(0).kind else {
+            panic!("expected a stream subscription");
+        };
+        let mut stream = make();
+
+        let waker = std::task::Waker::noop();
+        let mut cx = Context::from_waker(waker);
+        assert_eq!(stream.as_mut().poll_next(&mut cx), Poll::Ready(Some(10)));
+        assert_eq!(stream.as_mut().poll_next(&mut cx), Poll::Ready(Some(20)));
+        assert_eq!(stream.as_mut().poll_next(&mut cx), Poll::Ready(None));
+    }
+
+    #[test]
+    fn test_sub_watch_path_map() {
+        enum Child {
+            Changed(FileChange),
+        }
+        enum Parent {
+            Child(Child),
+        }
+
+        let sub: Sub<Child> = Sub::watch_path("config", "app.toml", Child::Changed);
+        let entries = sub.map(Parent::Child).into_entries();
+        assert_eq!(entries[0].id, "config");
+        let SubKind::Watch(watch) = &entries[0].kind else {
+            panic!("expected a watch subscription");
+        };
+
+        assert_eq!(watch.path, PathBuf::from("app.toml"));
+        assert_eq!(watch.poll_interval, Duration::from_millis(250));
+        assert_eq!(watch.debounce, Duration::from_millis(100));
+        match (watch.msg_fn)(FileChange::Removed("app.toml".into())) {
+            Parent::Child(Child::Changed(change)) => {
+                assert_eq!(change, FileChange::Removed("app.toml".into()))
+            },
+        }
+    }
+
     #[test]
     fn test_convenience_methods() {
         let sub1: Sub<TestMsg> = Sub::every_millis("a", 100, || TestMsg::Tick);
@@ -337,7 +825,7 @@ mod tests {
         let entries1 = sub1.into_entries();
         let entries2 = sub2.into_entries();
 
-        assert_eq!(entries1[0].interval, Duration::from_millis(100));
-        assert_eq!(entries2[0].interval, Duration::from_secs(1));
+        assert_eq!(interval_parts(&entries1[0]).0, Duration::from_millis(100));
+        assert_eq!(interval_parts(&entries2[0]).0, Duration::from_secs(1));
     }
 }
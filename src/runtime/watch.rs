@@ -0,0 +1,265 @@
+//! Polling file watcher backing `Sub::watch_path`.
+//!
+//! The watcher compares snapshots of file metadata (modification time and
+//! size) taken at each poll. Watching a directory tracks its direct entries
+//! (non-recursively). Changes are debounced per path: a change is reported
+//! once the path has been quiet for the debounce period, and bursts of
+//! changes are merged into a single event.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+/// A change to a watched file or directory entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileChange {
+    /// The file was created.
+    Created(PathBuf),
+    /// The file's contents or metadata changed.
+    Modified(PathBuf),
+    /// The file was deleted.
+    Removed(PathBuf),
+}
+
+impl FileChange {
+    /// The path that changed.
+    pub fn path(&self) -> &Path {
+        match self {
+            FileChange::Created(path) | FileChange::Modified(path) | FileChange::Removed(path) => {
+                path
+            },
+        }
+    }
+}
+
+/// Kind of change, before merging into a [`FileChange`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChangeKind {
+    Created,
+    Modified,
+    Removed,
+}
+
+impl ChangeKind {
+    /// Merge a newer change into an older, not yet reported one.
+    ///
+    /// Returns `None` when the changes cancel out (created then removed).
+    fn merge(self, newer: ChangeKind) -> Option<ChangeKind> {
+        match (self, newer) {
+            (ChangeKind::Created, ChangeKind::Removed) => None,
+            (ChangeKind::Created, _) => Some(ChangeKind::Created),
+            (ChangeKind::Removed, ChangeKind::Created) => Some(ChangeKind::Modified),
+            (_, newer) => Some(newer),
+        }
+    }
+}
+
+/// Metadata compared between polls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Stamp {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+/// A change seen but not yet reported.
+struct PendingChange {
+    kind: ChangeKind,
+    last_seen: Instant,
+}
+
+/// Polls a path for changes.
+pub(crate) struct PathWatcher {
+    path: PathBuf,
+    debounce: Duration,
+    snapshot: HashMap<PathBuf, Stamp>,
+    pending: HashMap<PathBuf, PendingChange>,
+}
+
+impl PathWatcher {
+    /// Start watching a path, taking the initial snapshot.
+    pub fn new(path: PathBuf, debounce: Duration) -> Self {
+        let snapshot = snapshot(&path);
+        Self { path, debounce, snapshot, pending: HashMap::new() }
+    }
+
+    /// Poll the path, returning changes whose debounce period has elapsed.
+    pub fn poll(&mut self, now: Instant) -> Vec<FileChange> {
+        let current = snapshot(&self.path);
+
+        for (path, stamp) in &current {
+            match self.snapshot.get(path) {
+                None => self.record(path, ChangeKind::Created, now),
+                Some(previous) if previous != stamp => self.record(path, ChangeKind::Modified, now),
+                Some(_) => {},
+            }
+        }
+        let removed: Vec<PathBuf> =
+            self.snapshot.keys().filter(|path| !current.contains_key(*path)).cloned().collect();
+        for path in removed {
+            self.record(&path, ChangeKind::Removed, now);
+        }
+        self.snapshot = current;
+
+        let debounce = self.debounce;
+        let mut ready: Vec<(PathBuf, ChangeKind)> = Vec::new();
+        self.pending.retain(|path, change| {
+            if now.saturating_duration_since(change.last_seen) >= debounce {
+                ready.push((path.clone(), change.kind));
+                false
+            } else {
+                true
+            }
+        });
+        ready.sort_by(|a, b| a.0.cmp(&b.0));
+
+        ready
+            .into_iter()
+            .map(|(path, kind)| match kind {
+                ChangeKind::Created => FileChange::Created(path),
+                ChangeKind::Modified => FileChange::Modified(path),
+                ChangeKind::Removed => FileChange::Removed(path),
+            })
+            .collect()
+    }
+
+    /// When the earliest pending change becomes ready to report, if any.
+    pub fn next_flush(&self) -> Option<Instant> {
+        self.pending.values().map(|change| change.last_seen + self.debounce).min()
+    }
+
+    /// Record a change, merging it with any pending change for the path.
+    fn record(&mut self, path: &Path, kind: ChangeKind, now: Instant) {
+        match self.pending.get_mut(path) {
+            Some(pending) => match pending.kind.merge(kind) {
+                Some(merged) => {
+                    pending.kind = merged;
+                    pending.last_seen = now;
+                },
+                None => {
+                    self.pending.remove(path);
+                },
+            },
+            None => {
+                self.pending.insert(path.to_path_buf(), PendingChange { kind, last_seen: now });
+            },
+        }
+    }
+}
+
+/// Take a snapshot of a file, or of a directory's direct entries.
+fn snapshot(path: &Path) -> HashMap<PathBuf, Stamp> {
+    let mut entries = HashMap::new();
+
+    let Ok(metadata) = fs::metadata(path) else {
+        return entries;
+    };
+
+    if metadata.is_dir() {
+        if let Ok(dir) = fs::read_dir(path) {
+            for entry in dir.flatten() {
+                if let Ok(metadata) = entry.metadata() {
+                    entries.insert(entry.path(), stamp(&metadata));
+                }
+            }
+        }
+    } else {
+        entries.insert(path.to_path_buf(), stamp(&metadata));
+    }
+
+    entries
+}
+
+fn stamp(metadata: &fs::Metadata) -> Stamp {
+    Stamp { modified: metadata.modified().ok(), len: metadata.len() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Create a fresh, empty directory for a test.
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("teapot-watch-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_watch_file_lifecycle() {
+        let dir = temp_dir("file");
+        let file = dir.join("config.toml");
+        let mut watcher = PathWatcher::new(file.clone(), Duration::ZERO);
+        let start = Instant::now();
+
+        assert!(watcher.poll(start).is_empty());
+
+        fs::write(&file, "a = 1").unwrap();
+        assert_eq!(watcher.poll(start), vec![FileChange::Created(file.clone())]);
+
+        fs::write(&file, "a = 12").unwrap();
+        assert_eq!(watcher.poll(start), vec![FileChange::Modified(file.clone())]);
+
+        fs::remove_file(&file).unwrap();
+        assert_eq!(watcher.poll(start), vec![FileChange::Removed(file.clone())]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_watch_directory_entries() {
+        let dir = temp_dir("dir");
+        fs::write(dir.join("existing.log"), "x").unwrap();
+        let mut watcher = PathWatcher::new(dir.clone(), Duration::ZERO);
+        let now = Instant::now();
+
+        fs::write(dir.join("new.log"), "y").unwrap();
+        fs::write(dir.join("existing.log"), "xx").unwrap();
+
+        assert_eq!(
+            watcher.poll(now),
+            vec![
+                FileChange::Modified(dir.join("existing.log")),
+                FileChange::Created(dir.join("new.log")),
+            ]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_watch_debounce_merges_changes() {
+        let dir = temp_dir("debounce");
+        let file = dir.join("data.json");
+        let debounce = Duration::from_millis(100);
+        let mut watcher = PathWatcher::new(file.clone(), debounce);
+        let start = Instant::now();
+
+        fs::write(&file, "1").unwrap();
+        assert!(watcher.poll(start).is_empty());
+        fs::write(&file, "12").unwrap();
+        assert!(watcher.poll(start + Duration::from_millis(50)).is_empty());
+        assert_eq!(watcher.next_flush(), Some(start + Duration::from_millis(150)));
+
+        // Created then modified within the window is reported once as created
+        assert_eq!(
+            watcher.poll(start + Duration::from_millis(150)),
+            vec![FileChange::Created(file.clone())]
+        );
+        assert_eq!(watcher.next_flush(), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_change_merging() {
+        assert_eq!(ChangeKind::Created.merge(ChangeKind::Removed), None);
+        assert_eq!(ChangeKind::Created.merge(ChangeKind::Modified), Some(ChangeKind::Created));
+        assert_eq!(ChangeKind::Removed.merge(ChangeKind::Created), Some(ChangeKind::Modified));
+        assert_eq!(ChangeKind::Modified.merge(ChangeKind::Removed), Some(ChangeKind::Removed));
+    }
+}
@@ -1,86 +1,424 @@
-//! Terminal backend implementation.
+//! Terminal backends.
+//!
+//! A [`Backend`] is the terminal a program runs on: where input events come
+//! from, where frames are written, and how raw mode is switched. Programs
+//! use [`CrosstermBackend`] on the process's own terminal by default;
+//! [`StreamBackend`] runs a program over any `Read + Write` pair, and
+//! [`MemoryBackend`] keeps everything in memory for tests.
+//!
+//! [`StreamBackend`]: super::StreamBackend
+//! [`MemoryBackend`]: super::MemoryBackend
 
-use std::io::{self, Write};
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, IsTerminal, Write},
+    time::Duration,
+};
 
-use crossterm::{cursor, execute, terminal};
+use crossterm::{event, terminal};
 
-/// Terminal backend for rendering.
-pub struct Backend {
-    alt_screen: bool,
+#[cfg(unix)]
+use super::waker::InputWaiter;
+use super::{Event, Waker};
+
+/// The terminal a program runs on.
+///
+/// Frames and control sequences are written to the backend as bytes, so
+/// every backend is a [`Write`]r. Only a program using the default backend
+/// installs process-wide signal and panic handlers, so several programs
+/// with their own backends can run concurrently, one per thread.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use teapot::{Program, terminal::StreamBackend};
+///
+/// // Serve a program over a connection that speaks terminal escape codes
+/// let reader = stream.try_clone()?;
+/// let backend = StreamBackend::new(reader, stream).with_size(100, 30);
+/// Program::new(app).with_backend(backend).run()?;
+/// ```
+pub trait Backend: Write + Send {
+    /// Switch the terminal to raw mode, so input arrives a key at a time
+    /// without being echoed.
+    fn enable_raw_mode(&mut self) -> io::Result<()>;
+
+    /// Switch the terminal back from raw mode.
+    fn disable_raw_mode(&mut self) -> io::Result<()>;
+
+    /// The size of the terminal, in columns and rows.
+    fn size(&self) -> io::Result<(u16, u16)>;
+
+    /// Wait up to `timeout` for an input event.
+    ///
+    /// Returns `None` if no event arrived in time, or if the backend was
+    /// woken by its [`Waker`]. Once no more input can arrive, return an
+    /// [`UnexpectedEof`](io::ErrorKind::UnexpectedEof) error: the program
+    /// then quits as if `Cmd::quit` had been returned.
+    fn poll_event(&mut self, timeout: Duration) -> io::Result<Option<Event>>;
+
+    /// Get a [`Waker`] that interrupts [`Backend::poll_event`] from another
+    /// thread.
+    ///
+    /// The program wakes the backend when background work, a program
+    /// handle or a signal has something for it. Backends that cannot be
+    /// woken return `None`, the default; the program then polls them at a
+    /// short interval while messages may arrive.
+    fn waker(&mut self) -> Option<Waker> {
+        None
+    }
+}
+
+/// Where the process's terminal UI is drawn.
+///
+/// Drawing on stderr or the controlling terminal keeps stdout free for the
+/// program's result, as in `app | xargs ...`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputStream {
+    /// Standard output.
+    #[default]
+    Stdout,
+    /// Standard error.
+    Stderr,
+    /// The controlling terminal (`/dev/tty`, or the console on Windows),
+    /// whatever stdout and stderr are redirected to.
+    Tty,
+}
+
+impl OutputStream {
+    /// Whether the stream is a terminal.
+    pub fn is_terminal(self) -> bool {
+        match self {
+            Self::Stdout => io::stdout().is_terminal(),
+            Self::Stderr => io::stderr().is_terminal(),
+            Self::Tty => open_tty_output().is_ok_and(|tty| tty.is_terminal()),
+        }
+    }
+
+    /// A writer for plain-text output when there is no terminal to draw on.
+    ///
+    /// The controlling terminal falls back to stderr, keeping stdout free.
+    pub(crate) fn plain_writer(self) -> Output {
+        self.plain_writer_with(open_tty_output)
+    }
+
+    fn plain_writer_with(self, open_tty: impl FnOnce() -> io::Result<File>) -> Output {
+        match self {
+            Self::Stdout => Output::Stdout,
+            Self::Stderr => Output::Stderr,
+            Self::Tty => open_tty().map_or(Output::Stderr, Output::Tty),
+        }
+    }
+}
+
+/// Which terminal counts as the process's input.
+///
+/// This decides whether a program can run interactively, and where
+/// accessible mode reads its answers. Keys for the TUI are read by
+/// crossterm, which uses stdin when it is a terminal and the controlling
+/// terminal otherwise.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InputSource {
+    /// Standard input; a program only runs interactively if it is a
+    /// terminal.
+    #[default]
+    Stdin,
+    /// The controlling terminal (`/dev/tty`, or the console on Windows), so
+    /// a program can run interactively while stdin is a pipe, as in
+    /// `cat list.txt | app`.
+    Tty,
+}
+
+impl InputSource {
+    /// Whether the source is a terminal.
+    pub fn is_terminal(self) -> bool {
+        match self {
+            Self::Stdin => io::stdin().is_terminal(),
+            Self::Tty => open_tty_input().is_ok_and(|tty| tty.is_terminal()),
+        }
+    }
+
+    /// A line reader for accessible-mode answers.
+    pub(crate) fn reader(self) -> io::Result<Box<dyn BufRead>> {
+        self.reader_with(open_tty_input)
+    }
+
+    pub(crate) fn reader_with(
+        self,
+        open_tty: impl FnOnce() -> io::Result<File>,
+    ) -> io::Result<Box<dyn BufRead>> {
+        Ok(match self {
+            Self::Stdin => Box::new(io::stdin().lock()),
+            Self::Tty => Box::new(BufReader::new(open_tty()?)),
+        })
+    }
+}
+
+/// Which of the process's streams are terminals, checked once.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Terminals {
+    pub stdin: bool,
+    pub stdout: bool,
+    pub stderr: bool,
+    pub tty_input: bool,
+    pub tty_output: bool,
+}
+
+impl Terminals {
+    /// Check the process's streams.
+    pub fn detect() -> Self {
+        Self {
+            stdin: InputSource::Stdin.is_terminal(),
+            stdout: OutputStream::Stdout.is_terminal(),
+            stderr: OutputStream::Stderr.is_terminal(),
+            tty_input: InputSource::Tty.is_terminal(),
+            tty_output: OutputStream::Tty.is_terminal(),
+        }
+    }
+
+    /// Whether `source` is a terminal.
+    pub fn has_input(&self, source: InputSource) -> bool {
+        match source {
+            InputSource::Stdin => self.stdin,
+            InputSource::Tty => self.tty_input,
+        }
+    }
+
+    /// Whether `stream` is a terminal.
+    pub fn has_output(&self, stream: OutputStream) -> bool {
+        match stream {
+            OutputStream::Stdout => self.stdout,
+            OutputStream::Stderr => self.stderr,
+            OutputStream::Tty => self.tty_output,
+        }
+    }
+}
+
+/// Open the controlling terminal for reading.
+pub(crate) fn open_tty_input() -> io::Result<File> {
+    #[cfg(windows)]
+    let path = "CONIN$";
+    #[cfg(not(windows))]
+    let path = "/dev/tty";
+
+    OpenOptions::new().read(true).open(path)
+}
+
+/// Open the controlling terminal for writing.
+pub(crate) fn open_tty_output() -> io::Result<File> {
+    #[cfg(windows)]
+    let path = "CONOUT$";
+    #[cfg(not(windows))]
+    let path = "/dev/tty";
+
+    OpenOptions::new().write(true).open(path)
+}
+
+/// The process's own terminal, through crossterm.
+///
+/// Frames are written to stdout unless another [`OutputStream`] is chosen.
+/// Input is read by crossterm from stdin when it is a terminal and from the
+/// controlling terminal otherwise. Raw mode is global to the process, so
+/// only one program at a time should use this backend.
+///
+/// On Unix the backend can be woken while it waits for input; elsewhere
+/// the program polls it.
+#[derive(Default)]
+pub struct CrosstermBackend {
+    output: Output,
+    /// Waits for input interruptibly, once a waker was requested
+    #[cfg(unix)]
+    waiter: Option<InputWaiter>,
+}
+
+impl std::fmt::Debug for CrosstermBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CrosstermBackend").field("output", &self.output).finish_non_exhaustive()
+    }
+}
+
+/// The stream a [`CrosstermBackend`] or plain-text output writes to.
+#[derive(Debug, Default)]
+pub(crate) enum Output {
+    #[default]
+    Stdout,
+    Stderr,
+    Tty(File),
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Stdout => io::stdout().write(buf),
+            Self::Stderr => io::stderr().write(buf),
+            Self::Tty(tty) => tty.write(buf),
+        }
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        match self {
+            Self::Stdout => io::stdout().write_all(buf),
+            Self::Stderr => io::stderr().write_all(buf),
+            Self::Tty(tty) => tty.write_all(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Stdout => io::stdout().flush(),
+            Self::Stderr => io::stderr().flush(),
+            Self::Tty(tty) => tty.flush(),
+        }
+    }
 }
 
-impl Backend {
-    /// Create a new backend.
+impl CrosstermBackend {
+    /// Create a backend for the process's terminal, drawing on stdout.
     pub fn new() -> Self {
-        Self { alt_screen: false }
+        Self::default()
+    }
+
+    /// Create a backend for the process's terminal, drawing on `output`.
+    ///
+    /// Fails if `output` is the controlling terminal and it cannot be
+    /// opened.
+    pub fn with_output(output: OutputStream) -> io::Result<Self> {
+        let output = match output {
+            OutputStream::Stdout => Output::Stdout,
+            OutputStream::Stderr => Output::Stderr,
+            OutputStream::Tty => Output::Tty(open_tty_output()?),
+        };
+        Ok(Self { output, ..Self::default() })
+    }
+}
+
+impl Write for CrosstermBackend {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.output.write(buf)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.output.write_all(buf)
     }
 
-    /// Enter raw mode.
-    pub fn enable_raw_mode(&self) -> io::Result<()> {
+    fn flush(&mut self) -> io::Result<()> {
+        self.output.flush()
+    }
+}
+
+impl Backend for CrosstermBackend {
+    fn enable_raw_mode(&mut self) -> io::Result<()> {
         terminal::enable_raw_mode()
     }
 
-    /// Leave raw mode.
-    pub fn disable_raw_mode(&self) -> io::Result<()> {
+    fn disable_raw_mode(&mut self) -> io::Result<()> {
         terminal::disable_raw_mode()
     }
 
-    /// Enter alternate screen.
-    pub fn enter_alt_screen(&mut self) -> io::Result<()> {
-        execute!(io::stdout(), terminal::EnterAlternateScreen)?;
-        self.alt_screen = true;
-        Ok(())
+    fn size(&self) -> io::Result<(u16, u16)> {
+        terminal::size()
     }
 
-    /// Leave alternate screen.
-    pub fn leave_alt_screen(&mut self) -> io::Result<()> {
-        if self.alt_screen {
-            execute!(io::stdout(), terminal::LeaveAlternateScreen)?;
-            self.alt_screen = false;
+    fn poll_event(&mut self, timeout: Duration) -> io::Result<Option<Event>> {
+        #[cfg(unix)]
+        if let Some(waiter) = &mut self.waiter {
+            // Let crossterm read only once input, a resize or a wake-up is
+            // ready, since its own wait cannot be interrupted
+            if !event::poll(Duration::ZERO)? {
+                waiter.wait(timeout)?;
+                if !event::poll(Duration::ZERO)? {
+                    return Ok(None);
+                }
+            }
+            return Ok(Some(Event::from(event::read()?)));
         }
-        Ok(())
+
+        if event::poll(timeout)? { Ok(Some(Event::from(event::read()?))) } else { Ok(None) }
     }
 
-    /// Hide the cursor.
-    pub fn hide_cursor(&self) -> io::Result<()> {
-        execute!(io::stdout(), cursor::Hide)
+    #[cfg(unix)]
+    fn waker(&mut self) -> Option<Waker> {
+        if self.waiter.is_none() {
+            self.waiter = InputWaiter::new().ok();
+        }
+        self.waiter.as_ref().map(InputWaiter::waker)
     }
+}
 
-    /// Show the cursor.
-    pub fn show_cursor(&self) -> io::Result<()> {
-        execute!(io::stdout(), cursor::Show)
+impl<B: Backend + ?Sized> Backend for Box<B> {
+    fn enable_raw_mode(&mut self) -> io::Result<()> {
+        (**self).enable_raw_mode()
     }
 
-    /// Move the cursor to a position.
-    pub fn move_cursor(&self, x: u16, y: u16) -> io::Result<()> {
-        execute!(io::stdout(), cursor::MoveTo(x, y))
+    fn disable_raw_mode(&mut self) -> io::Result<()> {
+        (**self).disable_raw_mode()
     }
 
-    /// Clear the screen.
-    pub fn clear(&self) -> io::Result<()> {
-        execute!(io::stdout(), terminal::Clear(terminal::ClearType::All))
+    fn size(&self) -> io::Result<(u16, u16)> {
+        (**self).size()
     }
 
-    /// Get terminal size.
-    pub fn size(&self) -> io::Result<(u16, u16)> {
-        terminal::size()
+    fn poll_event(&mut self, timeout: Duration) -> io::Result<Option<Event>> {
+        (**self).poll_event(timeout)
     }
 
-    /// Flush stdout.
-    pub fn flush(&self) -> io::Result<()> {
-        io::stdout().flush()
+    fn waker(&mut self) -> Option<Waker> {
+        (**self).waker()
     }
 }
 
-impl Default for Backend {
-    fn default() -> Self {
-        Self::new()
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+
+    /// A file holding `contents`, standing in for the controlling terminal.
+    fn fake_tty(name: &str, contents: &str) -> File {
+        let path = std::env::temp_dir().join(format!("teapot-tty-{}-{}", name, std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        let file = OpenOptions::new().read(true).write(true).open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_plain_writer_streams() {
+        assert!(matches!(OutputStream::Stdout.plain_writer(), Output::Stdout));
+        assert!(matches!(OutputStream::Stderr.plain_writer(), Output::Stderr));
+
+        let writer = OutputStream::Tty.plain_writer_with(|| Ok(fake_tty("writer", "")));
+        let Output::Tty(mut tty) = writer else { panic!("expected the tty, got {writer:?}") };
+        tty.write_all(b"frame").unwrap();
+    }
+
+    #[test]
+    fn test_plain_writer_falls_back_to_stderr() {
+        let writer = OutputStream::Tty.plain_writer_with(|| Err(io::ErrorKind::NotFound.into()));
+        assert!(matches!(writer, Output::Stderr));
+    }
+
+    #[test]
+    fn test_tty_reader() {
+        let mut reader = InputSource::Tty.reader_with(|| Ok(fake_tty("reader", "3\n"))).unwrap();
+        let mut answer = String::new();
+        reader.read_to_string(&mut answer).unwrap();
+        assert_eq!(answer, "3\n");
+
+        let err = InputSource::Tty
+            .reader_with(|| Err(io::ErrorKind::NotFound.into()))
+            .err()
+            .expect("opening the tty failed");
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
     }
-}
 
-impl Drop for Backend {
-    fn drop(&mut self) {
-        // Ensure we leave alt screen if we entered it
-        let _ = self.leave_alt_screen();
+    #[test]
+    fn test_terminals() {
+        let terminals = Terminals { stderr: true, tty_input: true, ..Terminals::default() };
+        assert!(!terminals.has_input(InputSource::Stdin));
+        assert!(terminals.has_input(InputSource::Tty));
+        assert!(!terminals.has_output(OutputStream::Stdout));
+        assert!(terminals.has_output(OutputStream::Stderr));
+        assert!(!terminals.has_output(OutputStream::Tty));
     }
 }
@@ -4,14 +4,23 @@
 //! - Event handling (keyboard, mouse, resize)
 //! - Terminal capabilities detection
 //! - Raw mode management
+//! - [`Backend`]s that programs run on: the process's terminal, byte streams such as a pty or
+//!   socket, or memory
 
 mod backend;
 mod input;
 mod output;
+mod parser;
+mod screen;
+mod stream;
+mod waker;
 
-pub use backend::Backend;
+pub(crate) use backend::Terminals;
+pub use backend::{Backend, CrosstermBackend, InputSource, OutputStream};
 pub use input::{Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
 pub use output::TerminalOutput;
+pub use stream::{MemoryBackend, ResizeHandle, StreamBackend};
+pub use waker::Waker;
 
 /// Get the current terminal size.
 pub fn size() -> std::io::Result<(u16, u16)> {
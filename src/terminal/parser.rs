@@ -0,0 +1,369 @@
+//! Parsing of raw terminal input bytes into events.
+//!
+//! Used by backends that read input from a byte stream rather than through
+//! crossterm: a pty, a socket or an SSH channel. Covers the sequences
+//! common terminals send in raw mode: printable UTF-8 text, control keys,
+//! CSI and SS3 cursor and function keys, SGR mouse reports, focus reports
+//! and bracketed paste.
+
+use super::{Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+
+const ESC: u8 = 0x1b;
+
+/// Start and end of a bracketed paste.
+const PASTE_START: &[u8] = b"\x1b[200~";
+const PASTE_END: &[u8] = b"\x1b[201~";
+
+/// Incremental parser for terminal input.
+///
+/// Bytes are fed as they are read; a sequence split across reads is kept
+/// until the rest arrives. An escape byte at the very end of a read is
+/// taken to be the Esc key, as terminals send escape sequences in one
+/// write.
+#[derive(Debug, Default)]
+pub(crate) struct InputParser {
+    pending: Vec<u8>,
+}
+
+/// Result of parsing one event from the front of the buffer.
+enum Parsed {
+    /// An event and the number of bytes it used
+    Event(Option<Event>, usize),
+    /// More bytes are needed
+    Incomplete,
+}
+
+impl InputParser {
+    /// Parse the bytes of one read, returning the complete events.
+    pub fn parse(&mut self, bytes: &[u8]) -> Vec<Event> {
+        self.pending.extend_from_slice(bytes);
+
+        let mut events = Vec::new();
+        let mut start = 0;
+        while start < self.pending.len() {
+            match parse_event(&self.pending[start..]) {
+                Parsed::Event(event, used) => {
+                    events.extend(event);
+                    start += used;
+                },
+                Parsed::Incomplete => break,
+            }
+        }
+
+        // A lone escape at the end of a read is the Esc key
+        if self.pending[start..] == [ESC] {
+            events.push(key(KeyCode::Esc, KeyModifiers::NONE));
+            start += 1;
+        }
+
+        self.pending.drain(..start);
+        events
+    }
+}
+
+fn key(code: KeyCode, modifiers: KeyModifiers) -> Event {
+    Event::Key(KeyEvent { code, modifiers })
+}
+
+/// Parse the event at the front of `bytes`.
+fn parse_event(bytes: &[u8]) -> Parsed {
+    match bytes[0] {
+        ESC => parse_escape(bytes),
+        _ => parse_key(bytes),
+    }
+}
+
+/// Parse a key that is not an escape sequence.
+fn parse_key(bytes: &[u8]) -> Parsed {
+    let event = match bytes[0] {
+        b'\r' | b'\n' => key(KeyCode::Enter, KeyModifiers::NONE),
+        b'\t' => key(KeyCode::Tab, KeyModifiers::NONE),
+        0x7f | 0x08 => key(KeyCode::Backspace, KeyModifiers::NONE),
+        0x00 => key(KeyCode::Char(' '), KeyModifiers::CONTROL),
+        byte @ 0x01..=0x1a => key(KeyCode::Char((b'a' + byte - 1) as char), KeyModifiers::CONTROL),
+        byte @ 0x1c..=0x1f => {
+            key(KeyCode::Char((b'4' + byte - 0x1c) as char), KeyModifiers::CONTROL)
+        },
+        _ => return parse_char(bytes),
+    };
+    Parsed::Event(Some(event), 1)
+}
+
+/// Parse a UTF-8 encoded character.
+fn parse_char(bytes: &[u8]) -> Parsed {
+    let len = match bytes[0] {
+        0x00..=0x7f => 1,
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf7 => 4,
+        // Not the start of a character
+        _ => return Parsed::Event(None, 1),
+    };
+    if bytes.len() < len {
+        return Parsed::Incomplete;
+    }
+
+    match std::str::from_utf8(&bytes[..len]).ok().and_then(|s| s.chars().next()) {
+        Some(c) => {
+            let modifiers = if c.is_uppercase() { KeyModifiers::SHIFT } else { KeyModifiers::NONE };
+            Parsed::Event(Some(key(KeyCode::Char(c), modifiers)), len)
+        },
+        None => Parsed::Event(None, 1),
+    }
+}
+
+/// Parse a sequence starting with an escape byte.
+fn parse_escape(bytes: &[u8]) -> Parsed {
+    match bytes.get(1) {
+        None => Parsed::Incomplete,
+        Some(b'[') => parse_csi(bytes),
+        Some(b'O') => parse_ss3(bytes),
+        Some(&ESC) => Parsed::Event(Some(key(KeyCode::Esc, KeyModifiers::NONE)), 1),
+        // Alt held with another key
+        Some(_) => match parse_key(&bytes[1..]) {
+            Parsed::Event(Some(Event::Key(event)), used) => {
+                let alt = key(event.code, event.modifiers | KeyModifiers::ALT);
+                Parsed::Event(Some(alt), used + 1)
+            },
+            Parsed::Event(event, used) => Parsed::Event(event, used + 1),
+            Parsed::Incomplete => Parsed::Incomplete,
+        },
+    }
+}
+
+/// Parse an SS3 sequence (`ESC O`), sent for some keys in application mode.
+fn parse_ss3(bytes: &[u8]) -> Parsed {
+    let Some(&last) = bytes.get(2) else {
+        return Parsed::Incomplete;
+    };
+    let code = match last {
+        b'A' => KeyCode::Up,
+        b'B' => KeyCode::Down,
+        b'C' => KeyCode::Right,
+        b'D' => KeyCode::Left,
+        b'H' => KeyCode::Home,
+        b'F' => KeyCode::End,
+        b'P'..=b'S' => KeyCode::F(last - b'P' + 1),
+        _ => return Parsed::Event(None, 3),
+    };
+    Parsed::Event(Some(key(code, KeyModifiers::NONE)), 3)
+}
+
+/// Parse a CSI sequence (`ESC [`).
+fn parse_csi(bytes: &[u8]) -> Parsed {
+    if bytes.starts_with(PASTE_START) {
+        return parse_paste(bytes);
+    }
+
+    // Parameter and intermediate bytes, then a final byte
+    let Some(end) = bytes[2..].iter().position(|byte| (0x40..=0x7e).contains(byte)) else {
+        return Parsed::Incomplete;
+    };
+    let end = end + 2;
+    let params = std::str::from_utf8(&bytes[2..end]).unwrap_or_default();
+    let used = end + 1;
+
+    if let Some(mouse) = params.strip_prefix('<') {
+        return Parsed::Event(parse_sgr_mouse(mouse, bytes[end]), used);
+    }
+
+    let mut numbers = params.split(';').map(|n| n.parse::<u16>().unwrap_or(1));
+    let first = numbers.next().unwrap_or(1);
+    let modifiers = numbers.next().map_or(KeyModifiers::NONE, modifiers_from_param);
+
+    let code = match bytes[end] {
+        b'A' => KeyCode::Up,
+        b'B' => KeyCode::Down,
+        b'C' => KeyCode::Right,
+        b'D' => KeyCode::Left,
+        b'H' => KeyCode::Home,
+        b'F' => KeyCode::End,
+        b'Z' => return Parsed::Event(Some(key(KeyCode::BackTab, KeyModifiers::SHIFT)), used),
+        b'I' => return Parsed::Event(Some(Event::FocusGained), used),
+        b'O' => return Parsed::Event(Some(Event::FocusLost), used),
+        b'~' => match first {
+            1 | 7 => KeyCode::Home,
+            2 => KeyCode::Insert,
+            3 => KeyCode::Delete,
+            4 | 8 => KeyCode::End,
+            5 => KeyCode::PageUp,
+            6 => KeyCode::PageDown,
+            11..=15 => KeyCode::F((first - 10) as u8),
+            17..=21 => KeyCode::F((first - 11) as u8),
+            23 | 24 => KeyCode::F((first - 12) as u8),
+            _ => return Parsed::Event(None, used),
+        },
+        _ => return Parsed::Event(None, used),
+    };
+    Parsed::Event(Some(key(code, modifiers)), used)
+}
+
+/// Modifiers encoded as `1 + bits` in a CSI parameter.
+fn modifiers_from_param(param: u16) -> KeyModifiers {
+    let bits = param.saturating_sub(1);
+    let mut modifiers = KeyModifiers::NONE;
+    if bits & 1 != 0 {
+        modifiers = modifiers | KeyModifiers::SHIFT;
+    }
+    if bits & 2 != 0 {
+        modifiers = modifiers | KeyModifiers::ALT;
+    }
+    if bits & 4 != 0 {
+        modifiers = modifiers | KeyModifiers::CONTROL;
+    }
+    modifiers
+}
+
+/// Parse a bracketed paste, up to and including its end marker.
+fn parse_paste(bytes: &[u8]) -> Parsed {
+    let content = &bytes[PASTE_START.len()..];
+    match content.windows(PASTE_END.len()).position(|window| window == PASTE_END) {
+        Some(end) => {
+            let text = String::from_utf8_lossy(&content[..end]).into_owned();
+            Parsed::Event(Some(Event::Paste(text)), PASTE_START.len() + end + PASTE_END.len())
+        },
+        None => Parsed::Incomplete,
+    }
+}
+
+/// Parse an SGR mouse report: `ESC [ < button ; column ; row` and `M` for
+/// press or `m` for release.
+fn parse_sgr_mouse(params: &str, last: u8) -> Option<Event> {
+    let mut numbers = params.split(';').map(|n| n.parse::<u16>().ok());
+    let (Some(Some(code)), Some(Some(column)), Some(Some(row))) =
+        (numbers.next(), numbers.next(), numbers.next())
+    else {
+        return None;
+    };
+
+    let button = match code & 0b11 {
+        0 => MouseButton::Left,
+        1 => MouseButton::Middle,
+        _ => MouseButton::Right,
+    };
+    let kind = if code & 64 != 0 {
+        match code & 0b11 {
+            0 => MouseEventKind::ScrollUp,
+            1 => MouseEventKind::ScrollDown,
+            2 => MouseEventKind::ScrollLeft,
+            _ => MouseEventKind::ScrollRight,
+        }
+    } else if code & 32 != 0 {
+        if code & 0b11 == 3 { MouseEventKind::Moved } else { MouseEventKind::Drag(button) }
+    } else if last == b'm' {
+        MouseEventKind::Up(button)
+    } else {
+        MouseEventKind::Down(button)
+    };
+
+    let mut modifiers = KeyModifiers::NONE;
+    if code & 4 != 0 {
+        modifiers = modifiers | KeyModifiers::SHIFT;
+    }
+    if code & 8 != 0 {
+        modifiers = modifiers | KeyModifiers::ALT;
+    }
+    if code & 16 != 0 {
+        modifiers = modifiers | KeyModifiers::CONTROL;
+    }
+
+    Some(Event::Mouse(MouseEvent {
+        kind,
+        column: column.saturating_sub(1),
+        row: row.saturating_sub(1),
+        modifiers,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(events: Vec<Event>) -> Vec<(KeyCode, KeyModifiers)> {
+        events
+            .into_iter()
+            .map(|event| match event {
+                Event::Key(key) => (key.code, key.modifiers),
+                other => panic!("expected a key, got {:?}", other),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_text_and_control_keys() {
+        let mut parser = InputParser::default();
+        assert_eq!(
+            keys(parser.parse("hé\r\x03\x7f\tQ".as_bytes())),
+            vec![
+                (KeyCode::Char('h'), KeyModifiers::NONE),
+                (KeyCode::Char('é'), KeyModifiers::NONE),
+                (KeyCode::Enter, KeyModifiers::NONE),
+                (KeyCode::Char('c'), KeyModifiers::CONTROL),
+                (KeyCode::Backspace, KeyModifiers::NONE),
+                (KeyCode::Tab, KeyModifiers::NONE),
+                (KeyCode::Char('Q'), KeyModifiers::SHIFT),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_escape_sequences() {
+        let mut parser = InputParser::default();
+        assert_eq!(
+            keys(parser.parse(b"\x1b[A\x1bOB\x1b[1;5C\x1b[3~\x1b[15~\x1b[Z\x1bx\x1b")),
+            vec![
+                (KeyCode::Up, KeyModifiers::NONE),
+                (KeyCode::Down, KeyModifiers::NONE),
+                (KeyCode::Right, KeyModifiers::CONTROL),
+                (KeyCode::Delete, KeyModifiers::NONE),
+                (KeyCode::F(5), KeyModifiers::NONE),
+                (KeyCode::BackTab, KeyModifiers::SHIFT),
+                (KeyCode::Char('x'), KeyModifiers::ALT),
+                (KeyCode::Esc, KeyModifiers::NONE),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sequences_split_across_reads() {
+        let mut parser = InputParser::default();
+        assert!(parser.parse(b"\x1b[1;").is_empty());
+        assert_eq!(keys(parser.parse(b"2D")), vec![(KeyCode::Left, KeyModifiers::SHIFT)]);
+
+        let euro = "€".as_bytes();
+        assert!(parser.parse(&euro[..1]).is_empty());
+        assert_eq!(keys(parser.parse(&euro[1..])), vec![(KeyCode::Char('€'), KeyModifiers::NONE)]);
+
+        assert!(parser.parse(b"\x1b[200~two\nli").is_empty());
+        assert_eq!(parser.parse(b"nes\x1b[201~"), vec![Event::Paste("two\nlines".to_string())]);
+    }
+
+    #[test]
+    fn test_mouse_and_focus() {
+        let mut parser = InputParser::default();
+        assert_eq!(
+            parser.parse(b"\x1b[<0;10;5M\x1b[<0;10;5m\x1b[<65;1;1M\x1b[I"),
+            vec![
+                Event::Mouse(MouseEvent {
+                    kind: MouseEventKind::Down(MouseButton::Left),
+                    column: 9,
+                    row: 4,
+                    modifiers: KeyModifiers::NONE,
+                }),
+                Event::Mouse(MouseEvent {
+                    kind: MouseEventKind::Up(MouseButton::Left),
+                    column: 9,
+                    row: 4,
+                    modifiers: KeyModifiers::NONE,
+                }),
+                Event::Mouse(MouseEvent {
+                    kind: MouseEventKind::ScrollDown,
+                    column: 0,
+                    row: 0,
+                    modifiers: KeyModifiers::NONE,
+                }),
+                Event::FocusGained,
+            ]
+        );
+    }
+}
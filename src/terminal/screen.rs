@@ -0,0 +1,375 @@
+//! A grid of character cells updated from terminal output.
+//!
+//! [`Screen`] interprets the text and escape sequences the renderer writes
+//! the way a terminal would: printing with automatic line wrapping, cursor
+//! movement, erasing, and scrolling once the cursor moves past the bottom
+//! row. Styling, cursor shapes, titles and mode switches do not change the
+//! text on screen and are skipped.
+
+use unicode_width::UnicodeWidthChar;
+
+/// One cell of the grid.
+#[derive(Debug, Clone, Default, PartialEq)]
+enum Cell {
+    #[default]
+    Blank,
+    /// A character, with any zero-width characters that follow it
+    Text(String),
+    /// The right half of a double-width character
+    Continuation,
+}
+
+/// Where the parser is within an escape sequence.
+#[derive(Debug, Default)]
+enum Parse {
+    #[default]
+    Ground,
+    /// After ESC
+    Escape,
+    /// Inside a control sequence, collecting its parameters
+    Csi(String),
+    /// Inside an operating system command, until BEL or ESC \
+    Osc { escape: bool },
+}
+
+/// The text on a terminal's screen.
+#[derive(Debug, Default)]
+pub(crate) struct Screen {
+    width: usize,
+    rows: Vec<Vec<Cell>>,
+    row: usize,
+    column: usize,
+    /// The last column was written; the next character goes on a new line
+    wrap_pending: bool,
+    parse: Parse,
+    /// Bytes of a UTF-8 character split across writes
+    partial: Vec<u8>,
+}
+
+impl Screen {
+    /// Create a blank screen with the cursor in the top-left corner.
+    pub fn new(width: u16, height: u16) -> Self {
+        let width = usize::from(width);
+        Self { width, rows: vec![vec![Cell::Blank; width]; usize::from(height)], ..Self::default() }
+    }
+
+    /// Change the size, keeping the text that still fits.
+    pub fn resize(&mut self, width: u16, height: u16) {
+        self.width = usize::from(width);
+        self.rows.resize_with(usize::from(height), Vec::new);
+        for row in &mut self.rows {
+            row.resize(self.width, Cell::Blank);
+        }
+        self.row = self.row.min(self.rows.len().saturating_sub(1));
+        self.column = self.column.min(self.width.saturating_sub(1));
+        self.wrap_pending = false;
+    }
+
+    /// Apply output written to the terminal.
+    pub fn write(&mut self, bytes: &[u8]) {
+        self.partial.extend_from_slice(bytes);
+        let buffer = std::mem::take(&mut self.partial);
+
+        let mut rest = &buffer[..];
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(text) => {
+                    text.chars().for_each(|c| self.input(c));
+                    return;
+                },
+                Err(err) => {
+                    let (valid, invalid) = rest.split_at(err.valid_up_to());
+                    if let Ok(text) = std::str::from_utf8(valid) {
+                        text.chars().for_each(|c| self.input(c));
+                    }
+                    match err.error_len() {
+                        Some(len) => {
+                            self.input(char::REPLACEMENT_CHARACTER);
+                            rest = &invalid[len..];
+                        },
+                        // Wait for the rest of the character
+                        None => {
+                            self.partial = invalid.to_vec();
+                            return;
+                        },
+                    }
+                },
+            }
+        }
+    }
+
+    /// The text on screen, one line per row.
+    ///
+    /// Trailing spaces and blank rows below the last text are left out.
+    pub fn text(&self) -> String {
+        let mut lines: Vec<String> = self
+            .rows
+            .iter()
+            .map(|row| {
+                let line: String = row
+                    .iter()
+                    .map(|cell| match cell {
+                        Cell::Blank => " ",
+                        Cell::Text(text) => text,
+                        Cell::Continuation => "",
+                    })
+                    .collect();
+                line.trim_end().to_string()
+            })
+            .collect();
+
+        while lines.last().is_some_and(String::is_empty) {
+            lines.pop();
+        }
+        lines.join("\n")
+    }
+
+    /// Interpret one character of output.
+    fn input(&mut self, c: char) {
+        match std::mem::take(&mut self.parse) {
+            Parse::Ground => self.ground(c),
+            Parse::Escape => match c {
+                '[' => self.parse = Parse::Csi(String::new()),
+                ']' => self.parse = Parse::Osc { escape: false },
+                // Reverse index
+                'M' => self.up(1),
+                // Any other single-character sequence changes no text
+                _ => {},
+            },
+            Parse::Csi(mut params) => match c {
+                '\x40'..='\x7e' => self.control_sequence(&params, c),
+                _ => {
+                    params.push(c);
+                    self.parse = Parse::Csi(params);
+                },
+            },
+            Parse::Osc { escape } => match c {
+                '\x07' => {},
+                '\\' if escape => {},
+                _ => self.parse = Parse::Osc { escape: c == '\x1b' },
+            },
+        }
+    }
+
+    /// Interpret a character outside escape sequences.
+    fn ground(&mut self, c: char) {
+        match c {
+            '\x1b' => self.parse = Parse::Escape,
+            '\r' => self.move_to_column(0),
+            '\n' => self.line_feed(),
+            '\x08' => self.move_to_column(self.column.saturating_sub(1)),
+            '\t' => self.move_to_column((self.column / 8 + 1) * 8),
+            c if c.is_control() => {},
+            c => self.print(c),
+        }
+    }
+
+    /// Apply a control sequence with the final character `action`.
+    fn control_sequence(&mut self, params: &str, action: char) {
+        // Private modes (`?25l`, `?2026h`) and cursor shapes (` q`) change no text
+        if params.starts_with('?') || params.contains(' ') {
+            return;
+        }
+
+        let args: Vec<usize> = params.split(';').map(|arg| arg.parse().unwrap_or(0)).collect();
+        let arg = |index: usize| args.get(index).copied().unwrap_or(0);
+        // Movements treat a missing or zero count as one
+        let count = arg(0).max(1);
+
+        match action {
+            'A' => self.up(count),
+            'B' => self.down(count),
+            'C' => self.move_to_column(self.column + count),
+            'D' => self.move_to_column(self.column.saturating_sub(count)),
+            'E' => {
+                self.down(count);
+                self.move_to_column(0);
+            },
+            'F' => {
+                self.up(count);
+                self.move_to_column(0);
+            },
+            'G' => self.move_to_column(count - 1),
+            'H' | 'f' => {
+                self.row = (arg(0).max(1) - 1).min(self.rows.len().saturating_sub(1));
+                self.move_to_column(arg(1).max(1) - 1);
+            },
+            'J' => self.erase_display(arg(0)),
+            'K' => self.erase_line(arg(0)),
+            'S' => (0..count).for_each(|_| self.scroll_up()),
+            // Styling, and anything else that changes no text
+            _ => {},
+        }
+    }
+
+    /// Write a character at the cursor and move past it.
+    fn print(&mut self, c: char) {
+        let width = c.width().unwrap_or(0);
+        if self.rows.is_empty() || self.width == 0 {
+            return;
+        }
+
+        if width == 0 {
+            // Combines with the character before the cursor
+            let column =
+                if self.wrap_pending { self.column } else { self.column.saturating_sub(1) };
+            if let Some(Cell::Text(text)) = self.rows[self.row].get_mut(column) {
+                text.push(c);
+            }
+            return;
+        }
+
+        if self.wrap_pending || self.column + width > self.width {
+            self.move_to_column(0);
+            self.line_feed();
+        }
+        // Too wide for the screen at all
+        if width > self.width {
+            return;
+        }
+
+        self.clear_cells(self.column, self.column + width);
+        self.rows[self.row][self.column] = Cell::Text(c.to_string());
+        if width == 2 {
+            self.rows[self.row][self.column + 1] = Cell::Continuation;
+        }
+
+        if self.column + width == self.width {
+            self.column = self.width - 1;
+            self.wrap_pending = true;
+        } else {
+            self.column += width;
+        }
+    }
+
+    /// Blank the cells from `start` to `end` on the cursor's row, along with
+    /// the other halves of double-width characters cut in two.
+    fn clear_cells(&mut self, start: usize, end: usize) {
+        let row = &mut self.rows[self.row];
+        let end = end.min(row.len());
+        if start >= end {
+            return;
+        }
+
+        if start > 0 && row[start] == Cell::Continuation {
+            row[start - 1] = Cell::Blank;
+        }
+        if row.get(end) == Some(&Cell::Continuation) {
+            row[end] = Cell::Blank;
+        }
+        row[start..end].fill(Cell::Blank);
+    }
+
+    /// Move the cursor to `column` on its row.
+    fn move_to_column(&mut self, column: usize) {
+        self.column = column.min(self.width.saturating_sub(1));
+        self.wrap_pending = false;
+    }
+
+    /// Move the cursor up, stopping at the top row.
+    fn up(&mut self, count: usize) {
+        self.row = self.row.saturating_sub(count);
+        self.wrap_pending = false;
+    }
+
+    /// Move the cursor down, stopping at the bottom row.
+    fn down(&mut self, count: usize) {
+        self.row = (self.row + count).min(self.rows.len().saturating_sub(1));
+        self.wrap_pending = false;
+    }
+
+    /// Move to the next row, scrolling the screen up at the bottom.
+    fn line_feed(&mut self) {
+        if self.row + 1 < self.rows.len() {
+            self.row += 1;
+        } else {
+            self.scroll_up();
+        }
+        self.wrap_pending = false;
+    }
+
+    /// Drop the top row and add a blank one at the bottom.
+    fn scroll_up(&mut self) {
+        if !self.rows.is_empty() {
+            self.rows.remove(0);
+            self.rows.push(vec![Cell::Blank; self.width]);
+        }
+    }
+
+    /// Erase part of the screen: below the cursor (0), above it (1) or all
+    /// of it (2, 3).
+    fn erase_display(&mut self, mode: usize) {
+        let (above, below) = match mode {
+            0 => (0..0, self.row + 1..self.rows.len()),
+            1 => (0..self.row, 0..0),
+            _ => (0..self.rows.len(), 0..0),
+        };
+        for row in above.chain(below) {
+            self.rows[row].fill(Cell::Blank);
+        }
+        if mode < 2 {
+            self.erase_line(mode);
+        }
+    }
+
+    /// Erase part of the cursor's row: from the cursor on (0), up to and
+    /// including it (1) or all of it (2).
+    fn erase_line(&mut self, mode: usize) {
+        if self.rows.is_empty() {
+            return;
+        }
+        match mode {
+            0 => self.clear_cells(self.column, self.width),
+            1 => self.clear_cells(0, self.column + 1),
+            _ => self.clear_cells(0, self.width),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn screen(width: u16, height: u16, output: &str) -> String {
+        let mut screen = Screen::new(width, height);
+        screen.write(output.as_bytes());
+        screen.text()
+    }
+
+    #[test]
+    fn test_text_wraps_and_scrolls() {
+        assert_eq!(screen(5, 3, "hello world"), "hello\n worl\nd");
+        // Filling the last column does not move to the next line by itself
+        assert_eq!(screen(5, 3, "hello\r\nworld"), "hello\nworld");
+        assert_eq!(screen(5, 2, "one\r\ntwo\r\nthree"), "two\nthree");
+        assert_eq!(screen(5, 2, "日本語"), "日本\n語");
+    }
+
+    #[test]
+    fn test_cursor_movement_and_erasing() {
+        assert_eq!(screen(10, 3, "one\r\ntwo\x1b[1A\x1b[1G\x1b[2Kuno"), "uno\ntwo");
+        assert_eq!(screen(10, 3, "a\r\nb\r\nc\x1b[2;1H\x1b[J"), "a");
+        assert_eq!(screen(10, 3, "abc\x1b[2J\x1b[3;2Hx"), "\n\n x");
+        assert_eq!(screen(10, 1, "abcdef\x1b[3G\x1b[K"), "ab");
+    }
+
+    #[test]
+    fn test_styling_and_modes_are_skipped() {
+        let output = "\x1b[?2026h\x1b[?25l\x1b]0;Title\x07\x1b[1;31mred\x1b[0m\x1b[6 q\x1b[?2026l";
+        assert_eq!(screen(10, 1, output), "red");
+    }
+
+    #[test]
+    fn test_characters_split_across_writes() {
+        let mut screen = Screen::new(10, 1);
+        let bytes = "✓ ok".as_bytes();
+        screen.write(&bytes[..2]);
+        screen.write(&bytes[2..]);
+        assert_eq!(screen.text(), "✓ ok");
+    }
+
+    #[test]
+    fn test_overwriting_half_of_a_wide_character() {
+        assert_eq!(screen(10, 1, "日本\x1b[2Gx"), " x本");
+    }
+}
@@ -0,0 +1,389 @@
+//! Backends over byte streams and in memory.
+
+use std::{
+    collections::VecDeque,
+    io::{self, Read, Write},
+    sync::{
+        Arc, Condvar, Mutex,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::{Backend, Event, Waker, parser::InputParser, screen::Screen};
+
+/// Default size of a terminal that cannot be queried.
+const DEFAULT_SIZE: (u16, u16) = (80, 24);
+
+/// A terminal reached through a pair of byte streams, such as a pty, a
+/// socket or an SSH channel.
+///
+/// Input bytes are read on a background thread and parsed into events;
+/// frames are written to the output stream. The stream's peer is
+/// responsible for raw mode (a pty's line discipline, or the SSH client),
+/// so switching raw mode does nothing. The size cannot be queried from a
+/// stream: set it with [`StreamBackend::with_size`] and report changes
+/// through a [`ResizeHandle`].
+///
+/// When the input stream ends, for example because the SSH client
+/// disconnected, the program quits as if `Cmd::quit` had been returned.
+pub struct StreamBackend<W> {
+    output: W,
+    input: Receiver<Input>,
+    sender: Sender<Input>,
+    size: Arc<Mutex<(u16, u16)>>,
+    closed: bool,
+}
+
+/// Sent by a stream backend's reader thread.
+enum Input {
+    Event(Event),
+    /// The input stream ended or failed
+    Closed,
+    /// Sent by the backend's waker
+    Wake,
+}
+
+impl<W: Write + Send> StreamBackend<W> {
+    /// Create a backend reading input from `input` and writing to `output`.
+    ///
+    /// The reader thread stops when `input` ends or fails, or after the
+    /// backend is dropped and more input arrives.
+    pub fn new<R: Read + Send + 'static>(mut input: R, output: W) -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        let tx = sender.clone();
+        thread::Builder::new()
+            .name("teapot-input".to_string())
+            .spawn(move || {
+                let mut parser = InputParser::default();
+                let mut buf = [0; 1024];
+                loop {
+                    let read = match input.read(&mut buf) {
+                        Ok(0) => break,
+                        Ok(read) => read,
+                        Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                        Err(_) => break,
+                    };
+                    for event in parser.parse(&buf[..read]) {
+                        if tx.send(Input::Event(event)).is_err() {
+                            return;
+                        }
+                    }
+                }
+                let _ = tx.send(Input::Closed);
+            })
+            .expect("failed to spawn input thread");
+
+        Self {
+            output,
+            input: receiver,
+            sender,
+            size: Arc::new(Mutex::new(DEFAULT_SIZE)),
+            closed: false,
+        }
+    }
+
+    /// Set the terminal size, 80x24 by default.
+    pub fn with_size(self, width: u16, height: u16) -> Self {
+        *self.size.lock().unwrap_or_else(|e| e.into_inner()) = (width, height);
+        self
+    }
+
+    /// Get a handle for reporting that the terminal was resized.
+    pub fn resize_handle(&self) -> ResizeHandle {
+        ResizeHandle { size: Arc::clone(&self.size), events: self.sender.clone() }
+    }
+}
+
+impl<W: Write> Write for StreamBackend<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.output.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.output.flush()
+    }
+}
+
+impl<W: Write + Send> Backend for StreamBackend<W> {
+    fn enable_raw_mode(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn disable_raw_mode(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn size(&self) -> io::Result<(u16, u16)> {
+        Ok(*self.size.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    fn poll_event(&mut self, timeout: Duration) -> io::Result<Option<Event>> {
+        let input = if self.closed {
+            self.input.try_recv().map_err(|_| RecvTimeoutError::Disconnected)
+        } else {
+            self.input.recv_timeout(timeout)
+        };
+        match input {
+            Ok(Input::Event(event)) => Ok(Some(event)),
+            Ok(Input::Wake) | Err(RecvTimeoutError::Timeout) => Ok(None),
+            Ok(Input::Closed) | Err(RecvTimeoutError::Disconnected) => {
+                self.closed = true;
+                Err(io::Error::new(io::ErrorKind::UnexpectedEof, "terminal input closed"))
+            },
+        }
+    }
+
+    fn waker(&mut self) -> Option<Waker> {
+        let sender = Mutex::new(self.sender.clone());
+        Some(Waker::new(move || {
+            let _ = sender.lock().unwrap_or_else(|e| e.into_inner()).send(Input::Wake);
+        }))
+    }
+}
+
+/// Reports size changes of a [`StreamBackend`]'s terminal, for example on
+/// an SSH window-change request.
+#[derive(Clone)]
+pub struct ResizeHandle {
+    size: Arc<Mutex<(u16, u16)>>,
+    events: Sender<Input>,
+}
+
+impl ResizeHandle {
+    /// Record the new size and send the program an [`Event::Resize`].
+    pub fn resize(&self, width: u16, height: u16) {
+        *self.size.lock().unwrap_or_else(|e| e.into_inner()) = (width, height);
+        let _ = self.events.send(Input::Event(Event::Resize { width, height }));
+    }
+}
+
+/// A terminal kept in memory, for tests.
+///
+/// Clones share the same terminal: give one to the program and keep
+/// another to send input and inspect what was written, either as raw
+/// output or as the text it leaves on screen.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let backend = MemoryBackend::new(40, 10);
+/// backend.push_event(Event::Key(KeyEvent { code: KeyCode::Char('q'), modifiers: KeyModifiers::NONE }));
+///
+/// Program::new(app).with_backend(backend.clone()).run()?;
+/// assert!(backend.screen().contains("Goodbye"));
+/// ```
+#[derive(Clone)]
+pub struct MemoryBackend {
+    state: Arc<MemoryState>,
+}
+
+#[derive(Default)]
+struct MemoryState {
+    input: Mutex<VecDeque<Event>>,
+    input_ready: Condvar,
+    output: Mutex<Vec<u8>>,
+    screen: Mutex<Screen>,
+    size: Mutex<(u16, u16)>,
+    raw_mode: AtomicBool,
+    /// Set by the backend's waker until the next poll
+    woken: AtomicBool,
+}
+
+impl MemoryBackend {
+    /// Create a terminal of the given size.
+    pub fn new(width: u16, height: u16) -> Self {
+        let state = MemoryState {
+            screen: Mutex::new(Screen::new(width, height)),
+            size: Mutex::new((width, height)),
+            ..Default::default()
+        };
+        Self { state: Arc::new(state) }
+    }
+
+    /// Queue an input event.
+    pub fn push_event(&self, event: Event) {
+        self.state.input.lock().unwrap_or_else(|e| e.into_inner()).push_back(event);
+        self.state.input_ready.notify_all();
+    }
+
+    /// Resize the terminal and queue an [`Event::Resize`].
+    pub fn resize(&self, width: u16, height: u16) {
+        self.set_size(width, height);
+        self.push_event(Event::Resize { width, height });
+    }
+
+    /// Resize the terminal without queueing an event.
+    pub(crate) fn set_size(&self, width: u16, height: u16) {
+        *self.state.size.lock().unwrap_or_else(|e| e.into_inner()) = (width, height);
+        self.state.screen.lock().unwrap_or_else(|e| e.into_inner()).resize(width, height);
+    }
+
+    /// Blank the screen, keeping the output written so far.
+    pub(crate) fn clear_screen(&self) {
+        let (width, height) = *self.state.size.lock().unwrap_or_else(|e| e.into_inner());
+        *self.state.screen.lock().unwrap_or_else(|e| e.into_inner()) = Screen::new(width, height);
+    }
+
+    /// Everything written to the terminal so far, escape sequences included.
+    pub fn output(&self) -> String {
+        String::from_utf8_lossy(&self.state.output.lock().unwrap_or_else(|e| e.into_inner()))
+            .into_owned()
+    }
+
+    /// The text the output leaves on screen, as a terminal of this size
+    /// would show it: lines wrap at the right edge, the screen scrolls at
+    /// the bottom, and cursor movement and erasing apply. Styling is left
+    /// out, as are trailing spaces and blank rows below the last text.
+    pub fn screen(&self) -> String {
+        self.state.screen.lock().unwrap_or_else(|e| e.into_inner()).text()
+    }
+
+    /// Whether the terminal is in raw mode.
+    pub fn is_raw_mode(&self) -> bool {
+        self.state.raw_mode.load(Ordering::SeqCst)
+    }
+}
+
+impl Write for MemoryBackend {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.state.output.lock().unwrap_or_else(|e| e.into_inner()).extend_from_slice(buf);
+        self.state.screen.lock().unwrap_or_else(|e| e.into_inner()).write(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Backend for MemoryBackend {
+    fn enable_raw_mode(&mut self) -> io::Result<()> {
+        self.state.raw_mode.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn disable_raw_mode(&mut self) -> io::Result<()> {
+        self.state.raw_mode.store(false, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn size(&self) -> io::Result<(u16, u16)> {
+        Ok(*self.state.size.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    fn poll_event(&mut self, timeout: Duration) -> io::Result<Option<Event>> {
+        let deadline = Instant::now().checked_add(timeout);
+        let mut input = self.state.input.lock().unwrap_or_else(|e| e.into_inner());
+        loop {
+            if let Some(event) = input.pop_front() {
+                return Ok(Some(event));
+            }
+            if self.state.woken.swap(false, Ordering::SeqCst) {
+                return Ok(None);
+            }
+            let remaining = deadline.map_or(Duration::MAX, |deadline| {
+                deadline.saturating_duration_since(Instant::now())
+            });
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+            input = self
+                .state
+                .input_ready
+                .wait_timeout(input, remaining)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+    }
+
+    fn waker(&mut self) -> Option<Waker> {
+        let state = Arc::clone(&self.state);
+        Some(Waker::new(move || {
+            // Set under the input lock so a poll cannot miss the wake-up
+            let _input = state.input.lock().unwrap_or_else(|e| e.into_inner());
+            state.woken.store(true, Ordering::SeqCst);
+            state.input_ready.notify_all();
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terminal::{KeyCode, KeyEvent, KeyModifiers};
+
+    #[test]
+    fn test_stream_backend_parses_input() {
+        // Input stays open until the events are read, so the resize cannot
+        // arrive after the end of input
+        let (input, mut writer) = io::pipe().unwrap();
+        writer.write_all(b"a\x1b[B").unwrap();
+        let mut backend = StreamBackend::new(input, Vec::new()).with_size(100, 30);
+        backend.resize_handle().resize(120, 40);
+
+        let timeout = Duration::from_secs(1);
+        let mut events = Vec::new();
+        while events.len() < 3 {
+            events.push(backend.poll_event(timeout).unwrap().unwrap());
+        }
+
+        assert!(events.contains(&Event::Key(KeyEvent {
+            code: KeyCode::Char('a'),
+            modifiers: KeyModifiers::NONE
+        })));
+        assert!(events.contains(&Event::Key(KeyEvent {
+            code: KeyCode::Down,
+            modifiers: KeyModifiers::NONE
+        })));
+        assert!(events.contains(&Event::Resize { width: 120, height: 40 }));
+        assert_eq!(backend.size().unwrap(), (120, 40));
+
+        // The end of input is reported as such, and the program quits on it
+        drop(writer);
+        let err = backend.poll_event(timeout).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        backend.write_all(b"frame").unwrap();
+        assert_eq!(backend.output, b"frame");
+    }
+
+    #[test]
+    fn test_memory_backend() {
+        let backend = MemoryBackend::new(40, 10);
+        let mut program_side = backend.clone();
+
+        assert_eq!(program_side.poll_event(Duration::ZERO).unwrap(), None);
+        backend.push_event(Event::FocusGained);
+        assert_eq!(program_side.poll_event(Duration::ZERO).unwrap(), Some(Event::FocusGained));
+
+        program_side.enable_raw_mode().unwrap();
+        write!(program_side, "hello\x1b[1Gj").unwrap();
+        assert!(backend.is_raw_mode());
+        assert_eq!(backend.output(), "hello\x1b[1Gj");
+        assert_eq!(backend.screen(), "jello");
+        assert_eq!(backend.size().unwrap(), (40, 10));
+    }
+
+    #[test]
+    fn test_wakers_interrupt_polling() {
+        let mut memory = MemoryBackend::new(40, 10);
+        let waker = memory.waker().unwrap();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            waker.wake();
+        });
+        let started = Instant::now();
+        assert_eq!(memory.poll_event(Duration::MAX).unwrap(), None);
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        // Input that stays open while the backend is woken
+        let (input, _writer) = io::pipe().unwrap();
+        let mut stream = StreamBackend::new(input, Vec::new());
+        stream.waker().unwrap().wake();
+        assert_eq!(stream.poll_event(Duration::from_secs(5)).unwrap(), None);
+    }
+}
@@ -0,0 +1,146 @@
+//! Waking a program that waits for terminal input.
+
+use std::{fmt, sync::Arc};
+
+/// Interrupts a [`Backend`]'s wait for input from another thread.
+///
+/// The runtime wakes its backend whenever background work, a program handle
+/// or a signal has something for the event loop, so an idle program sleeps
+/// until there is work instead of checking at a fixed interval.
+///
+/// [`Backend`]: super::Backend
+#[derive(Clone)]
+pub struct Waker {
+    wake: Arc<dyn Fn() + Send + Sync>,
+}
+
+impl Waker {
+    /// Create a waker calling `wake`, which must make the backend's current
+    /// or next `poll_event` return early.
+    pub fn new(wake: impl Fn() + Send + Sync + 'static) -> Self {
+        Self { wake: Arc::new(wake) }
+    }
+
+    /// Wake the backend.
+    pub fn wake(&self) {
+        (self.wake)();
+    }
+}
+
+impl fmt::Debug for Waker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Waker").finish_non_exhaustive()
+    }
+}
+
+#[cfg(unix)]
+pub(crate) use imp::InputWaiter;
+
+#[cfg(unix)]
+mod imp {
+    use std::{
+        fs::File,
+        io::{self, IsTerminal, Read, Write},
+        os::{
+            fd::{AsFd, AsRawFd, BorrowedFd},
+            unix::net::UnixStream,
+        },
+        sync::Arc,
+        time::Duration,
+    };
+
+    use mio::{Events, Interest, Poll, Token, unix::SourceFd};
+    use signal_hook::{SigId, consts::SIGWINCH, low_level};
+
+    use super::Waker;
+    use crate::terminal::backend::open_tty_input;
+
+    const INPUT: Token = Token(0);
+    const WAKE: Token = Token(1);
+
+    /// Longest wait passed to the poller; longer waits are unbounded.
+    const MAX_WAIT: Duration = Duration::from_secs(60 * 60 * 24);
+
+    /// Waits for the process's terminal input until woken.
+    ///
+    /// crossterm's own wait cannot be interrupted, so the terminal is
+    /// watched here alongside a wake pipe, and crossterm only reads once
+    /// something is ready.
+    pub(crate) struct InputWaiter {
+        poll: Poll,
+        events: Events,
+        /// Read end of the wake pipe
+        wake_rx: UnixStream,
+        /// Write end of the wake pipe, written by wakers
+        wake_tx: Arc<UnixStream>,
+        /// The controlling terminal, when stdin is not a terminal
+        _tty: Option<File>,
+        /// Writes to the wake pipe on resize
+        resize: SigId,
+    }
+
+    impl InputWaiter {
+        /// Watch the terminal crossterm reads from: stdin when it is a
+        /// terminal, the controlling terminal otherwise.
+        pub fn new() -> io::Result<Self> {
+            let (wake_rx, wake_tx) = UnixStream::pair()?;
+            wake_rx.set_nonblocking(true)?;
+            wake_tx.set_nonblocking(true)?;
+
+            let stdin = io::stdin();
+            let tty = if stdin.is_terminal() { None } else { Some(open_tty_input()?) };
+            let input: BorrowedFd<'_> = match &tty {
+                Some(tty) => tty.as_fd(),
+                None => stdin.as_fd(),
+            };
+
+            let poll = Poll::new()?;
+            let registry = poll.registry();
+            registry.register(&mut SourceFd(&input.as_raw_fd()), INPUT, Interest::READABLE)?;
+            registry.register(&mut SourceFd(&wake_rx.as_raw_fd()), WAKE, Interest::READABLE)?;
+
+            // crossterm reports resizes from its own SIGWINCH handler; wake
+            // up so it gets the chance
+            let resize = low_level::pipe::register(SIGWINCH, wake_tx.try_clone()?)?;
+
+            Ok(Self {
+                poll,
+                events: Events::with_capacity(4),
+                wake_rx,
+                wake_tx: Arc::new(wake_tx),
+                _tty: tty,
+                resize,
+            })
+        }
+
+        /// A waker interrupting [`InputWaiter::wait`].
+        pub fn waker(&self) -> Waker {
+            let wake_tx = Arc::clone(&self.wake_tx);
+            Waker::new(move || {
+                // A full pipe already holds a wake-up
+                let _ = (&*wake_tx).write(&[0]);
+            })
+        }
+
+        /// Wait up to `timeout` for input, a wake-up or a resize.
+        pub fn wait(&mut self, timeout: Duration) -> io::Result<()> {
+            let timeout = (timeout < MAX_WAIT).then_some(timeout);
+            match self.poll.poll(&mut self.events, timeout) {
+                Err(err) if err.kind() != io::ErrorKind::Interrupted => return Err(err),
+                _ => {},
+            }
+
+            if self.events.iter().any(|event| event.token() == WAKE) {
+                let mut buf = [0; 64];
+                while matches!((&self.wake_rx).read(&mut buf), Ok(read) if read > 0) {}
+            }
+            Ok(())
+        }
+    }
+
+    impl Drop for InputWaiter {
+        fn drop(&mut self) {
+            low_level::unregister(self.resize);
+        }
+    }
+}
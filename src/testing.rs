@@ -0,0 +1,587 @@
+//! Headless test harness for models.
+//!
+//! [`TestProgram`] runs a [`Model`] through the same runtime as
+//! [`Program::run`] — `handle_event`, the message filter, commands and
+//! subscriptions — without a terminal. Time is virtual: ticks and interval
+//! subscriptions only fire when the test calls [`TestProgram::advance`], so
+//! tests are deterministic and never sleep.
+//!
+//! Frames are drawn by the same renderer as on a real terminal, onto an
+//! in-memory terminal of 80x24 by default. What the tests see is the text
+//! left on that screen: long lines wrap, tall views scroll, and escape
+//! sequences in the view move the cursor or erase as they would for a user.
+//! Styling is left out.
+//!
+//! Background work (`Cmd::perform`, `Cmd::perform_async`, stream
+//! subscriptions) still runs on real threads; use [`TestProgram::wait_for`]
+//! to wait for its results.
+//!
+//! # Example
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use teapot::{Cmd, Event, KeyCode, Model, testing::TestProgram};
+//!
+//! struct Counter { count: i32 }
+//! enum Msg { Inc, Quit }
+//!
+//! impl Model for Counter {
+//!     type Message = Msg;
+//!     fn init(&self) -> Option<Cmd<Msg>> { None }
+//!     fn update(&mut self, msg: Msg) -> Option<Cmd<Msg>> {
+//!         match msg {
+//!             Msg::Inc => self.count += 1,
+//!             Msg::Quit => return Some(Cmd::quit()),
+//!         }
+//!         None
+//!     }
+//!     fn view(&self) -> String { format!("Count: {}", self.count) }
+//!     fn handle_event(&self, event: Event) -> Option<Msg> {
+//!         match event {
+//!             Event::Key(key) if key.code == KeyCode::Char('+') => Some(Msg::Inc),
+//!             Event::Key(key) if key.code == KeyCode::Char('q') => Some(Msg::Quit),
+//!             _ => None,
+//!         }
+//!     }
+//! }
+//!
+//! let mut test = TestProgram::new(Counter { count: 0 });
+//! test.type_text("++").press(KeyCode::Char('q'));
+//!
+//! assert!(test.is_finished());
+//! assert_eq!(test.view(), "Count: 2");
+//! assert_eq!(test.into_model().count, 2);
+//! ```
+//!
+//! # Snapshots
+//!
+//! [`TestProgram::assert_snapshot`] compares the current frame with a golden
+//! file in `tests/snapshots/<name>.snap` under the crate being tested. Set
+//! `TEAPOT_UPDATE_SNAPSHOTS=1` to create or update the golden files.
+//!
+//! # Replaying recordings
+//!
+//! A session recorded with [`Program::record_to`] can be replayed with
+//! [`TestProgram::replay`], reproducing the model's `update` calls and
+//! frames from a bug report. See [`runtime::recording`].
+//!
+//! [`Program::run`]: crate::Program::run
+//! [`Program::record_to`]: crate::Program::record_to
+//! [`runtime::recording`]: crate::runtime::recording
+
+use std::{
+    env, fs, io,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    Model, Program, ProgramError,
+    runtime::{Cursor, Recording, StepKind},
+    terminal::{Event, KeyCode, KeyEvent, KeyModifiers, MemoryBackend},
+};
+
+/// Environment variable that makes snapshot assertions write golden files.
+pub const UPDATE_SNAPSHOTS_ENV: &str = "TEAPOT_UPDATE_SNAPSHOTS";
+
+/// How often [`TestProgram::wait_for`] checks subscriptions for messages.
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// A model running under the test harness.
+///
+/// Interaction methods return `&mut Self` so they can be chained. Once the
+/// program has quit, further input is ignored.
+///
+/// # Panics
+///
+/// Methods panic if the runtime reports an error, such as a command that
+/// panicked, so failures surface in the test that caused them.
+pub struct TestProgram<M: Model> {
+    program: Program<M>,
+    /// The terminal frames are drawn on
+    backend: MemoryBackend,
+    /// When the program started, the origin of recorded times
+    started: Instant,
+    now: Instant,
+    timeout: Duration,
+    /// Distinct screens, in the order they were drawn
+    frames: Vec<String>,
+    /// Lines printed above the view with `Cmd::println`
+    printed: Vec<String>,
+    finished: bool,
+}
+
+impl<M: Model> TestProgram<M> {
+    /// Start a model on an 80x24 terminal.
+    pub fn new(model: M) -> Self {
+        Self::from_program(Program::new(model))
+    }
+
+    /// Start a configured program, keeping its message filter.
+    pub fn from_program(mut program: Program<M>) -> Self {
+        let now = Instant::now();
+        program.set_clock(now);
+        let backend = MemoryBackend::new(80, 24);
+        program.draw_on(backend.clone());
+
+        let mut test = Self {
+            program,
+            backend,
+            started: now,
+            now,
+            timeout: Duration::from_secs(1),
+            frames: Vec::new(),
+            printed: Vec::new(),
+            finished: false,
+        };
+
+        let quit = test.program.start();
+        test.finish_step(quit);
+        test
+    }
+
+    /// Set the size of the terminal, drawing the current frame afresh on a
+    /// blank screen and forgetting earlier frames.
+    ///
+    /// This does not send a resize event; use [`TestProgram::resize`] for that.
+    pub fn with_size(mut self, width: u16, height: u16) -> Self {
+        self.redraw_at(width, height);
+        self
+    }
+
+    /// Set how long [`TestProgram::wait_for`] waits before failing.
+    ///
+    /// Defaults to one second.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Send a terminal event through the model's `handle_event`.
+    pub fn send_event(&mut self, event: Event) -> &mut Self {
+        if !self.finished {
+            let quit = self.program.handle_input(event);
+            self.finish_step(quit);
+        }
+        self
+    }
+
+    /// Send a message directly to the model's `update`.
+    ///
+    /// The message passes through the program's filter.
+    pub fn send(&mut self, msg: M::Message) -> &mut Self {
+        if !self.finished {
+            let quit = self.program.deliver(msg);
+            self.finish_step(quit);
+        }
+        self
+    }
+
+    /// Press a key without modifiers.
+    pub fn press(&mut self, code: KeyCode) -> &mut Self {
+        self.press_with(code, KeyModifiers::NONE)
+    }
+
+    /// Press a key with modifiers held.
+    pub fn press_with(&mut self, code: KeyCode, modifiers: KeyModifiers) -> &mut Self {
+        self.send_event(Event::Key(KeyEvent { code, modifiers }))
+    }
+
+    /// Type each character of `text` as a key press.
+    pub fn type_text(&mut self, text: &str) -> &mut Self {
+        for c in text.chars() {
+            self.press(KeyCode::Char(c));
+        }
+        self
+    }
+
+    /// Resize the terminal and send the resize event.
+    ///
+    /// Text already on screen is cut off or padded rather than reflowed, and
+    /// the next frame is drawn over it.
+    pub fn resize(&mut self, width: u16, height: u16) -> &mut Self {
+        self.backend.set_size(width, height);
+        self.send_event(Event::Resize { width, height })
+    }
+
+    /// Advance the virtual clock, firing ticks and subscriptions in order.
+    ///
+    /// Each timer fires at its scheduled time, so advancing by one second
+    /// fires a 100ms interval subscription ten times.
+    pub fn advance(&mut self, duration: Duration) -> &mut Self {
+        let target = self.now + duration;
+
+        while !self.finished {
+            match self.program.next_due() {
+                Some(due) if due <= target => self.step_to(due.max(self.now)),
+                _ => break,
+            }
+        }
+
+        if !self.finished {
+            self.step_to(target);
+        }
+        self
+    }
+
+    /// Replay a session recorded with [`Program::record_to`].
+    ///
+    /// Each step happens at its recorded time, measured from when this test
+    /// program was created: events go through `handle_event`, and ticks fire
+    /// the timers and interval subscriptions due at that moment. Frames are
+    /// drawn afresh at the recorded size. Replay onto a freshly created program,
+    /// so its timers were scheduled at the same times as during recording.
+    /// Messages from other sources were not recorded; see
+    /// [`runtime::recording`].
+    ///
+    /// [`Program::record_to`]: crate::Program::record_to
+    /// [`runtime::recording`]: crate::runtime::recording
+    pub fn replay(&mut self, recording: &Recording) -> &mut Self {
+        if let Some((width, height)) = recording.size {
+            self.redraw_at(width, height);
+        }
+
+        for step in &recording.steps {
+            if self.finished {
+                break;
+            }
+
+            let at = (self.started + step.at).max(self.now);
+            match &step.kind {
+                StepKind::Tick => self.step_to(at),
+                StepKind::Event(event) => {
+                    self.now = at;
+                    self.program.set_clock(at);
+                    if let Event::Resize { width, height } = *event {
+                        self.backend.set_size(width, height);
+                    }
+                    self.send_event(event.clone());
+                },
+            }
+        }
+        self
+    }
+
+    /// Wait until the current frame satisfies `condition`.
+    ///
+    /// Results from background commands and subscriptions are delivered
+    /// while waiting; the virtual clock does not move.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the condition is not met within the timeout (see
+    /// [`TestProgram::with_timeout`]) or the program quits first.
+    pub fn wait_for(&mut self, condition: impl Fn(&str) -> bool) -> &mut Self {
+        let deadline = Instant::now() + self.timeout;
+
+        loop {
+            self.step_to(self.now);
+            if condition(&self.view()) {
+                return self;
+            }
+            if self.finished {
+                panic!("program quit before the condition was met; last frame:\n{}", self.view());
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                panic!("condition not met within {:?}; last frame:\n{}", self.timeout, self.view());
+            }
+
+            let quit = self.program.wait_for_event(remaining.min(WAIT_POLL_INTERVAL));
+            self.finish_step(quit);
+        }
+    }
+
+    /// The current frame: the text on the terminal's screen.
+    ///
+    /// Trailing spaces and blank rows below the last text are left out; see
+    /// [`MemoryBackend::screen`].
+    pub fn view(&self) -> String {
+        self.backend.screen()
+    }
+
+    /// Where the model places the terminal cursor, if it shows one.
+    pub fn cursor(&self) -> Option<Cursor> {
+        self.program.model().frame().cursor
+    }
+
+    /// Every distinct frame drawn so far, as text like [`TestProgram::view`].
+    pub fn frames(&self) -> Vec<String> {
+        self.frames.clone()
+    }
+
+    /// Lines printed above the view with `Cmd::println`, in order.
+    pub fn printed(&self) -> &[String] {
+        &self.printed
+    }
+
+    /// The model in its current state.
+    pub fn model(&self) -> &M {
+        self.program.model()
+    }
+
+    /// Whether the program has quit.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// The error the program stopped with, such as one raised by `Cmd::fail`.
+    pub fn error(&self) -> Option<&ProgramError> {
+        self.program.failure()
+    }
+
+    /// Stop the program and return the final model.
+    pub fn into_model(self) -> M {
+        self.program.into_model()
+    }
+
+    /// Compare the current frame with the golden file `name`.
+    ///
+    /// See [`assert_snapshot`].
+    #[track_caller]
+    pub fn assert_snapshot(&self, name: &str) {
+        assert_snapshot(name, &self.view());
+    }
+
+    /// Move the virtual clock to `now` and deliver everything ready.
+    fn step_to(&mut self, now: Instant) {
+        self.now = now;
+        self.program.set_clock(now);
+
+        let (backend, frames, printed) = (&self.backend, &mut self.frames, &mut self.printed);
+        let quit = self
+            .program
+            .process_pending(now, &mut |program| draw(program, backend, frames, printed));
+        self.finish_step(quit);
+    }
+
+    /// Resize the terminal and draw the current frame on a blank screen.
+    fn redraw_at(&mut self, width: u16, height: u16) {
+        self.backend.set_size(width, height);
+        self.backend.clear_screen();
+        self.program.draw_on(self.backend.clone());
+        self.frames.clear();
+        self.finish_step(Ok(self.finished));
+    }
+
+    /// Draw the frame after an interaction and note whether the program quit.
+    fn finish_step(&mut self, quit: io::Result<bool>) {
+        let quit = quit.and_then(|quit| {
+            draw(&mut self.program, &self.backend, &mut self.frames, &mut self.printed)
+                .map(|()| quit)
+        });
+        match quit {
+            Ok(_) if matches!(self.program.failure(), Some(ProgramError::Panic(_))) => {
+                panic!("program failed: {}", self.program.failure().unwrap())
+            },
+            Ok(quit) => self.finished |= quit,
+            Err(err) => panic!("program failed: {}", err),
+        }
+    }
+}
+
+/// Draw the current frame on `backend`, recording the screen unless it
+/// matches the previous frame, and the lines printed above it.
+fn draw<M: Model>(
+    program: &mut Program<M>,
+    backend: &MemoryBackend,
+    frames: &mut Vec<String>,
+    printed: &mut Vec<String>,
+) -> io::Result<()> {
+    printed.extend_from_slice(program.queued_printed());
+    program.render(program.now())?;
+
+    let screen = backend.screen();
+    if frames.last() != Some(&screen) {
+        frames.push(screen);
+    }
+    Ok(())
+}
+
+/// Compare `actual` with the golden file `tests/snapshots/<name>.snap`.
+///
+/// The file is resolved relative to `CARGO_MANIFEST_DIR` of the crate under
+/// test. When the `TEAPOT_UPDATE_SNAPSHOTS` environment variable is set, the
+/// golden file is written instead of compared.
+///
+/// # Panics
+///
+/// Panics if the golden file is missing or its contents differ.
+#[track_caller]
+pub fn assert_snapshot(name: &str, actual: &str) {
+    let path = snapshot_path(name);
+
+    if env::var_os(UPDATE_SNAPSHOTS_ENV).is_some() {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).expect("failed to create snapshot directory");
+        }
+        fs::write(&path, actual).expect("failed to write snapshot");
+        return;
+    }
+
+    match fs::read_to_string(&path) {
+        Ok(expected) => assert!(
+            expected == actual,
+            "snapshot `{}` does not match {}\n--- expected\n{}\n--- actual\n{}\n\
+             (set {}=1 to update)",
+            name,
+            path.display(),
+            expected,
+            actual,
+            UPDATE_SNAPSHOTS_ENV
+        ),
+        Err(_) => panic!(
+            "snapshot `{}` not found at {} (set {}=1 to create it)",
+            name,
+            path.display(),
+            UPDATE_SNAPSHOTS_ENV
+        ),
+    }
+}
+
+/// Location of the golden file for a snapshot.
+fn snapshot_path(name: &str) -> PathBuf {
+    let root = env::var_os("CARGO_MANIFEST_DIR").map_or_else(|| PathBuf::from("."), PathBuf::from);
+    root.join("tests").join("snapshots").join(format!("{}.snap", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Cmd, Sub};
+
+    struct Clock {
+        ticks: u32,
+        status: &'static str,
+    }
+
+    #[derive(Debug)]
+    enum Msg {
+        Tick,
+        Loaded(&'static str),
+        Load,
+        Quit,
+    }
+
+    impl Model for Clock {
+        type Message = Msg;
+
+        fn init(&self) -> Option<Cmd<Msg>> {
+            None
+        }
+
+        fn update(&mut self, msg: Msg) -> Option<Cmd<Msg>> {
+            match msg {
+                Msg::Tick => self.ticks += 1,
+                Msg::Loaded(status) => self.status = status,
+                Msg::Load => {
+                    return Some(Cmd::perform(|| {
+                        std::thread::sleep(Duration::from_millis(20));
+                        Msg::Loaded("ready")
+                    }));
+                },
+                Msg::Quit => {
+                    return Some(Cmd::batch(vec![Cmd::println("bye"), Cmd::quit()]));
+                },
+            }
+            None
+        }
+
+        fn view(&self) -> String {
+            format!("\x1b[1mticks: {}\x1b[0m\nstatus: {}", self.ticks, self.status)
+        }
+
+        fn handle_event(&self, event: Event) -> Option<Msg> {
+            match event {
+                Event::Key(key) if key.code == KeyCode::Char('l') => Some(Msg::Load),
+                Event::Key(key) if key.code == KeyCode::Esc => Some(Msg::Quit),
+                _ => None,
+            }
+        }
+
+        fn subscriptions(&self) -> Sub<Msg> {
+            Sub::every_millis("tick", 100, || Msg::Tick)
+        }
+    }
+
+    fn clock() -> TestProgram<Clock> {
+        TestProgram::new(Clock { ticks: 0, status: "idle" })
+    }
+
+    #[test]
+    fn test_advance_fires_each_interval() {
+        let mut test = clock();
+
+        test.advance(Duration::from_millis(99));
+        assert_eq!(test.model().ticks, 0);
+
+        test.advance(Duration::from_millis(901));
+        assert_eq!(test.model().ticks, 10);
+    }
+
+    #[test]
+    fn test_wait_for_background_command() {
+        let mut test = clock();
+
+        test.press(KeyCode::Char('l')).wait_for(|view| view.contains("ready"));
+        assert_eq!(test.model().status, "ready");
+    }
+
+    #[test]
+    fn test_quit_and_frames() {
+        let mut test = clock().with_size(12, 2);
+
+        test.advance(Duration::from_millis(200)).press(KeyCode::Esc);
+        assert!(test.is_finished());
+        assert_eq!(
+            test.frames(),
+            vec!["ticks: 0\nstatus: idle", "ticks: 1\nstatus: idle", "ticks: 2\nstatus: idle"]
+        );
+        assert_eq!(test.printed(), ["bye"]);
+
+        // Input after quitting is ignored
+        test.send(Msg::Tick);
+        assert_eq!(test.into_model().ticks, 2);
+    }
+
+    #[test]
+    fn test_ctrl_c_interrupts_by_default() {
+        let mut test = clock();
+
+        test.press_with(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        assert!(test.is_finished());
+
+        // Suspending is a no-op without a terminal
+        let mut test = clock();
+        test.press_with(KeyCode::Char('z'), KeyModifiers::CONTROL);
+        assert!(!test.is_finished());
+    }
+
+    #[test]
+    #[should_panic(expected = "condition not met")]
+    fn test_wait_for_times_out() {
+        clock().with_timeout(Duration::from_millis(20)).wait_for(|view| view.contains("never"));
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let mut test = clock().with_size(20, 2);
+
+        test.advance(Duration::from_millis(300));
+        test.assert_snapshot("testing_clock");
+    }
+
+    #[test]
+    fn test_view_is_the_terminal_screen() {
+        // The second line wraps, scrolling the first off the top
+        let test = clock().with_size(10, 2);
+        assert_eq!(test.view(), "status: id\nle");
+
+        // Only the lines that changed are redrawn over the earlier frame
+        let program = Program::new(Clock { ticks: 0, status: "idle" }).with_alt_screen();
+        let mut test = TestProgram::from_program(program).with_size(20, 3);
+        test.advance(Duration::from_millis(100));
+        assert_eq!(test.view(), "ticks: 1\nstatus: idle");
+    }
+}
@@ -7,5 +7,5 @@ mod worker;
 
 pub use keys::{KeyBinding, KeyBindings};
 pub use scroll::ScrollState;
-pub use size::{measure_text, wrap_text};
+pub use size::{measure_text, truncate_text, wrap_text};
 pub use worker::{ManagedWorker, WorkerHandle};